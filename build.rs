@@ -0,0 +1,26 @@
+//! Records the version of jlrs the app is built against, for the About dialog.
+use std::path::Path;
+
+fn main() {
+    let lock = Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.lock");
+    println!("cargo:rerun-if-changed={}", lock.display());
+
+    let version = std::fs::read_to_string(&lock)
+        .ok()
+        .and_then(|lock| locked_version(&lock, "jlrs"))
+        .unwrap_or_else(|| String::from("unknown"));
+    println!("cargo:rustc-env=JLRS_VERSION={version}");
+}
+
+/// Version of the package `name` in the contents of a `Cargo.lock`.
+fn locked_version(lock: &str, name: &str) -> Option<String> {
+    let name_line = format!("name = \"{name}\"");
+    let mut lines = lock.lines().map(str::trim);
+
+    lines.find(|line| *line == name_line)?;
+    lines
+        .next()?
+        .strip_prefix("version = \"")?
+        .strip_suffix('"')
+        .map(String::from)
+}
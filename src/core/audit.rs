@@ -0,0 +1,158 @@
+use serde::Serialize;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::core::stmimage::STMImage;
+use crate::core::task::{Task, TaskState};
+
+/// Default location of the acquisition log, relative to the working directory.
+pub const DEFAULT_AUDIT_LOG: &str = "acquisitions.jsonl";
+
+/// Items of a task the acquisition log can record.
+pub trait Audited {
+    /// What is logged of an item. Leaves out measured data, which would add
+    /// the whole scan to every line of the log.
+    type Parameters: Serialize;
+
+    fn parameters(&self) -> Self::Parameters;
+}
+
+impl Audited for STMImage {
+    type Parameters = STMImage;
+
+    fn parameters(&self) -> STMImage {
+        STMImage::parameters(self)
+    }
+}
+
+/// One line of the acquisition log, written whenever a task changes state.
+#[derive(Serialize)]
+pub struct AuditRecord<'a, P> {
+    /// Seconds since the UNIX epoch.
+    pub timestamp: f64,
+    pub task: usize,
+    pub description: &'a str,
+    /// The state entered; a `Failed` state carries its error message.
+    pub state: &'a TaskState,
+    pub parameters: Vec<P>,
+    pub notes: &'a str,
+}
+
+impl<'a, P> AuditRecord<'a, P> {
+    pub fn new<T>(task: &'a Task<T>) -> Self
+    where
+        T: Audited<Parameters = P>,
+    {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0.0, |d| d.as_secs_f64());
+
+        Self {
+            timestamp,
+            task: task.index(),
+            description: task.description(),
+            state: task.status(),
+            parameters: task.content().iter().map(T::parameters).collect(),
+            notes: task.notes(),
+        }
+    }
+}
+
+/// Append-only JSON Lines log of every acquisition, kept across sessions.
+pub struct AuditLog {
+    path: PathBuf,
+}
+
+impl Default for AuditLog {
+    fn default() -> Self {
+        Self::new(PathBuf::from(DEFAULT_AUDIT_LOG))
+    }
+}
+
+impl AuditLog {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    /// Appends the current state of `task` as a single JSON line.
+    pub fn record<T>(&self, task: &Task<T>) -> std::io::Result<()>
+    where
+        T: Audited,
+    {
+        let mut line = serde_json::to_string(&AuditRecord::new(task))?;
+        line.push('\n');
+
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?
+            .write_all(line.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::stmimage::Measurement;
+    use serde_json::json;
+
+    fn image() -> STMImage {
+        STMImage::new(2, 1e-8, 0.0, 0.0, 0.0, 0.1, 0.5, 1e-10, None)
+    }
+
+    #[test]
+    fn state_changes_append_lines_without_measured_data() {
+        let path = std::env::temp_dir().join("stm_controller_audit.jsonl");
+        let _ = std::fs::remove_file(&path);
+        let log = AuditLog::new(path.clone());
+        let mut scanned = Task::new(vec![image()], String::from("survey"), 0);
+        let mut failed = Task::new(vec![image()], String::from("detail"), 1);
+
+        scanned.state(TaskState::Running);
+        log.record(&scanned).unwrap();
+        scanned.content_mut()[0].set_measurement(Measurement {
+            data: vec![1.0; 4],
+            spectroscopy: vec![],
+        });
+        scanned.state(TaskState::Completed);
+        log.record(&scanned).unwrap();
+        failed.state(TaskState::Running);
+        log.record(&failed).unwrap();
+        failed.state(TaskState::Failed(String::from("tip crashed")));
+        log.record(&failed).unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let records: Vec<serde_json::Value> = contents
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        let states: Vec<_> = records
+            .iter()
+            .map(|record| json!([record["task"], record["state"]]))
+            .collect();
+        assert_eq!(
+            json!(states),
+            json!([
+                [0, "Running"],
+                [0, "Completed"],
+                [1, "Running"],
+                [1, { "Failed": "tip crashed" }],
+            ])
+        );
+        assert_eq!(records[1]["description"], "survey");
+        assert_eq!(records[1]["parameters"][0]["bias"], 0.5);
+        assert_eq!(records[1]["parameters"][0]["lines"], 2);
+        assert!(records[1]["parameters"][0]["data"].is_null());
+        assert!(records
+            .windows(2)
+            .all(|pair| pair[0]["timestamp"].as_f64() <= pair[1]["timestamp"].as_f64()));
+    }
+}
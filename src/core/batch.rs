@@ -0,0 +1,256 @@
+//! Import a batch of scans from a CSV or JSON file.
+//!
+//! Every row describes one image, in SI units, and becomes its own task. CSV
+//! files need a header row naming the columns; JSON files hold an array of
+//! objects with the same keys:
+//!
+//! | column      | required    | unit |
+//! |-------------|-------------|------|
+//! | `name`      | no          |      |
+//! | `lines`     | yes         |      |
+//! | `size`      | yes         | m    |
+//! | `x_offset`  | no (0)      | m    |
+//! | `y_offset`  | no (0)      | m    |
+//! | `rotation`  | no (0)      | °    |
+//! | `line_time` | yes         | s    |
+//! | `bias`      | yes         | V    |
+//! | `set_point` | no (100 pA) | A    |
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::core::hardware;
+use crate::core::stmimage::{STMImage, DEFAULT_SET_POINT};
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct BatchRow {
+    #[serde(default)]
+    pub name: Option<String>,
+    pub lines: u32,
+    pub size: f64,
+    #[serde(default)]
+    pub x_offset: f64,
+    #[serde(default)]
+    pub y_offset: f64,
+    #[serde(default)]
+    pub rotation: f64,
+    pub line_time: f64,
+    pub bias: f64,
+    #[serde(default)]
+    pub set_point: Option<f64>,
+}
+
+impl BatchRow {
+    /// Validates the row against the hardware limits.
+    pub fn to_image(&self) -> Result<STMImage, String> {
+        if !hardware::LINES.contains(&self.lines) {
+            return Err(format!("unsupported line count {}", self.lines));
+        }
+
+        Ok(STMImage::new(
+            self.lines,
+            hardware::check("size", self.size, hardware::SIZE)?,
+            hardware::check("x_offset", self.x_offset, hardware::OFFSET)?,
+            hardware::check("y_offset", self.y_offset, hardware::OFFSET)?,
+            hardware::check("rotation", self.rotation, hardware::ROTATION)?,
+            hardware::check("line_time", self.line_time, hardware::LINE_TIME)?,
+            hardware::check("bias", self.bias, hardware::BIAS)?,
+            hardware::check(
+                "set_point",
+                self.set_point.unwrap_or(DEFAULT_SET_POINT),
+                hardware::SET_POINT,
+            )?,
+            None,
+        ))
+    }
+}
+
+/// A row that was left out of the import, numbered from 1 (after the header).
+#[derive(Debug, Clone)]
+pub struct SkippedRow {
+    pub row: usize,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BatchImport {
+    /// Task name and image for every valid row, in file order.
+    pub tasks: Vec<(String, STMImage)>,
+    pub skipped: Vec<SkippedRow>,
+}
+
+impl BatchImport {
+    /// Reads a batch file, choosing the format from its extension (`.json`,
+    /// anything else is treated as CSV).
+    pub fn from_file(path: &Path) -> Result<Self, String> {
+        let contents = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+
+        let rows = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => parse_json(&contents)?,
+            _ => parse_csv(&contents)?,
+        };
+
+        Ok(Self::from_rows(rows))
+    }
+
+    pub fn from_rows(rows: Vec<Result<BatchRow, String>>) -> Self {
+        let mut import = Self::default();
+
+        for (i, row) in rows.into_iter().enumerate() {
+            let row_number = i + 1;
+            match row.and_then(|row| row.to_image().map(|image| (row.name, image))) {
+                Ok((name, image)) => {
+                    let name = name.unwrap_or_else(|| format!("Batch row {row_number}"));
+                    import.tasks.push((name, image));
+                }
+                Err(reason) => import.skipped.push(SkippedRow {
+                    row: row_number,
+                    reason,
+                }),
+            }
+        }
+
+        import
+    }
+}
+
+pub fn parse_json(contents: &str) -> Result<Vec<Result<BatchRow, String>>, String> {
+    let values: Vec<serde_json::Value> =
+        serde_json::from_str(contents).map_err(|e| e.to_string())?;
+
+    Ok(values
+        .into_iter()
+        .map(|value| serde_json::from_value(value).map_err(|e| e.to_string()))
+        .collect())
+}
+
+pub fn parse_csv(contents: &str) -> Result<Vec<Result<BatchRow, String>>, String> {
+    let mut lines = contents.lines().filter(|line| !line.trim().is_empty());
+    let header: Vec<&str> = lines
+        .next()
+        .ok_or_else(|| String::from("batch file is empty"))?
+        .split(',')
+        .map(str::trim)
+        .collect();
+
+    for column in ["lines", "size", "line_time", "bias"] {
+        if !header.contains(&column) {
+            return Err(format!("missing required column \"{column}\""));
+        }
+    }
+
+    Ok(lines
+        .map(|line| {
+            let fields: HashMap<&str, &str> = header
+                .iter()
+                .copied()
+                .zip(line.split(',').map(str::trim))
+                .collect();
+            parse_csv_row(&fields)
+        })
+        .collect())
+}
+
+fn parse_csv_row(fields: &HashMap<&str, &str>) -> Result<BatchRow, String> {
+    let number = |column: &str, default: Option<f64>| -> Result<f64, String> {
+        match fields.get(column).filter(|field| !field.is_empty()) {
+            Some(field) => field
+                .parse::<f64>()
+                .map_err(|_| format!("{column} \"{field}\" is not a number")),
+            None => default.ok_or_else(|| format!("missing {column}")),
+        }
+    };
+
+    let lines = fields
+        .get("lines")
+        .ok_or_else(|| String::from("missing lines"))?
+        .parse::<u32>()
+        .map_err(|_| String::from("lines is not a whole number"))?;
+
+    Ok(BatchRow {
+        name: fields
+            .get("name")
+            .filter(|name| !name.is_empty())
+            .map(|name| name.to_string()),
+        lines,
+        size: number("size", None)?,
+        x_offset: number("x_offset", Some(0.0))?,
+        y_offset: number("y_offset", Some(0.0))?,
+        rotation: number("rotation", Some(0.0))?,
+        line_time: number("line_time", None)?,
+        bias: number("bias", None)?,
+        set_point: Some(number("set_point", Some(DEFAULT_SET_POINT))?),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_rows_are_imported_in_file_order() {
+        let csv = "name, lines, size, line_time, bias, set_point\n\
+                   survey, 256, 1e-6, 0.5, 1.0,\n\
+                   , 64, 5e-8, 0.2, -0.3, 2e-10\n";
+
+        let import = BatchImport::from_rows(parse_csv(csv).unwrap());
+
+        assert!(import.skipped.is_empty());
+        let names: Vec<_> = import.tasks.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["survey", "Batch row 2"]);
+        let (_, detail) = &import.tasks[1];
+        assert_eq!(detail.lines(), 64);
+        assert_eq!(detail.bias(), -0.3);
+        assert_eq!(detail.set_point(), 2e-10);
+    }
+
+    #[test]
+    fn invalid_csv_rows_are_skipped_with_the_reason() {
+        let csv = "lines,size,line_time,bias\n\
+                   64,1e-7,0.2,0.5\n\
+                   100,1e-7,0.2,0.5\n\
+                   64,1e-7,0.2,high\n\
+                   64,1e-7,0.2,9\n";
+
+        let import = BatchImport::from_rows(parse_csv(csv).unwrap());
+
+        assert_eq!(import.tasks.len(), 1);
+        let skipped: Vec<_> = import
+            .skipped
+            .iter()
+            .map(|skipped| (skipped.row, skipped.reason.as_str()))
+            .collect();
+        assert_eq!(
+            skipped,
+            [
+                (2, "unsupported line count 100"),
+                (3, "bias \"high\" is not a number"),
+                (4, "bias 9 outside [-5, 5]"),
+            ]
+        );
+    }
+
+    #[test]
+    fn csv_without_a_required_column_is_refused() {
+        assert_eq!(
+            parse_csv("lines,size,bias\n64,1e-7,0.5\n").unwrap_err(),
+            "missing required column \"line_time\""
+        );
+    }
+
+    #[test]
+    fn json_rows_are_imported_and_invalid_ones_skipped() {
+        let json = r#"[
+            {"name": "survey", "lines": 256, "size": 1e-6, "line_time": 0.5, "bias": 1.0},
+            {"lines": 64, "size": 1e-7, "line_time": 0.2, "bias": "high"}
+        ]"#;
+
+        let import = BatchImport::from_rows(parse_json(json).unwrap());
+
+        assert_eq!(import.tasks.len(), 1);
+        assert_eq!(import.tasks[0].0, "survey");
+        assert_eq!(import.tasks[0].1.set_point(), DEFAULT_SET_POINT);
+        assert_eq!(import.skipped.len(), 1);
+        assert_eq!(import.skipped[0].row, 2);
+    }
+}
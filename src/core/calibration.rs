@@ -0,0 +1,112 @@
+//! Conversion from the physical units shown in the UI to instrument units.
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Default hardware profile, relative to the working directory.
+pub const DEFAULT_PROFILE: &str = "hardware_profile.json";
+
+/// A linear map `value * scale + offset`; the scale is applied first.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Linear {
+    pub scale: f64,
+    pub offset: f64,
+}
+
+impl Default for Linear {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            offset: 0.0,
+        }
+    }
+}
+
+impl Linear {
+    pub fn apply(&self, value: f64) -> f64 {
+        value * self.scale + self.offset
+    }
+}
+
+/// Per-parameter calibration of an instrument, applied only when an image is
+/// dispatched so the UI keeps showing physical values. Parameters missing
+/// from the profile are passed through unchanged.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Calibration {
+    pub size: Linear,
+    pub x_offset: Linear,
+    pub y_offset: Linear,
+    pub bias: Linear,
+}
+
+impl Calibration {
+    /// Reads the `calibration` section of a JSON hardware profile.
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        #[derive(Deserialize)]
+        struct Profile {
+            #[serde(default)]
+            calibration: Calibration,
+        }
+
+        let json = std::fs::read_to_string(path)?;
+        let profile: Profile = serde_json::from_str(&json)?;
+        Ok(profile.calibration)
+    }
+
+    /// Loads [`DEFAULT_PROFILE`], see [`Calibration::load_or_default`].
+    pub fn from_default_profile() -> std::io::Result<Self> {
+        Self::load_or_default(Path::new(DEFAULT_PROFILE))
+    }
+
+    /// Reads the profile at `path` like [`Calibration::load`], with the
+    /// identity calibration when there is no profile. A profile that exists
+    /// but cannot be read or parsed is an error rather than silently ignored.
+    pub fn load_or_default(path: &Path) -> std::io::Result<Self> {
+        match Self::load(path) {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            result => result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::stmimage::STMImage;
+
+    #[test]
+    fn a_missing_profile_is_the_identity() {
+        let path = std::env::temp_dir().join("stm_controller_no_such_profile.json");
+        let calibration = Calibration::load_or_default(&path).unwrap();
+
+        assert_eq!(calibration, Calibration::default());
+    }
+
+    #[test]
+    fn a_malformed_profile_is_an_error() {
+        let path = std::env::temp_dir().join("stm_controller_malformed_profile.json");
+        std::fs::write(&path, r#"{ "calibration": { "bias": "#).unwrap();
+        let result = Calibration::load_or_default(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn calibration_changes_the_dispatched_image_only() {
+        let calibration = Calibration {
+            bias: Linear {
+                scale: 2.0,
+                offset: 0.5,
+            },
+            ..Calibration::default()
+        };
+        let image = STMImage::new(256, 1e-7, 0.0, 0.0, 0.0, 0.1, 1.0, 1e-10, None);
+
+        let dispatched = image.calibrated(&calibration);
+
+        assert_eq!(dispatched.bias(), 2.5);
+        assert_eq!(dispatched.size(), 1e-7);
+        assert_eq!(image.bias(), 1.0);
+    }
+}
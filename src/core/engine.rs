@@ -0,0 +1,132 @@
+//! Queue execution shared by the window and the headless mode.
+use crate::core::runner::TaskRunner;
+use crate::core::task::{Task, TaskList, TaskState};
+
+/// Runs every item of `task` in order on `runner`, handing `on_item` the task,
+/// the position of each item and its result, e.g. to store the result on the
+/// item. Stops at the first item that fails.
+pub fn run_task<T, R>(
+    runner: &mut R,
+    task: &mut Task<T>,
+    mut on_item: impl FnMut(&mut Task<T>, usize, R::Output),
+) -> Result<(), R::Error>
+where
+    T: Clone,
+    R: TaskRunner<T>,
+{
+    for i in 0..task.content().len() {
+        let output = runner.run(task.content()[i].clone())?;
+        on_item(task, i, output);
+    }
+
+    Ok(())
+}
+
+/// Runs every idle task from the current one to the end of the queue.
+///
+/// Each task is marked running, then completed or failed with the runner's
+/// error; a failed task does not stop the queue. `on_state` sees the task after
+/// every state change, e.g. to log or export it, and `on_item` gets the result
+/// of every finished item, see [`run_task`].
+pub fn run_queue<T, R>(
+    list: &mut TaskList<T>,
+    runner: &mut R,
+    mut on_state: impl FnMut(&Task<T>),
+    mut on_item: impl FnMut(&mut Task<T>, usize, R::Output),
+) where
+    T: Clone,
+    R: TaskRunner<T>,
+{
+    let start = list.current_task.unwrap_or(0);
+
+    for idx in start..list.tasks.len() {
+        if !list.tasks[idx].is_idle() {
+            continue;
+        }
+        list.current_task = Some(idx);
+
+        list.tasks[idx].state(TaskState::Running);
+        on_state(&list.tasks[idx]);
+
+        let state = match run_task(runner, &mut list.tasks[idx], &mut on_item) {
+            Ok(()) => TaskState::Completed,
+            Err(e) => TaskState::Failed(e.to_string()),
+        };
+
+        list.tasks[idx].state(state);
+        on_state(&list.tasks[idx]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::task::InsertPosition;
+
+    /// Doubles every item, failing on negative ones.
+    struct Doubler;
+
+    impl TaskRunner<f64> for Doubler {
+        type Output = f64;
+        type Error = String;
+
+        fn run(&mut self, item: f64) -> Result<f64, String> {
+            if item < 0.0 {
+                return Err(format!("{} is negative", item));
+            }
+            Ok(2.0 * item)
+        }
+    }
+
+    fn queue(contents: Vec<Vec<f64>>) -> TaskList<f64> {
+        let mut list = TaskList::default();
+        for (i, content) in contents.into_iter().enumerate() {
+            list.insert(
+                Task::new(content, format!("task {}", i), 0),
+                InsertPosition::End,
+            );
+        }
+        list
+    }
+
+    #[test]
+    fn results_are_handed_over_to_be_stored() {
+        let mut list = queue(vec![vec![1.0, 2.0], vec![3.0]]);
+
+        run_queue(
+            &mut list,
+            &mut Doubler,
+            |_| {},
+            |task, item, output| task.content_mut()[item] = output,
+        );
+
+        assert_eq!(list.tasks[0].content(), &vec![2.0, 4.0]);
+        assert_eq!(list.tasks[1].content(), &vec![6.0]);
+        assert!(list
+            .tasks
+            .iter()
+            .all(|task| matches!(task.status(), TaskState::Completed)));
+    }
+
+    #[test]
+    fn failed_task_does_not_stop_the_queue() {
+        let mut list = queue(vec![vec![-1.0, 1.0], vec![3.0]]);
+        let mut states = vec![];
+
+        run_queue(
+            &mut list,
+            &mut Doubler,
+            |task| states.push((task.index(), task.status().clone())),
+            |task, item, output| task.content_mut()[item] = output,
+        );
+
+        assert!(matches!(&states[..], [
+            (0, TaskState::Running),
+            (0, TaskState::Failed(e)),
+            (1, TaskState::Running),
+            (1, TaskState::Completed),
+        ] if e == "-1 is negative"));
+        assert_eq!(list.tasks[0].content(), &vec![-1.0, 1.0]);
+        assert_eq!(list.tasks[1].content(), &vec![6.0]);
+    }
+}
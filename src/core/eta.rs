@@ -0,0 +1,221 @@
+use chrono::{DateTime, Duration, TimeZone};
+use std::time::Instant;
+
+/// Longest duration [`format_duration`] spells out, in days.
+const MAX_DAYS: f64 = 999.0;
+/// Shown for a duration that cannot be estimated yet.
+pub const UNKNOWN_DURATION: &str = "--:--:--";
+/// Shown for a finish time that cannot be estimated yet.
+pub const UNKNOWN_CLOCK: &str = "done ~--:--";
+
+/// Weight of the newest line duration in the moving average.
+const SMOOTHING: f64 = 0.2;
+/// Line durations needed before the measured rate replaces the nominal one.
+const MIN_SAMPLES: u32 = 5;
+
+/// Estimates the real time per scan line from line-completion timestamps.
+///
+/// Durations between consecutive lines are smoothed with an exponential moving
+/// average, `ema = SMOOTHING * dt + (1 - SMOOTHING) * ema`, seeded with the
+/// first duration. Until `MIN_SAMPLES` durations have been seen the nominal
+/// line time is used instead.
+///
+/// When the runtime reports a garbage collection pause the line in progress is
+/// flagged as stalled; its duration is dropped instead of being averaged in,
+/// so a GC pause does not read as a slow line.
+#[derive(Debug, Clone, Default)]
+pub struct LineRateEstimator {
+    /// Lines recorded since the last reset.
+    lines: u32,
+    last_line: Option<Instant>,
+    average: Option<f64>,
+    samples: u32,
+    stalled: bool,
+}
+
+impl LineRateEstimator {
+    /// Forgets all measurements, e.g. when a new task starts.
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Records that a line finished at `at`.
+    pub fn record_line(&mut self, at: Instant) {
+        self.lines += 1;
+        if std::mem::take(&mut self.stalled) {
+            self.last_line = Some(at);
+            return;
+        }
+
+        if let Some(last) = self.last_line {
+            let dt = at.saturating_duration_since(last).as_secs_f64();
+            self.average = Some(match self.average {
+                Some(average) => SMOOTHING * dt + (1.0 - SMOOTHING) * average,
+                None => dt,
+            });
+            self.samples += 1;
+        }
+        self.last_line = Some(at);
+    }
+
+    /// Flags the line in progress as interrupted by a runtime stall, such as a
+    /// Julia GC pause. The interval ending at the next `record_line` is skipped.
+    pub fn flag_stall(&mut self) {
+        self.stalled = true;
+    }
+
+    /// Lines recorded since the last reset, stalled ones included.
+    pub fn lines(&self) -> u32 {
+        self.lines
+    }
+
+    /// Seconds per line, measured once enough lines were timed.
+    pub fn seconds_per_line(&self, nominal: f64) -> f64 {
+        match self.average {
+            Some(average) if self.samples >= MIN_SAMPLES => average,
+            _ => nominal,
+        }
+    }
+
+    /// Seconds left to scan `lines_left` lines.
+    pub fn remaining(&self, lines_left: u32, nominal: f64) -> f64 {
+        lines_left as f64 * self.seconds_per_line(nominal)
+    }
+}
+
+/// Formats `secs` as `hh:mm:ss`, or `dd:hh:mm:ss` from one day on. Partial
+/// seconds are dropped. Durations beyond [`MAX_DAYS`] read as `">999 days"`,
+/// and negative or non-finite ones as [`UNKNOWN_DURATION`].
+pub fn format_duration(secs: f64) -> String {
+    if !secs.is_finite() || secs < 0.0 {
+        return String::from(UNKNOWN_DURATION);
+    }
+
+    let days = (secs / 86400.0).floor();
+    if days > MAX_DAYS {
+        return format!(">{MAX_DAYS} days");
+    }
+
+    let secs = secs.floor() as u64 % 86400;
+    let (hrs, mins, secs) = (secs / 3600, secs / 60 % 60, secs % 60);
+
+    if days > 0.0 {
+        format!("{:02}:{:02}:{:02}:{:02}", days, hrs, mins, secs)
+    } else {
+        format!("{:02}:{:02}:{:02}", hrs, mins, secs)
+    }
+}
+
+/// Formats the wall time `secs` after `now`, e.g. `"done ~14:32"`. A finish
+/// on a later day also names the weekday, as in `"done ~Tue 09:05"`. Negative
+/// or non-finite durations, and ones past the calendar's range, read as
+/// [`UNKNOWN_CLOCK`].
+pub fn format_finish_clock<Tz: TimeZone>(now: DateTime<Tz>, secs: f64) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    if !secs.is_finite() || secs < 0.0 {
+        return String::from(UNKNOWN_CLOCK);
+    }
+
+    let Some(done) = now
+        .clone()
+        .checked_add_signed(Duration::milliseconds((secs * 1000.0) as i64))
+    else {
+        return String::from(UNKNOWN_CLOCK);
+    };
+
+    if done.date_naive() == now.date_naive() {
+        format!("done ~{}", done.format("%H:%M"))
+    } else {
+        format!("done ~{}", done.format("%a %H:%M"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An estimator that timed lines `intervals` seconds apart.
+    fn timed(intervals: &[f64]) -> LineRateEstimator {
+        let mut estimator = LineRateEstimator::default();
+        let mut at = Instant::now();
+        estimator.record_line(at);
+        for &dt in intervals {
+            at += std::time::Duration::from_secs_f64(dt);
+            estimator.record_line(at);
+        }
+        estimator
+    }
+
+    #[test]
+    fn nominal_line_time_is_used_until_enough_lines_are_timed() {
+        assert_eq!(timed(&[]).seconds_per_line(3.0), 3.0);
+        assert_eq!(timed(&[1.0; 4]).seconds_per_line(3.0), 3.0);
+        assert!((timed(&[1.0; 5]).seconds_per_line(3.0) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn line_durations_are_smoothed_exponentially() {
+        let estimator = timed(&[1.0, 1.0, 1.0, 1.0, 1.0, 2.0]);
+        // 0.2 * 2 s + 0.8 * 1 s
+        assert!((estimator.seconds_per_line(3.0) - 1.2).abs() < 1e-9);
+
+        let estimator = timed(&[1.0, 1.0, 1.0, 1.0, 1.0, 2.0, 2.0]);
+        // 0.2 * 2 s + 0.8 * 1.2 s
+        assert!((estimator.seconds_per_line(3.0) - 1.36).abs() < 1e-9);
+        assert!((estimator.remaining(10, 3.0) - 13.6).abs() < 1e-9);
+    }
+
+    #[test]
+    fn stalled_line_is_left_out_of_the_average() {
+        let mut estimator = timed(&[1.0; 5]);
+        let mut at = Instant::now() + std::time::Duration::from_secs(5);
+        estimator.flag_stall();
+        at += std::time::Duration::from_secs(30);
+        estimator.record_line(at);
+
+        assert_eq!(estimator.lines(), 7);
+        assert!((estimator.seconds_per_line(3.0) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn durations_under_a_day_read_as_hours() {
+        assert_eq!(format_duration(0.0), "00:00:00");
+        assert_eq!(format_duration(90.0), "00:01:30");
+        assert_eq!(format_duration(86399.9), "23:59:59");
+    }
+
+    #[test]
+    fn durations_from_a_day_include_days() {
+        assert_eq!(format_duration(86400.0), "01:00:00:00");
+        assert_eq!(format_duration(1e7), "115:17:46:40");
+    }
+
+    #[test]
+    fn durations_out_of_reach_are_not_spelled_out() {
+        assert_eq!(format_duration(1000.0 * 86400.0), ">999 days");
+        assert_eq!(format_duration(-1.0), UNKNOWN_DURATION);
+        assert_eq!(format_duration(f64::NAN), UNKNOWN_DURATION);
+        assert_eq!(format_duration(f64::INFINITY), UNKNOWN_DURATION);
+    }
+
+    #[test]
+    fn finish_clock_shows_the_local_time_done_by() {
+        // A Tuesday afternoon
+        let now = chrono::Utc.with_ymd_and_hms(2023, 5, 2, 14, 0, 0).unwrap();
+
+        assert_eq!(format_finish_clock(now, 32.0 * 60.0), "done ~14:32");
+        let tomorrow = (19.0 * 60.0 + 5.0) * 60.0;
+        assert_eq!(format_finish_clock(now, tomorrow), "done ~Wed 09:05");
+    }
+
+    #[test]
+    fn finish_clock_without_an_estimate_is_unknown() {
+        let now = chrono::Utc.with_ymd_and_hms(2023, 5, 2, 14, 0, 0).unwrap();
+
+        assert_eq!(format_finish_clock(now, -1.0), UNKNOWN_CLOCK);
+        assert_eq!(format_finish_clock(now, f64::NAN), UNKNOWN_CLOCK);
+        assert_eq!(format_finish_clock(now, 1e20), UNKNOWN_CLOCK);
+    }
+}
@@ -0,0 +1,228 @@
+//! Writing the results of a completed task to disk.
+//!
+//! Each task names the [`OutputFormat`] it wants. When it completes,
+//! [`export_task`] asks an [`Exporter`] for one file per image and format,
+//! named `<description>_<image>.<extension>` in the output directory.
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::core::stmimage::STMImage;
+use crate::core::task::Task;
+use crate::native::image_plot::Colormap;
+
+/// File formats a task can be exported to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum OutputFormat {
+    /// Grayscale image of the height data.
+    #[default]
+    Png,
+    /// Comma-separated values.
+    Csv,
+    /// Raw little-endian `f64` height data.
+    Raw,
+    /// Every format above.
+    All,
+}
+
+impl OutputFormat {
+    pub const ALL: [OutputFormat; 4] = [
+        OutputFormat::Png,
+        OutputFormat::Csv,
+        OutputFormat::Raw,
+        OutputFormat::All,
+    ];
+
+    /// The single formats this choice stands for.
+    pub fn formats(&self) -> &'static [OutputFormat] {
+        match self {
+            OutputFormat::Png => &[OutputFormat::Png],
+            OutputFormat::Csv => &[OutputFormat::Csv],
+            OutputFormat::Raw => &[OutputFormat::Raw],
+            OutputFormat::All => &[OutputFormat::Png, OutputFormat::Csv, OutputFormat::Raw],
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Png => "png",
+            OutputFormat::Csv => "csv",
+            OutputFormat::Raw | OutputFormat::All => "bin",
+        }
+    }
+}
+
+impl std::fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            OutputFormat::Png => "PNG",
+            OutputFormat::Csv => "CSV",
+            OutputFormat::Raw => "Raw binary",
+            OutputFormat::All => "All formats",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Something that writes a single item of a task in a single format.
+pub trait Exporter<T> {
+    type Error;
+
+    fn write(&mut self, item: &T, format: OutputFormat, path: &Path) -> Result<(), Self::Error>;
+}
+
+/// Exports every item of `task` into `dir` in the task's output format,
+/// returning the paths written.
+pub fn export_task<T, E>(
+    exporter: &mut E,
+    task: &Task<T>,
+    dir: &Path,
+) -> Result<Vec<PathBuf>, E::Error>
+where
+    E: Exporter<T>,
+{
+    let mut written = vec![];
+
+    for (i, item) in task.content().iter().enumerate() {
+        for format in task.output_format().formats() {
+            let path = dir.join(format!(
+                "{}_{}.{}",
+                task.description(),
+                i,
+                format.extension()
+            ));
+            exporter.write(item, *format, &path)?;
+            written.push(path);
+        }
+    }
+
+    Ok(written)
+}
+
+/// Writes image files to disk.
+///
+/// PNG files show the height data of scanned images in `colormap`, CSV files
+/// hold the scan parameters in the batch-import layout and raw files hold the
+/// height data as little-endian `f64`, row by row.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FileExporter {
+    pub colormap: Colormap,
+}
+
+impl Exporter<STMImage> for FileExporter {
+    type Error = io::Error;
+
+    fn write(&mut self, image: &STMImage, format: OutputFormat, path: &Path) -> io::Result<()> {
+        match format {
+            OutputFormat::Csv => std::fs::write(
+                path,
+                format!(
+                    "lines,size,x_offset,y_offset,rotation,line_time,bias,set_point\n{},{},{},{},{},{},{},{}\n",
+                    image.lines(),
+                    image.size(),
+                    image.x_offset(),
+                    image.y_offset(),
+                    image.rotation(),
+                    image.line_time(),
+                    image.bias(),
+                    image.set_point(),
+                ),
+            ),
+            OutputFormat::Png => image.export_png(path, self.colormap),
+            OutputFormat::Raw => {
+                let data = image.data().ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidInput, "image has no data to write")
+                })?;
+                let bytes: Vec<u8> = data.iter().flat_map(|z| z.to_le_bytes()).collect();
+                std::fs::write(path, bytes)
+            }
+            OutputFormat::All => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "write each of the formats of All to its own file",
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Records what it was asked to write instead of writing it.
+    #[derive(Default)]
+    struct RecordingExporter {
+        written: Vec<(f64, OutputFormat, PathBuf)>,
+    }
+
+    impl Exporter<f64> for RecordingExporter {
+        type Error = ();
+
+        fn write(&mut self, item: &f64, format: OutputFormat, path: &Path) -> Result<(), ()> {
+            self.written.push((*item, format, path.to_path_buf()));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn task_format_decides_the_files_written() {
+        let mut task = Task::new(vec![1.0, 2.0], String::from("scan"), 0);
+        task.set_output_format(OutputFormat::Csv);
+        let mut exporter = RecordingExporter::default();
+
+        let paths = export_task(&mut exporter, &task, Path::new("out")).unwrap();
+
+        assert_eq!(
+            paths,
+            [Path::new("out/scan_0.csv"), Path::new("out/scan_1.csv")]
+        );
+        assert_eq!(
+            exporter.written,
+            [
+                (1.0, OutputFormat::Csv, PathBuf::from("out/scan_0.csv")),
+                (2.0, OutputFormat::Csv, PathBuf::from("out/scan_1.csv")),
+            ]
+        );
+    }
+
+    #[test]
+    fn all_formats_write_one_file_each() {
+        let mut task = Task::new(vec![1.0], String::from("scan"), 0);
+        task.set_output_format(OutputFormat::All);
+        let mut exporter = RecordingExporter::default();
+
+        let paths = export_task(&mut exporter, &task, Path::new("out")).unwrap();
+
+        let formats: Vec<_> = exporter.written.iter().map(|(_, f, _)| *f).collect();
+        assert_eq!(
+            formats,
+            [OutputFormat::Png, OutputFormat::Csv, OutputFormat::Raw]
+        );
+        assert_eq!(
+            paths,
+            [
+                Path::new("out/scan_0.png"),
+                Path::new("out/scan_0.csv"),
+                Path::new("out/scan_0.bin"),
+            ]
+        );
+    }
+
+    #[test]
+    fn raw_files_hold_the_height_data() {
+        let mut image = STMImage::new(2, 1e-8, 0.0, 0.0, 0.0, 0.1, 0.5, 1e-10, None);
+        let path = std::env::temp_dir().join("stm_controller_raw_export.bin");
+        let mut exporter = FileExporter::default();
+        assert!(exporter.write(&image, OutputFormat::Raw, &path).is_err());
+
+        image.set_data(vec![1.0, -2.0, 0.5, 3e-9]);
+        exporter.write(&image, OutputFormat::Raw, &path).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let data: Vec<f64> = bytes
+            .chunks(8)
+            .map(|chunk| f64::from_le_bytes(chunk.try_into().unwrap()))
+            .collect();
+        assert_eq!(data, [1.0, -2.0, 0.5, 3e-9]);
+    }
+}
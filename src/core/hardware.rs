@@ -0,0 +1,19 @@
+//! Parameter ranges accepted by the R9 controller, in SI units.
+
+pub const LINES: [u32; 10] = [8, 16, 32, 64, 128, 256, 512, 1024, 2048, 4096];
+pub const SIZE: (f64, f64) = (210e-12, 2.1e-6);
+pub const OFFSET: (f64, f64) = (-1.05e-6, 1.05e-6);
+pub const ROTATION: (f64, f64) = (0.0, 360.0);
+pub const LINE_TIME: (f64, f64) = (102.4e-3, 100.0);
+pub const BIAS: (f64, f64) = (-5.0, 5.0);
+pub const SET_POINT: (f64, f64) = (1e-12, 10e-9);
+
+/// Checks that `value` lies within `(lower, upper)`, naming the parameter in
+/// the error otherwise.
+pub fn check(name: &str, value: f64, (lower, upper): (f64, f64)) -> Result<f64, String> {
+    if (lower..=upper).contains(&value) {
+        Ok(value)
+    } else {
+        Err(format!("{name} {value} outside [{lower}, {upper}]"))
+    }
+}
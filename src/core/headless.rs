@@ -0,0 +1,92 @@
+//! Running a saved queue without a window, for `stm_controller --run`.
+use crate::core::engine;
+use crate::core::runner::TaskRunner;
+use crate::core::stmimage::{Measurement, STMImage};
+use crate::core::task::{Task, TaskList, TaskState};
+use std::cell::RefCell;
+use std::io::Write;
+
+/// Runs every idle task of `tasklist` on `runner`, storing what was measured on
+/// each image and writing progress and a summary of the queue to `out`.
+/// `on_state` sees the task after every state change, as in
+/// [`engine::run_queue`], so a completed task can be exported from there.
+/// Fails if any task failed.
+pub fn run<R, W>(
+    tasklist: &mut TaskList<STMImage>,
+    runner: &mut R,
+    out: &mut W,
+    mut on_state: impl FnMut(&Task<STMImage>),
+) -> Result<(), String>
+where
+    R: TaskRunner<STMImage, Output = Measurement>,
+    W: Write,
+{
+    // Both callbacks of the engine print, so the writer and its first error
+    // are shared between them.
+    let written = RefCell::new((out, Ok(())));
+    let print = |line: String| {
+        let mut written = written.borrow_mut();
+        let (out, result) = &mut *written;
+        if result.is_ok() {
+            *result = writeln!(out, "{}", line);
+        }
+    };
+
+    engine::run_queue(
+        tasklist,
+        runner,
+        |task| {
+            print(format!(
+                "[{}] {}: {:?}",
+                task.index(),
+                task.description(),
+                task.status()
+            ));
+            on_state(task);
+        },
+        |task, item, output| {
+            print(format!(
+                "[{}] image {}/{} ({}): {} points",
+                task.index(),
+                item + 1,
+                task.content().len(),
+                task.content()[item].label(),
+                output.data.len()
+            ));
+            task.content_mut()[item].set_measurement(output);
+        },
+    );
+
+    let count = |state: fn(&Task<STMImage>) -> bool| {
+        tasklist.tasks.iter().filter(|&task| state(task)).count()
+    };
+    let failed = count(Task::is_failed);
+    print(format!(
+        "{} tasks: {} completed, {} failed",
+        tasklist.tasks.len(),
+        count(|task| matches!(task.status(), TaskState::Completed)),
+        failed
+    ));
+    written
+        .into_inner()
+        .1
+        .map_err(|e| format!("Could not print the progress: {}", e))?;
+
+    if failed > 0 {
+        return Err(format!(
+            "{} of {} tasks failed",
+            failed,
+            tasklist.tasks.len()
+        ));
+    }
+    Ok(())
+}
+
+/// Process exit code for the result of [`run`]: nonzero if the queue could not
+/// be run or any of its tasks failed.
+pub fn exit_code(result: &Result<(), String>) -> i32 {
+    match result {
+        Ok(()) => 0,
+        Err(_) => 1,
+    }
+}
@@ -1,15 +1,47 @@
 use iced::widget::{text, Text};
 use iced::{alignment, Font};
+use std::sync::OnceLock;
+
+const ICON_BYTES: &[u8] = include_bytes!("../../fonts/icons.ttf");
 
 // Fonts
 pub const ICONS: Font = Font::External {
     name: "Icons",
-    bytes: include_bytes!("../../fonts/icons.ttf"),
+    bytes: ICON_BYTES,
 };
 
-fn icon(unicode: char, size: f32) -> Text<'static> {
-    text(unicode.to_string())
-        .font(ICONS)
+/// Setting this environment variable draws every icon as plain text.
+pub const TEXT_ICONS_ENV: &str = "STM_RS_TEXT_ICONS";
+
+/// The icon font, if it parses and was not disabled through `TEXT_ICONS_ENV`.
+fn icon_face() -> Option<&'static ttf_parser::Face<'static>> {
+    static FACE: OnceLock<Option<ttf_parser::Face<'static>>> = OnceLock::new();
+
+    FACE.get_or_init(|| {
+        if std::env::var_os(TEXT_ICONS_ENV).is_some() {
+            return None;
+        }
+        ttf_parser::Face::parse(ICON_BYTES, 0).ok()
+    })
+    .as_ref()
+}
+
+/// The glyph to draw from `face`, or `None` when there is no icon font or it
+/// lacks `unicode`.
+fn glyph(unicode: char, face: Option<&ttf_parser::Face>) -> Option<char> {
+    face.and_then(|face| face.glyph_index(unicode))
+        .map(|_| unicode)
+}
+
+/// Draws `unicode` from the icon font, or `fallback` in the default font when
+/// the icon font is unavailable or lacks the glyph.
+fn icon(unicode: char, fallback: &'static str, size: f32) -> Text<'static> {
+    let label = match glyph(unicode, icon_face()) {
+        Some(glyph) => text(glyph.to_string()).font(ICONS),
+        None => text(fallback),
+    };
+
+    label
         .width(size + 2.0)
         .horizontal_alignment(alignment::Horizontal::Center)
         .size(size)
@@ -19,49 +51,94 @@ const DEFAULT_ICON_SIZE: f32 = 25.0;
 const SMALL_ICON_SIZE: f32 = 20.0;
 
 pub fn play_icon() -> Text<'static> {
-    icon('\u{e918}', DEFAULT_ICON_SIZE)
+    icon('\u{e918}', ">", DEFAULT_ICON_SIZE)
 }
 
 pub fn pause_icon() -> Text<'static> {
-    icon('\u{e919}', DEFAULT_ICON_SIZE)
+    icon('\u{e919}', "||", DEFAULT_ICON_SIZE)
 }
 
 pub fn stop_icon() -> Text<'static> {
-    icon('\u{e90f}', DEFAULT_ICON_SIZE)
+    icon('\u{e90f}', "[]", DEFAULT_ICON_SIZE)
 }
 
 pub fn completed_icon() -> Text<'static> {
-    icon('\u{e904}', DEFAULT_ICON_SIZE)
+    icon('\u{e904}', "ok", DEFAULT_ICON_SIZE)
 }
 
 pub fn failed_icon() -> Text<'static> {
-    icon('\u{e906}', DEFAULT_ICON_SIZE)
+    icon('\u{e906}', "x", DEFAULT_ICON_SIZE)
 }
 
 pub fn running_icon() -> Text<'static> {
-    icon('\u{e91d}', DEFAULT_ICON_SIZE)
+    icon('\u{e91d}', "..", DEFAULT_ICON_SIZE)
 }
 
 pub fn circle_icon() -> Text<'static> {
-    icon('\u{e90a}', DEFAULT_ICON_SIZE)
+    icon('\u{e90a}', "o", DEFAULT_ICON_SIZE)
 }
 
 pub fn menu_icon() -> Text<'static> {
-    icon('\u{e90d}', SMALL_ICON_SIZE)
+    icon('\u{e90d}', "=", SMALL_ICON_SIZE)
 }
 
 pub fn images_icon() -> Text<'static> {
-    icon('\u{e91c}', SMALL_ICON_SIZE)
+    icon('\u{e91c}', "img", SMALL_ICON_SIZE)
 }
 
 pub fn graph_icon() -> Text<'static> {
-    icon('\u{e91f}', SMALL_ICON_SIZE)
+    icon('\u{e91f}', "~", SMALL_ICON_SIZE)
 }
 
 pub fn gear_icon() -> Text<'static> {
-    icon('\u{e920}', SMALL_ICON_SIZE)
+    icon('\u{e920}', "*", SMALL_ICON_SIZE)
 }
 
 pub fn three_dots_vertical_icon() -> Text<'static> {
-    icon('\u{e90c}', DEFAULT_ICON_SIZE)
+    icon('\u{e90c}', ":", DEFAULT_ICON_SIZE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Code points drawn by the icon functions above.
+    const CODE_POINTS: [char; 12] = [
+        '\u{e918}', '\u{e919}', '\u{e90f}', '\u{e904}', '\u{e906}', '\u{e91d}', '\u{e90a}',
+        '\u{e90d}', '\u{e91c}', '\u{e91f}', '\u{e920}', '\u{e90c}',
+    ];
+
+    #[test]
+    fn the_icon_font_has_every_glyph() {
+        let face = ttf_parser::Face::parse(ICON_BYTES, 0).unwrap();
+
+        for unicode in CODE_POINTS {
+            assert_eq!(glyph(unicode, Some(&face)), Some(unicode), "{unicode:?}");
+        }
+    }
+
+    #[test]
+    fn icons_fall_back_to_text_without_the_font() {
+        for unicode in CODE_POINTS {
+            assert_eq!(glyph(unicode, None), None);
+        }
+
+        let icons = [
+            play_icon(),
+            pause_icon(),
+            stop_icon(),
+            completed_icon(),
+            failed_icon(),
+            running_icon(),
+            circle_icon(),
+            menu_icon(),
+            images_icon(),
+            graph_icon(),
+            gear_icon(),
+            three_dots_vertical_icon(),
+        ];
+
+        let elements: Vec<iced::Element<()>> = icons.into_iter().map(Into::into).collect();
+        assert_eq!(elements.len(), 12);
+    }
 }
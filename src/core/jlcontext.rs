@@ -1,44 +1,598 @@
-use crossbeam_channel::Receiver;
+use crate::core::{
+    calibration::Calibration,
+    runner::TaskRunner,
+    stmimage::{Measurement, STMImage},
+};
+use crate::core_async::stmimage::ScanJob;
+use crossbeam_channel::{Receiver, Sender};
 use jlrs::prelude::*;
 use jlrs::error::JlrsError;
+use std::future::Future;
 use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::thread::JoinHandle;
 
+/// Number of Julia tasks that can run at the same time.
+pub const WORKERS: usize = 2;
+/// Julia script defining the `Test.read_lockin` procedure used by default.
+pub const DEFAULT_PROCEDURE_PATH: &str =
+    concat!(env!("CARGO_MANIFEST_DIR"), "/src/procedures/lockin_test.jl");
+/// How often a suspended dispatch checks whether it may continue.
+const SUSPEND_POLL: std::time::Duration = std::time::Duration::from_millis(100);
+/// Version of the jlrs crate the app was built against, read from Cargo.lock
+/// by the build script.
+const JLRS_VERSION: &str = env!("JLRS_VERSION");
+
+/// Versions of the app and of the Julia stack it runs on, for the About dialog.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VersionInfo {
+    pub app: &'static str,
+    pub jlrs: &'static str,
+    /// Version reported by the running Julia, `None` if it could not be asked.
+    pub julia: Option<String>,
+}
+
+impl VersionInfo {
+    /// Compile-time versions only, for when no Julia runtime is running.
+    pub fn without_julia() -> Self {
+        Self {
+            app: env!("CARGO_PKG_VERSION"),
+            jlrs: JLRS_VERSION,
+            julia: None,
+        }
+    }
+}
+
+impl std::fmt::Display for VersionInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{} {}", env!("CARGO_PKG_NAME"), self.app)?;
+        writeln!(f, "jlrs {}", self.jlrs)?;
+        write!(f, "Julia {}", self.julia.as_deref().unwrap_or("unavailable"))
+    }
+}
+
+type RuntimeHandle = JoinHandle<Result<(), Box<JlrsError>>>;
+
+/// What a dispatch resolves with: the measurement of every image scanned, in
+/// order, and the error that stopped it early, if any.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Dispatched {
+    pub data: Vec<Measurement>,
+    pub error: Option<String>,
+}
+
+/// Reported by dispatched tasks while they scan, naming the task by its id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScanEvent {
+    /// The task finished another scan line.
+    Line(u64),
+    /// Julia spent long enough collecting garbage during the task's line in
+    /// progress that the line's duration says little about the scan rate.
+    GcStalled(u64),
+}
+
+impl ScanEvent {
+    /// Id of the task reporting the event.
+    pub fn task(&self) -> u64 {
+        match *self {
+            ScanEvent::Line(task) | ScanEvent::GcStalled(task) => task,
+        }
+    }
+}
+
+/// Tells dispatched tasks to stop. Clones share the same signal.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
 pub struct JuliaContext
 {
     pub julia: AsyncJulia<Tokio>,
-    pub handle: JoinHandle<Result<(), Box<JlrsError>>>,
-    pub receiver: Option<Receiver<Result<jlrs::prelude::Bool, Box<JlrsError>>>>
+    pub handle: RuntimeHandle,
+    /// Applied to images as they are dispatched. The identity until set, e.g.
+    /// from [`Calibration::from_default_profile`].
+    pub calibration: Calibration,
+    /// Julia script defining the procedures tasks call into.
+    procedure_path: PathBuf,
+    /// Script last included in the runtime, if any.
+    included_procedure: Option<PathBuf>,
+    /// Set while dispatched tasks should hold before their next image.
+    suspended: Arc<AtomicBool>,
+    /// Shared by every task dispatched since the last cancellation.
+    cancel_token: CancelToken,
+    /// Handed to dispatched tasks to report their progress on.
+    event_sender: Sender<ScanEvent>,
+    events: Receiver<ScanEvent>,
 }
 
 impl Default for JuliaContext {
+    /// # Panics
+    ///
+    /// Panics if the Julia runtime cannot be started. Use
+    /// [`JuliaContext::try_new`] to handle that case instead.
     fn default() -> Self {
-        let (julia, handle) = unsafe {
+        Self::try_new().expect("Could not init Julia")
+    }
+}
+
+impl JuliaContext {
+    /// Starts the Julia runtime, returning the error if it cannot be started,
+    /// e.g. when Julia is not installed.
+    pub fn try_new() -> Result<Self, Box<JlrsError>> {
+        Self::try_with_procedure(DEFAULT_PROCEDURE_PATH)
+    }
+
+    /// Starts the Julia runtime like [`JuliaContext::try_new`], running tasks
+    /// with the procedures defined in the script at `procedure_path`.
+    pub fn try_with_procedure(procedure_path: impl Into<PathBuf>) -> Result<Self, Box<JlrsError>> {
+        let (julia, handle) = Self::start_runtime()?;
+        let (event_sender, events) = crossbeam_channel::unbounded();
+
+        Ok(Self {
+            julia,
+            handle,
+            calibration: Calibration::default(),
+            procedure_path: procedure_path.into(),
+            included_procedure: None,
+            suspended: Arc::new(AtomicBool::new(false)),
+            cancel_token: CancelToken::default(),
+            event_sender,
+            events,
+        })
+    }
+
+    fn start_runtime() -> Result<(AsyncJulia<Tokio>, RuntimeHandle), Box<JlrsError>> {
+        unsafe {
             RuntimeBuilder::new()
                 .async_runtime::<Tokio>()
-                .channel_capacity(NonZeroUsize::new(2).unwrap())
-                .start::<1>()
-                .expect("Could not init Julia")
+                .channel_capacity(NonZeroUsize::new(WORKERS).unwrap())
+                .start::<WORKERS>()
+        }
+    }
+
+    /// Whether the runtime thread is still running. It stops when Julia
+    /// panics or exits, after which tasks sent to it never complete.
+    ///
+    /// A stopped runtime cannot be started again: Julia can only be
+    /// initialized once per process, so the app has to be relaunched.
+    pub fn is_alive(&self) -> bool {
+        !self.handle.is_finished()
+    }
+
+    /// Lets the runtime stop once the tasks sent to it are done. The runtime
+    /// thread exits when the last handle to Julia is gone, so the handle is
+    /// dropped before the thread is detached. The thread is not joined, since
+    /// dispatched tasks may still hold a handle while the app closes.
+    pub fn shutdown(self) {
+        let Self { julia, handle, .. } = self;
+        drop(julia);
+        drop(handle);
+    }
+
+    /// Registers `Task` with the runtime.
+    pub fn load<Task>(&mut self) -> Result<(), String>
+    where
+        Task: AsyncTask,
+    {
+        self.register::<Task>()
+    }
+
+    fn register<Task>(&self) -> Result<(), String>
+    where
+        Task: AsyncTask,
+    {
+        // Include the custom code MyTask needs by registering it.
+        let (sender, receiver) = crossbeam_channel::bounded(1);
+        self.julia
+            .try_register_task::<Task, _>(sender)
+            .map_err(|e| e.to_string())?;
+
+        receiver
+            .recv()
+            .map_err(|_| String::from("Julia runtime hung up"))?
+            .map_err(|e| format!("Could not register task: {}", e))
+    }
+
+    pub fn procedure_path(&self) -> &Path {
+        &self.procedure_path
+    }
+
+    /// Runs tasks with the procedures of another script, included on the next
+    /// call to [`JuliaContext::include_procedure`].
+    pub fn set_procedure_path(&mut self, procedure_path: impl Into<PathBuf>) {
+        self.procedure_path = procedure_path.into();
+    }
+
+    /// Includes the procedure script in the runtime, unless it already was.
+    /// Fails with a message naming the script if it does not exist or cannot
+    /// be evaluated.
+    pub fn include_procedure(&mut self) -> Result<(), String> {
+        if self.included_procedure.as_ref() == Some(&self.procedure_path) {
+            return Ok(());
+        }
+        check_procedure(&self.procedure_path)?;
+
+        let path = self.procedure_path.clone();
+        let (sender, receiver) = crossbeam_channel::bounded(1);
+        self.julia
+            .try_blocking_task(
+                move |mut frame| unsafe {
+                    Value::include(&mut frame, &path)?.into_jlrs_result()?;
+                    Ok(())
+                },
+                sender,
+            )
+            .map_err(|e| e.to_string())?;
+
+        receiver
+            .recv()
+            .map_err(|_| String::from("Julia runtime hung up"))?
+            .map_err(|e| {
+                format!(
+                    "Could not include Julia procedure {}: {}",
+                    self.procedure_path.display(),
+                    e
+                )
+            })?;
+
+        self.included_procedure = Some(self.procedure_path.clone());
+        Ok(())
+    }
+
+    /// Asks the running Julia for its version, alongside the compile-time
+    /// versions of the app and jlrs.
+    pub fn version_info(&self) -> VersionInfo {
+        let (sender, receiver) = crossbeam_channel::bounded(1);
+        let julia = self
+            .julia
+            .try_blocking_task(
+                |mut frame| unsafe {
+                    let version = Value::eval_string(&mut frame, "string(VERSION)")
+                        .into_jlrs_result()?
+                        .unbox::<String>()?;
+                    Ok(version.ok())
+                },
+                sender,
+            )
+            .ok()
+            .and_then(|_| receiver.recv().ok())
+            .and_then(|result| result.ok())
+            .flatten();
+
+        VersionInfo {
+            julia,
+            ..VersionInfo::without_julia()
+        }
+    }
+
+    /// Holds every dispatched task before its next image. The image Julia is
+    /// scanning finishes first, as a scan cannot be interrupted midway.
+    pub fn suspend(&self) {
+        self.suspended.store(true, Ordering::SeqCst);
+    }
+
+    /// Lets suspended tasks carry on with their next image.
+    pub fn resume(&self) {
+        self.suspended.store(false, Ordering::SeqCst);
+    }
+
+    pub fn is_suspended(&self) -> bool {
+        self.suspended.load(Ordering::SeqCst)
+    }
+
+    /// Cancels every task dispatched so far, including suspended ones. A
+    /// task stops before its next image: the image Julia is scanning still
+    /// runs to the end. Tasks dispatched afterwards are not affected.
+    pub fn cancel(&mut self) {
+        self.cancel_token.cancel();
+        self.cancel_token = CancelToken::default();
+    }
+
+    /// Signal shared by every task dispatched or run from now until the next
+    /// [`JuliaContext::cancel`]. Cancelling it from elsewhere, e.g. another
+    /// thread while [`TaskRunner::run`] blocks, stops those tasks the same way.
+    pub fn cancel_token(&self) -> CancelToken {
+        self.cancel_token.clone()
+    }
+
+    /// Takes the events reported by dispatched tasks since the last call, in
+    /// the order they happened.
+    pub fn take_events(&self) -> Vec<ScanEvent> {
+        self.events.try_iter().collect()
+    }
+
+    /// Runs `images` one after another without blocking the caller, reporting
+    /// each scanned line as a [`ScanEvent`] of `task`. Resolves once the last
+    /// one finished, or with the error of the first that failed alongside the
+    /// data of those scanned before it. Up to `WORKERS` of these can be in
+    /// flight at once. Each waits between images while the context is
+    /// suspended, and gives up before its next image once cancelled.
+    pub fn dispatch(&self, task: u64, images: Vec<STMImage>) -> impl Future<Output = Dispatched> {
+        let julia = self.julia.clone();
+        let calibration = self.calibration;
+        let event_sender = self.event_sender.clone();
+
+        let scan_image = move |image: STMImage| {
+            let julia = julia.clone();
+            let job = ScanJob {
+                image: image.calibrated(&calibration),
+                progress: Some((task, event_sender.clone())),
+            };
+            async move { scan(&julia, job).await }
         };
+        run_images(
+            images,
+            self.suspended.clone(),
+            self.cancel_token.clone(),
+            scan_image,
+        )
+    }
+}
 
-        let receiver: Option<Receiver<Result<jlrs::prelude::Bool, Box<JlrsError>>>> = None;
+/// Scans `images` one after another with `scan_image`, as
+/// [`JuliaContext::dispatch`] describes: waiting before each image while
+/// `suspended`, giving up once `cancel_token` is cancelled and stopping at the
+/// first error.
+async fn run_images<S, F>(
+    images: Vec<STMImage>,
+    suspended: Arc<AtomicBool>,
+    cancel_token: CancelToken,
+    mut scan_image: S,
+) -> Dispatched
+where
+    S: FnMut(STMImage) -> F,
+    F: Future<Output = Result<Measurement, String>>,
+{
+    let mut data = Vec::with_capacity(images.len());
 
-        Self {
-            julia, 
-            handle,
-            receiver
+    for image in images {
+        while suspended.load(Ordering::SeqCst) && !cancel_token.is_cancelled() {
+            tokio::time::sleep(SUSPEND_POLL).await;
+        }
+        if cancel_token.is_cancelled() {
+            return Dispatched {
+                data,
+                error: Some(String::from("Interrupted by user.")),
+            };
+        }
+
+        match scan_image(image).await {
+            Ok(measurement) => data.push(measurement),
+            Err(e) => {
+                return Dispatched {
+                    data,
+                    error: Some(e),
+                }
+            }
         }
     }
+    Dispatched { data, error: None }
 }
 
-impl JuliaContext {
-    pub fn load<Task>(&self) 
-    where 
-        Task : AsyncTask
-    {
-        // Include the custom code MyTask needs by registering it.
+/// Fails with a message naming the procedure script at `path` if there is no
+/// such file to include.
+fn check_procedure(path: &Path) -> Result<(), String> {
+    if path.is_file() {
+        Ok(())
+    } else {
+        Err(format!("Julia procedure {} does not exist", path.display()))
+    }
+}
+
+/// Scans the image of `job` on `julia` and waits for its data without blocking
+/// the executor.
+async fn scan(julia: &AsyncJulia<Tokio>, job: ScanJob) -> Result<Measurement, String> {
+    let (sender, receiver) = crossbeam_channel::bounded(1);
+    julia.try_task(job, sender).map_err(|e| e.to_string())?;
+
+    tokio::task::spawn_blocking(move || receiver.recv())
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|_| String::from("Julia runtime hung up"))?
+        .map_err(|e| e.to_string())
+}
+
+impl TaskRunner<STMImage> for JuliaContext {
+    type Output = Measurement;
+    type Error = String;
+
+    /// Sends the image to Julia, converted to instrument units, and blocks
+    /// until its result comes back. Fails with the Julia error, if the
+    /// runtime stopped before answering, or without scanning once the
+    /// [`JuliaContext::cancel_token`] was cancelled.
+    fn run(&mut self, image: STMImage) -> Result<Measurement, String> {
+        if self.cancel_token.is_cancelled() {
+            return Err(String::from("Interrupted by user."));
+        }
+
+        let job = ScanJob {
+            image: image.calibrated(&self.calibration),
+            progress: None,
+        };
         let (sender, receiver) = crossbeam_channel::bounded(1);
-        self.julia.try_register_task::<Task, _>(sender).unwrap();
-        receiver.recv().unwrap().unwrap();
+        self.julia
+            .try_task(job, sender)
+            .map_err(|e| e.to_string())?;
+
+        receiver
+            .recv()
+            .map_err(|_| String::from("Julia runtime hung up"))?
+            .map_err(|e| e.to_string())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    fn images(biases: &[f64]) -> Vec<STMImage> {
+        biases
+            .iter()
+            .map(|&bias| STMImage::new(8, 1e-8, 0.0, 0.0, 0.0, 0.1, bias, 1e-10, None))
+            .collect()
+    }
+
+    /// A stand-in for the height data Julia scans, telling images apart.
+    fn measured(value: f64) -> Measurement {
+        Measurement {
+            data: vec![value],
+            spectroscopy: vec![],
+        }
+    }
+
+    #[test]
+    fn dispatch_returns_the_data_of_every_image_in_order() {
+        let scan_image = |image: STMImage| async move { Ok(measured(image.bias())) };
+        let dispatched = block_on(run_images(
+            images(&[0.5, 1.0]),
+            Arc::default(),
+            CancelToken::default(),
+            scan_image,
+        ));
+
+        assert_eq!(
+            dispatched,
+            Dispatched {
+                data: vec![measured(0.5), measured(1.0)],
+                error: None,
+            }
+        );
+    }
+
+    #[test]
+    fn cancel_token_clones_share_the_signal() {
+        let token = CancelToken::default();
+        let clone = token.clone();
+        assert!(!token.is_cancelled());
+
+        clone.cancel();
+        assert!(token.is_cancelled());
+    }
+
+    #[test]
+    fn cancelling_stops_before_the_next_image() {
+        let cancel_token = CancelToken::default();
+        let token = cancel_token.clone();
+        let scan_image = move |image: STMImage| {
+            // Cancelled while the first image scans, which still completes
+            token.cancel();
+            async move { Ok(measured(image.bias())) }
+        };
+        let dispatched = block_on(run_images(
+            images(&[0.5, 1.0]),
+            Arc::default(),
+            cancel_token,
+            scan_image,
+        ));
+
+        assert_eq!(dispatched.data, [measured(0.5)]);
+        assert_eq!(dispatched.error.as_deref(), Some("Interrupted by user."));
+    }
+
+    #[test]
+    fn suspended_dispatch_waits_and_gives_up_once_cancelled() {
+        let mut scans = 0;
+        let cancel_token = CancelToken::default();
+        let suspended = Arc::new(AtomicBool::new(true));
+        let canceller = {
+            let token = cancel_token.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(SUSPEND_POLL);
+                token.cancel();
+            })
+        };
+
+        let dispatched = block_on(run_images(images(&[0.5]), suspended, cancel_token, |_| {
+            scans += 1;
+            async { Ok(Measurement::default()) }
+        }));
+        canceller.join().unwrap();
+
+        assert_eq!(scans, 0);
+        assert!(dispatched.data.is_empty());
+        assert_eq!(dispatched.error.as_deref(), Some("Interrupted by user."));
+    }
+
+    #[test]
+    fn failed_scan_ends_the_dispatch_with_its_error() {
+        let mut scans = 0;
+        let dispatched = block_on(run_images(
+            images(&[0.5, 1.0, 1.5]),
+            Arc::default(),
+            CancelToken::default(),
+            |image: STMImage| {
+                scans += 1;
+                let result = if image.bias() == 1.0 {
+                    Err(String::from("UndefVarError: read_lockin not defined"))
+                } else {
+                    Ok(measured(image.bias()))
+                };
+                async { result }
+            },
+        ));
+
+        // What was scanned before the error is kept
+        assert_eq!(
+            dispatched,
+            Dispatched {
+                data: vec![measured(0.5)],
+                error: Some(String::from("UndefVarError: read_lockin not defined")),
+            }
+        );
+        assert_eq!(scans, 2);
+    }
+
+    #[test]
+    fn version_info_names_every_component() {
+        let without_julia = VersionInfo::without_julia();
+        assert_eq!(without_julia.app, env!("CARGO_PKG_VERSION"));
+        assert!(!without_julia.jlrs.is_empty());
+        assert!(without_julia.to_string().ends_with("\nJulia unavailable"));
+
+        let running = VersionInfo {
+            julia: Some(String::from("1.9.1")),
+            ..without_julia
+        };
+        assert_eq!(
+            running.to_string(),
+            format!(
+                "stm_controller {}\njlrs {}\nJulia 1.9.1",
+                running.app, running.jlrs
+            )
+        );
+    }
+
+    #[test]
+    fn procedure_scripts_must_exist() {
+        let path = std::env::temp_dir().join("stm_controller_procedure.jl");
+        std::fs::write(&path, "module Test end\n").unwrap();
+        let found = check_procedure(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(found, Ok(()));
+        assert_eq!(
+            check_procedure(&path),
+            Err(format!("Julia procedure {} does not exist", path.display()))
+        );
+        assert!(check_procedure(Path::new(DEFAULT_PROCEDURE_PATH)).is_ok());
+    }
+}
@@ -1,5 +1,21 @@
+pub mod audit;
+pub mod batch;
+pub mod calibration;
+pub mod engine;
+pub mod eta;
+pub mod export;
+pub mod hardware;
+pub mod headless;
 pub mod icons;
+pub mod notify;
+pub mod settings;
 pub mod stmimage;
+pub mod storage;
+pub mod sweep;
+pub mod synthetic;
 pub mod task;
+pub mod undo;
 pub mod vector2;
 pub mod jlcontext;
+pub mod runner;
+pub mod scangeometry;
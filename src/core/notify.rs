@@ -0,0 +1,21 @@
+//! Letting the operator know a queue finished while they were away.
+use notify_rust::Notification;
+use std::io::{self, Write};
+
+/// Called once the last task of a run is done, with a summary of the run.
+pub type CompletionHook = Box<dyn Fn(&str)>;
+
+/// Shows `summary` as a desktop notification, ringing the terminal bell
+/// instead when no notification server answers.
+pub fn desktop_notification(summary: &str) {
+    let shown = Notification::new()
+        .summary("Queue finished")
+        .body(summary)
+        .show();
+
+    if let Err(e) = shown {
+        eprintln!("Could not show a notification: {}", e);
+        print!("\x07");
+        let _ = io::stdout().flush();
+    }
+}
@@ -0,0 +1,11 @@
+/// Something that can execute the items of a queued task.
+///
+/// [`JuliaContext`](crate::core::jlcontext::JuliaContext) is the runner used
+/// by the app; other implementations can stand in for the instrument.
+pub trait TaskRunner<T> {
+    type Output: std::fmt::Debug;
+    type Error: std::fmt::Display;
+
+    /// Runs a single item of a task to completion.
+    fn run(&mut self, item: T) -> Result<Self::Output, Self::Error>;
+}
@@ -0,0 +1,195 @@
+use crate::core::hardware;
+
+/// Fraction of the scan size moved by one arrow-key nudge.
+pub const NUDGE_FRACTION: f64 = 0.01;
+/// Fraction of the scan size moved by one arrow-key nudge with Shift held.
+pub const LARGE_NUDGE_FRACTION: f64 = 0.1;
+
+/// Position of a scan frame on the sample, in meters and degrees.
+///
+/// Points in the scan frame are measured from the frame centre along the fast
+/// and slow scan axes. Mapping them onto the sample first rotates them by
+/// `rotation` (counter-clockwise, about the frame centre) and then translates
+/// them by the offset, so the frame keeps its `size` and centre no matter how
+/// it is rotated.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ScanGeometry {
+    pub size: f64,
+    pub x_offset: f64,
+    pub y_offset: f64,
+    pub rotation: f64,
+}
+
+impl ScanGeometry {
+    pub fn new(size: f64, x_offset: f64, y_offset: f64, rotation: f64) -> Self {
+        Self {
+            size,
+            x_offset,
+            y_offset,
+            rotation,
+        }
+    }
+
+    /// Maps a point from scan-frame coordinates to sample coordinates.
+    pub fn to_sample(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        let (sin, cos) = self.rotation.to_radians().sin_cos();
+
+        (
+            x * cos - y * sin + self.x_offset,
+            x * sin + y * cos + self.y_offset,
+        )
+    }
+
+    /// Maps a point from sample coordinates back into scan-frame coordinates.
+    pub fn to_scan(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        let (sin, cos) = self.rotation.to_radians().sin_cos();
+        let (dx, dy) = (x - self.x_offset, y - self.y_offset);
+
+        (dx * cos + dy * sin, -dx * sin + dy * cos)
+    }
+
+    /// Offset after nudging the frame one step along `direction` (each
+    /// component -1, 0 or 1, in sample axes), kept within the hardware range.
+    ///
+    /// With a `grid` spacing the offset is first snapped to the grid and then
+    /// moved by one grid increment, or ten with `large`, so the result always
+    /// lands on the grid.
+    pub fn nudged(&self, (dx, dy): (f64, f64), large: bool, grid: Option<f64>) -> (f64, f64) {
+        let (origin, step) = match grid.filter(|grid| *grid > 0.0) {
+            Some(grid) => (self.snapped(grid), if large { 10.0 * grid } else { grid }),
+            None => {
+                let fraction = if large {
+                    LARGE_NUDGE_FRACTION
+                } else {
+                    NUDGE_FRACTION
+                };
+                (*self, self.size * fraction)
+            }
+        };
+        let (lower, upper) = hardware::OFFSET;
+
+        (
+            (origin.x_offset + dx * step).clamp(lower, upper),
+            (origin.y_offset + dy * step).clamp(lower, upper),
+        )
+    }
+
+    /// The frame with its offset and size rounded to the nearest multiple of
+    /// `grid`, in meters. The size never snaps below one grid increment.
+    pub fn snapped(&self, grid: f64) -> Self {
+        if grid <= 0.0 {
+            return *self;
+        }
+        let snap = |value: f64| (value / grid).round() * grid;
+
+        Self {
+            size: snap(self.size).max(grid),
+            x_offset: snap(self.x_offset),
+            y_offset: snap(self.y_offset),
+            ..*self
+        }
+    }
+
+    /// Corners of the scan frame in sample coordinates, counter-clockwise from
+    /// the bottom left.
+    pub fn corners(&self) -> [(f64, f64); 4] {
+        let half = self.size / 2.0;
+
+        [
+            self.to_sample((-half, -half)),
+            self.to_sample((half, -half)),
+            self.to_sample((half, half)),
+            self.to_sample((-half, half)),
+        ]
+    }
+
+    /// `rows` × `cols` points spread evenly over the scan frame, edge to edge,
+    /// in sample coordinates. Rows run from the top of the frame down and
+    /// each goes left to right; a single row or column sits on the centre line.
+    pub fn grid_points(&self, rows: u32, cols: u32) -> Vec<(f64, f64)> {
+        let along = |n: u32, i: u32| {
+            if n <= 1 {
+                0.0
+            } else {
+                self.size * (f64::from(i) / f64::from(n - 1) - 0.5)
+            }
+        };
+
+        let mut points = Vec::with_capacity((rows * cols) as usize);
+        for row in 0..rows {
+            for col in 0..cols {
+                points.push(self.to_sample((along(cols, col), -along(rows, row))));
+            }
+        }
+        points
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close((x, y): (f64, f64), (expected_x, expected_y): (f64, f64)) {
+        assert!(
+            (x - expected_x).abs() < 1e-15 && (y - expected_y).abs() < 1e-15,
+            "({x}, {y}) is not ({expected_x}, {expected_y})"
+        );
+    }
+
+    #[test]
+    fn rotation_turns_the_frame_about_its_centre() {
+        let geometry = ScanGeometry::new(20e-9, 5e-9, -5e-9, 90.0);
+
+        // The fast axis points along the sample's y axis
+        assert_close(geometry.to_sample((10e-9, 0.0)), (5e-9, 5e-9));
+        assert_close(geometry.to_sample((0.0, 0.0)), (5e-9, -5e-9));
+
+        let corners = geometry.corners();
+        assert_close(corners[0], (15e-9, -15e-9));
+        assert_close(corners[2], (-5e-9, 5e-9));
+    }
+
+    #[test]
+    fn to_scan_undoes_to_sample() {
+        let geometry = ScanGeometry::new(20e-9, 3e-9, 7e-9, 30.0);
+
+        for point in [(0.0, 0.0), (10e-9, -4e-9), (-6e-9, 8e-9)] {
+            assert_close(geometry.to_scan(geometry.to_sample(point)), point);
+        }
+        assert_close(geometry.to_scan((3e-9, 7e-9)), (0.0, 0.0));
+    }
+
+    #[test]
+    fn snapping_rounds_offset_and_size_to_the_grid() {
+        let grid = 10e-9;
+        let snapped = ScanGeometry::new(4e-9, 23e-9, -16e-9, 30.0).snapped(grid);
+
+        assert_close((snapped.x_offset, snapped.y_offset), (20e-9, -20e-9));
+        // Never smaller than one grid increment
+        assert!((snapped.size - grid).abs() < 1e-15);
+        assert_eq!(snapped.rotation, 30.0);
+
+        let geometry = ScanGeometry::new(47e-9, 0.0, 0.0, 0.0);
+        assert!((geometry.snapped(grid).size - 50e-9).abs() < 1e-15);
+        assert_eq!(geometry.snapped(0.0), geometry);
+    }
+
+    #[test]
+    fn nudges_move_a_share_of_the_scan_size() {
+        let geometry = ScanGeometry::new(100e-9, 0.0, 0.0, 0.0);
+
+        assert_close(geometry.nudged((1.0, 0.0), false, None), (1e-9, 0.0));
+        assert_close(geometry.nudged((0.0, -1.0), true, None), (0.0, -10e-9));
+    }
+
+    #[test]
+    fn nudges_land_on_the_grid_and_stay_in_range() {
+        let (geometry, grid) = (ScanGeometry::new(100e-9, 23e-9, 0.0, 0.0), Some(10e-9));
+        assert_close(geometry.nudged((1.0, 0.0), false, grid), (30e-9, 0.0));
+        assert_close(geometry.nudged((-1.0, 1.0), true, grid), (-80e-9, 100e-9));
+
+        let (_, upper) = hardware::OFFSET;
+        let at_edge = ScanGeometry::new(1e-6, upper, 0.0, 0.0);
+        assert_close(at_edge.nudged((1.0, 0.0), true, None), (upper, 0.0));
+    }
+}
@@ -0,0 +1,156 @@
+//! User preferences kept across launches, in a TOML file in the platform
+//! config directory.
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::core::export::OutputFormat;
+use crate::core::jlcontext::DEFAULT_PROCEDURE_PATH;
+
+/// Name of the settings file inside the app's config directory.
+pub const SETTINGS_FILE: &str = "settings.toml";
+
+/// Color scheme of the app.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ThemeChoice {
+    #[default]
+    Light,
+    Dark,
+}
+
+impl ThemeChoice {
+    pub const ALL: [ThemeChoice; 2] = [ThemeChoice::Light, ThemeChoice::Dark];
+}
+
+impl std::fmt::Display for ThemeChoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ThemeChoice::Light => "Light",
+            ThemeChoice::Dark => "Dark",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Preferences read at startup and written back on exit. Keys missing from
+/// the file keep their defaults.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Scan size the form starts with, in meters.
+    pub size: f64,
+    /// Lines per frame the form starts with, if one was picked.
+    pub lines: Option<u32>,
+    pub theme: ThemeChoice,
+    /// Julia script defining the procedures tasks call into.
+    pub procedure_path: String,
+    /// Name recorded with every image scanned.
+    pub operator: String,
+    /// Notify the desktop once the queue has run out of tasks.
+    pub notify_on_finish: bool,
+    /// Format new tasks are exported in.
+    pub output_format: OutputFormat,
+    /// Draw widgets in their final state instead of animating them.
+    pub reduce_motion: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            size: 50e-9,
+            lines: None,
+            theme: ThemeChoice::default(),
+            procedure_path: String::from(DEFAULT_PROCEDURE_PATH),
+            operator: String::new(),
+            notify_on_finish: true,
+            output_format: OutputFormat::default(),
+            reduce_motion: false,
+        }
+    }
+}
+
+impl Settings {
+    /// Location of the settings file, `None` if the platform has no config
+    /// directory.
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join(env!("CARGO_PKG_NAME")).join(SETTINGS_FILE))
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        toml::from_str(&contents).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Writes the settings to `path`, creating its directory if needed.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let contents =
+            toml::to_string_pretty(self).map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        std::fs::write(path, contents)
+    }
+
+    /// Loads the settings file at [`Settings::default_path`], falling back to
+    /// the defaults when there is no usable file.
+    pub fn from_default_path() -> Self {
+        Self::default_path()
+            .and_then(|path| Self::load(&path).ok())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saved_settings_reload_unchanged() {
+        let dir = std::env::temp_dir().join("stm_controller_settings_round_trip");
+        let (path, resaved) = (dir.join(SETTINGS_FILE), dir.join("resaved.toml"));
+        let settings = Settings {
+            size: 120e-9,
+            lines: Some(256),
+            theme: ThemeChoice::Dark,
+            procedure_path: String::from("/opt/stm/procedures.jl"),
+            operator: String::from("Ada"),
+            ..Settings::default()
+        };
+
+        settings.save(&path).unwrap();
+        let loaded = Settings::load(&path).unwrap();
+        loaded.save(&resaved).unwrap();
+        let (saved_bytes, resaved_bytes) = (std::fs::read(&path), std::fs::read(&resaved));
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(loaded, settings);
+        assert_eq!(saved_bytes.unwrap(), resaved_bytes.unwrap());
+    }
+
+    #[test]
+    fn output_format_and_motion_are_kept_across_launches() {
+        let path = std::env::temp_dir()
+            .join("stm_controller_settings_format")
+            .join(SETTINGS_FILE);
+        let settings = Settings {
+            output_format: OutputFormat::Csv,
+            reduce_motion: true,
+            ..Settings::default()
+        };
+
+        settings.save(&path).unwrap();
+        let loaded = Settings::load(&path).unwrap();
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+        assert_eq!(loaded, settings);
+    }
+
+    #[test]
+    fn files_without_an_output_format_default_to_png() {
+        let settings: Settings = toml::from_str("operator = \"Ada\"").unwrap();
+
+        assert_eq!(settings.output_format, OutputFormat::Png);
+        assert!(!settings.reduce_motion);
+        assert_eq!(settings.operator, "Ada");
+    }
+}
@@ -1,15 +1,55 @@
-use crate::core::vector2::Vector2;
+use crate::core::{
+    calibration::Calibration, scangeometry::ScanGeometry, sweep, task::Requeue, vector2::Vector2,
+};
+use crate::native::image_plot::{height_range, Colormap};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
-#[derive(Default, Debug, Clone)]
+/// Tunneling current set point, in A, of images saved before it was recorded.
+pub const DEFAULT_SET_POINT: f64 = 100e-12;
+
+fn default_set_point() -> f64 {
+    DEFAULT_SET_POINT
+}
+
+/// What the instrument measured for an image.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Measurement {
+    /// Height data, row by row.
+    pub data: Vec<f64>,
+    /// Curves of each spectroscopy of the image, in order, with one curve per
+    /// position it is measured at. See [`STS::data`].
+    pub spectroscopy: Vec<Vec<Vec<f64>>>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct STMImage {
     lines: u32,
     size: f64,
     x_offset: f64,
     y_offset: f64,
+    rotation: f64,
     line_time: f64,
     bias: f64,
-    // set_point: f64,
+    /// Tunneling current set point, in A.
+    #[serde(default = "default_set_point")]
+    set_point: f64,
     spectroscopy: Option<Vec<STS>>,
+    /// Height data returned by the instrument once scanned, row by row.
+    #[serde(default)]
+    data: Option<Vec<f64>>,
+    /// When the scan completed.
+    #[serde(default)]
+    captured_at: Option<SystemTime>,
+    /// Who ran the scan.
+    #[serde(default)]
+    operator: String,
+    #[serde(default)]
+    notes: String,
 }
 
 impl STMImage {
@@ -18,9 +58,10 @@ impl STMImage {
         size: f64,
         x_offset: f64,
         y_offset: f64,
+        rotation: f64,
         line_time: f64,
         bias: f64,
-        // set_point: f64,
+        set_point: f64,
         spectroscopy: Option<Vec<STS>>,
     ) -> Self {
         Self {
@@ -28,24 +69,518 @@ impl STMImage {
             size,
             x_offset,
             y_offset,
+            rotation,
             line_time,
             bias,
-            // set_point,
+            set_point,
             spectroscopy,
+            data: None,
+            captured_at: None,
+            operator: String::new(),
+            notes: String::new(),
+        }
+    }
+
+    pub fn lines(&self) -> u32 {
+        self.lines
+    }
+
+    pub fn size(&self) -> f64 {
+        self.size
+    }
+
+    pub fn x_offset(&self) -> f64 {
+        self.x_offset
+    }
+
+    pub fn y_offset(&self) -> f64 {
+        self.y_offset
+    }
+
+    pub fn rotation(&self) -> f64 {
+        self.rotation
+    }
+
+    pub fn line_time(&self) -> f64 {
+        self.line_time
+    }
+
+    pub fn bias(&self) -> f64 {
+        self.bias
+    }
+
+    pub fn spectroscopy(&self) -> Option<&[STS]> {
+        self.spectroscopy.as_deref()
+    }
+
+    pub fn set_point(&self) -> f64 {
+        self.set_point
+    }
+
+    /// Height data of the scanned image, `None` until it has been scanned.
+    pub fn data(&self) -> Option<&[f64]> {
+        self.data.as_deref()
+    }
+
+    /// Stores the scanned height data, stamping the image as captured now.
+    pub fn set_data(&mut self, data: Vec<f64>) {
+        self.data = Some(data);
+        self.captured_at = Some(SystemTime::now());
+    }
+
+    /// Stores everything measured for the image: the height data, as
+    /// [`STMImage::set_data`] does, and the curves of each spectroscopy.
+    pub fn set_measurement(&mut self, measurement: Measurement) {
+        self.set_data(measurement.data);
+
+        let spectroscopy = self.spectroscopy.iter_mut().flatten();
+        for (sts, curves) in spectroscopy.zip(measurement.spectroscopy) {
+            sts.set_data(curves);
+        }
+    }
+
+    pub fn captured_at(&self) -> Option<SystemTime> {
+        self.captured_at
+    }
+
+    pub fn operator(&self) -> &str {
+        &self.operator
+    }
+
+    pub fn set_operator(&mut self, operator: &str) {
+        self.operator = operator.to_string();
+    }
+
+    pub fn notes(&self) -> &str {
+        &self.notes
+    }
+
+    pub fn set_notes(&mut self, notes: String) {
+        self.notes = notes;
+    }
+
+    /// Writes the height data as a `lines` × `lines` PNG, colored with
+    /// `colormap`, with the scan parameters, capture time, operator and notes
+    /// in text chunks. Fails if the image has not been scanned yet.
+    pub fn export_png(&self, path: &Path, colormap: Colormap) -> io::Result<()> {
+        let data = self.data.as_deref().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "image has no data to write")
+        })?;
+
+        let lines = self.lines as usize;
+        if data.len() != lines * lines {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("expected {} values, got {}", lines * lines, data.len()),
+            ));
+        }
+
+        let data: Vec<f32> = data.iter().map(|&z| z as f32).collect();
+        let (min, max) = height_range(&data);
+        let pixels: Vec<u8> = data
+            .iter()
+            .flat_map(|&z| colormap.height_color(z, min, max).into_rgba8())
+            .collect();
+
+        let to_io = |e: png::EncodingError| io::Error::new(io::ErrorKind::Other, e);
+        let mut encoder =
+            png::Encoder::new(BufWriter::new(File::create(path)?), self.lines, self.lines);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut chunks = vec![("Description", self.label())];
+        if let Some(captured_at) = self.captured_at {
+            chunks.push((
+                "Creation Time",
+                DateTime::<Local>::from(captured_at).to_rfc3339(),
+            ));
+        }
+        if !self.operator.is_empty() {
+            chunks.push(("Author", self.operator.clone()));
+        }
+        if !self.notes.is_empty() {
+            chunks.push(("Comment", self.notes.clone()));
+        }
+        for (keyword, text) in chunks {
+            encoder
+                .add_text_chunk(keyword.to_string(), text)
+                .map_err(to_io)?;
+        }
+
+        let mut writer = encoder.write_header().map_err(to_io)?;
+        writer.write_image_data(&pixels).map_err(to_io)
+    }
+
+    /// Writes each measured spectroscopy of the image to
+    /// `<stem>_sts_<n>.csv` in `dir`, returning the paths written. See
+    /// [`STS::to_csv`].
+    pub fn export_spectroscopy_csv(&self, dir: &Path, stem: &str) -> io::Result<Vec<PathBuf>> {
+        let mut written = vec![];
+
+        let measured = self
+            .spectroscopy
+            .iter()
+            .flatten()
+            .filter(|sts| sts.data.is_some());
+        for (i, sts) in measured.enumerate() {
+            let path = dir.join(format!("{stem}_sts_{i}.csv"));
+            sts.to_csv(&path, &self.sts_positions(&sts.sts_type))?;
+            written.push(path);
+        }
+
+        Ok(written)
+    }
+
+    /// Every position `sts_type` measures at on this image, with grids laid
+    /// over its scan frame.
+    pub fn sts_positions(&self, sts_type: &STSType) -> Vec<Vector2<f64>> {
+        sts_type.positions(&self.geometry())
+    }
+
+    /// A copy of the image without anything measured: no height data and no
+    /// spectroscopy curves. The measurements are never cloned.
+    pub fn parameters(&self) -> Self {
+        Self {
+            lines: self.lines,
+            size: self.size,
+            x_offset: self.x_offset,
+            y_offset: self.y_offset,
+            rotation: self.rotation,
+            line_time: self.line_time,
+            bias: self.bias,
+            set_point: self.set_point,
+            spectroscopy: self
+                .spectroscopy
+                .as_ref()
+                .map(|spectroscopy| spectroscopy.iter().map(STS::parameters).collect()),
+            data: None,
+            captured_at: self.captured_at,
+            operator: self.operator.clone(),
+            notes: self.notes.clone(),
+        }
+    }
+
+    /// Scans at `set_point` instead of the one the image was created with.
+    pub fn with_set_point(mut self, set_point: f64) -> Self {
+        self.set_point = set_point;
+        self
+    }
+
+    /// Names the swept parameters of the image, e.g. `"0.5 V, 1e-10 A"`.
+    pub fn label(&self) -> String {
+        format!("{} V, {:e} A", self.bias, self.set_point)
+    }
+
+    /// Returns a copy in instrument units, ready to be dispatched.
+    pub fn calibrated(&self, calibration: &Calibration) -> Self {
+        Self {
+            size: calibration.size.apply(self.size),
+            x_offset: calibration.x_offset.apply(self.x_offset),
+            y_offset: calibration.y_offset.apply(self.y_offset),
+            bias: calibration.bias.apply(self.bias),
+            ..self.clone()
         }
     }
+
+    pub fn geometry(&self) -> ScanGeometry {
+        ScanGeometry::new(self.size, self.x_offset, self.y_offset, self.rotation)
+    }
 }
 
-#[derive(Debug, Clone)]
+impl Requeue for STMImage {
+    /// The image with its parameters and notes, but no measurements and no
+    /// record of who scanned it when.
+    fn requeued(&self) -> Self {
+        Self {
+            captured_at: None,
+            operator: String::new(),
+            ..self.parameters()
+        }
+    }
+}
+
+/// A bias spectroscopy measurement taken at a point, along a line or on a
+/// grid over the sample, after the image it belongs to.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct STS {
     sts_type: STSType,
     start_voltage: f64,
     stop_voltage: f64,
     step_voltage: f64,
+    /// Measured curve at each position, one value per voltage, once taken.
+    #[serde(default)]
+    data: Option<Vec<Vec<f64>>>,
 }
 
-#[derive(Debug, Clone)]
-enum STSType {
+impl STS {
+    pub fn new(
+        sts_type: STSType,
+        start_voltage: f64,
+        stop_voltage: f64,
+        step_voltage: f64,
+    ) -> Self {
+        Self {
+            sts_type,
+            start_voltage,
+            stop_voltage,
+            step_voltage,
+            data: None,
+        }
+    }
+
+    pub fn sts_type(&self) -> &STSType {
+        &self.sts_type
+    }
+
+    pub fn start_voltage(&self) -> f64 {
+        self.start_voltage
+    }
+
+    pub fn stop_voltage(&self) -> f64 {
+        self.stop_voltage
+    }
+
+    pub fn step_voltage(&self) -> f64 {
+        self.step_voltage
+    }
+
+    /// Voltages the curves are measured at, in order. Empty for a sweep too
+    /// long to generate, see [`sweep::range`].
+    pub fn voltages(&self) -> Vec<f64> {
+        sweep::range(self.start_voltage, self.stop_voltage, self.step_voltage).unwrap_or_default()
+    }
+
+    /// Measured curve at each position, `None` until it has been taken.
+    pub fn data(&self) -> Option<&[Vec<f64>]> {
+        self.data.as_deref()
+    }
+
+    pub fn set_data(&mut self, data: Vec<Vec<f64>>) {
+        self.data = Some(data);
+    }
+
+    /// A copy of the spectroscopy without its measured curves.
+    pub fn parameters(&self) -> Self {
+        Self {
+            sts_type: self.sts_type.clone(),
+            data: None,
+            ..*self
+        }
+    }
+
+    /// Writes the measured curves as CSV: a voltage column, then one column
+    /// per position headed with its type and coordinates. `positions` are
+    /// those measured at, see [`STMImage::sts_positions`]. Fails if the
+    /// spectroscopy has not been measured yet.
+    pub fn to_csv(&self, path: &Path, positions: &[Vector2<f64>]) -> io::Result<()> {
+        let data = self.data.as_deref().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "spectroscopy has no data to write",
+            )
+        })?;
+        if data.len() != positions.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("expected {} curves, got {}", positions.len(), data.len()),
+            ));
+        }
+
+        let mut csv = String::from("voltage");
+        for (i, position) in positions.iter().enumerate() {
+            let name = match self.sts_type {
+                STSType::Point(_) => String::from("point"),
+                STSType::Line(_) => format!("line {i}"),
+                STSType::Grid { .. } => format!("grid {i}"),
+            };
+            csv += &format!(",{} x={} y={}", name, position.x(), position.y());
+        }
+        csv.push('\n');
+
+        for (row, voltage) in self.voltages().into_iter().enumerate() {
+            csv += &voltage.to_string();
+            for curve in data {
+                csv.push(',');
+                if let Some(value) = curve.get(row) {
+                    csv += &value.to_string();
+                }
+            }
+            csv.push('\n');
+        }
+
+        std::fs::write(path, csv)
+    }
+}
+
+/// Where on the sample a spectroscopy measurement is taken, in meters.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum STSType {
     Point(Vector2<f64>),
+    /// Measured at every vertex of the polyline, in order.
     Line(Vec<Vector2<f64>>),
+    /// Measured on a regular grid spanning the image's scan frame, see
+    /// [`ScanGeometry::grid_points`].
+    Grid {
+        rows: u32,
+        cols: u32,
+    },
+}
+
+impl STSType {
+    /// Every position measured at, in order. Grids are laid over `geometry`.
+    pub fn positions(&self, geometry: &ScanGeometry) -> Vec<Vector2<f64>> {
+        match self {
+            STSType::Point(position) => vec![position.clone()],
+            STSType::Line(positions) => positions.clone(),
+            STSType::Grid { rows, cols } => geometry
+                .grid_points(*rows, *cols)
+                .into_iter()
+                .map(|(x, y)| Vector2::new(x, y))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 100 nm image at the origin, unrotated.
+    fn image(spectroscopy: Vec<STS>) -> STMImage {
+        STMImage::new(
+            2,
+            100e-9,
+            0.0,
+            0.0,
+            0.0,
+            0.1,
+            0.5,
+            DEFAULT_SET_POINT,
+            Some(spectroscopy),
+        )
+    }
+
+    #[test]
+    fn grid_spans_the_scan_frame() {
+        let grid = STSType::Grid { rows: 3, cols: 3 };
+
+        let positions = image(vec![]).sts_positions(&grid);
+
+        let expected: Vec<_> = [50e-9, 0.0, -50e-9]
+            .into_iter()
+            .flat_map(|y| [-50e-9, 0.0, 50e-9].map(|x| Vector2::new(x, y)))
+            .collect();
+        assert_eq!(positions, expected);
+    }
+
+    #[test]
+    fn measured_curves_are_stored_on_each_spectroscopy() {
+        let point = STS::new(STSType::Point(Vector2::new(1e-9, 2e-9)), -1.0, 1.0, 1.0);
+        let grid = STS::new(STSType::Grid { rows: 3, cols: 3 }, 0.0, 1.0, 0.5);
+        let mut image = image(vec![point, grid]);
+        let grid_curves: Vec<Vec<f64>> = (0..9).map(|i| vec![f64::from(i); 3]).collect();
+
+        image.set_measurement(Measurement {
+            data: vec![1.0; 4],
+            spectroscopy: vec![vec![vec![0.1, 0.2, 0.3]], grid_curves.clone()],
+        });
+
+        assert_eq!(image.data(), Some(&[1.0; 4][..]));
+        assert!(image.captured_at().is_some());
+        let sts = image.spectroscopy().unwrap();
+        assert_eq!(sts[0].data(), Some(&[vec![0.1, 0.2, 0.3]][..]));
+        assert_eq!(sts[1].data(), Some(&grid_curves[..]));
+        assert_eq!(
+            sts[1].data().unwrap().len(),
+            image.sts_positions(sts[1].sts_type()).len()
+        );
+    }
+
+    #[test]
+    fn provenance_is_recorded_on_completion_and_kept_when_saved() {
+        let mut image = image(vec![]);
+        assert_eq!(image.captured_at(), None);
+
+        let before = SystemTime::now();
+        image.set_measurement(Measurement {
+            data: vec![0.0; 4],
+            spectroscopy: vec![],
+        });
+        image.set_operator("Ada");
+        image.set_notes(String::from("tip changed before this one"));
+        let captured_at = image.captured_at().unwrap();
+        assert!(captured_at >= before && captured_at <= SystemTime::now());
+
+        let saved = serde_json::to_string(&image).unwrap();
+        let loaded: STMImage = serde_json::from_str(&saved).unwrap();
+        assert_eq!(loaded.captured_at(), Some(captured_at));
+        assert_eq!(loaded.operator(), "Ada");
+        assert_eq!(loaded.notes(), "tip changed before this one");
+    }
+
+    #[test]
+    fn exported_png_has_the_scan_resolution_and_colors() {
+        let mut image = STMImage::new(4, 100e-9, 0.0, 0.0, 0.0, 0.1, 0.5, DEFAULT_SET_POINT, None);
+        let path = std::env::temp_dir().join("stm_controller_export.png");
+        let unscanned = image.export_png(&path, Colormap::Grayscale).unwrap_err();
+        assert_eq!(unscanned.kind(), io::ErrorKind::InvalidInput);
+
+        image.set_measurement(Measurement {
+            data: (0..16).map(f64::from).collect(),
+            spectroscopy: vec![],
+        });
+        image.export_png(&path, Colormap::Grayscale).unwrap();
+        let decoder = png::Decoder::new(File::open(&path).unwrap());
+        let mut reader = decoder.read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut pixels).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!((info.width, info.height), (4, 4));
+        // Lowest height black, highest white
+        assert_eq!(pixels[..4], [0, 0, 0, 255]);
+        assert_eq!(pixels[60..], [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn measured_spectroscopy_round_trips_through_csv() {
+        let grid = STS::new(STSType::Grid { rows: 3, cols: 3 }, 0.0, 1.0, 0.5);
+        let point = STS::new(STSType::Point(Vector2::new(0.0, 0.0)), 0.0, 1.0, 0.5);
+        let mut image = image(vec![grid, point]);
+        let curves: Vec<Vec<f64>> = (0..9)
+            .map(|i| vec![f64::from(i), f64::from(10 + i), f64::from(20 + i)])
+            .collect();
+        // Only the grid was measured
+        image.set_measurement(Measurement {
+            data: vec![0.0; 4],
+            spectroscopy: vec![curves.clone()],
+        });
+        let dir = std::env::temp_dir().join("stm_controller_sts_csv");
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let written = image.export_spectroscopy_csv(&dir, "scan_0").unwrap();
+        let csv = std::fs::read_to_string(&written[0]).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(written, [dir.join("scan_0_sts_0.csv")]);
+        let lines: Vec<&str> = csv.lines().collect();
+        let header: Vec<&str> = lines[0].split(',').collect();
+        assert_eq!(header.len(), 10);
+        assert_eq!(header[0], "voltage");
+        assert_eq!(header[1], "grid 0 x=-0.00000005 y=0.00000005");
+        assert_eq!(header[5], "grid 4 x=0 y=0");
+
+        let rows: Vec<Vec<f64>> = lines[1..]
+            .iter()
+            .map(|line| line.split(',').map(|v| v.parse().unwrap()).collect())
+            .collect();
+        assert_eq!(rows.len(), 3);
+        for (row, voltage) in rows.iter().zip([0.0, 0.5, 1.0]) {
+            assert_eq!(row[0], voltage);
+        }
+        for (position, curve) in curves.iter().enumerate() {
+            let column: Vec<f64> = rows.iter().map(|row| row[position + 1]).collect();
+            assert_eq!(&column, curve);
+        }
+    }
 }
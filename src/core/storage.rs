@@ -0,0 +1,58 @@
+//! Disk space checks before long acquisitions.
+use std::path::Path;
+
+/// Directory acquisitions are written to.
+pub const DEFAULT_OUTPUT_DIR: &str = ".";
+/// Bytes stored per pixel of height data (one `f64`).
+pub const BYTES_PER_PIXEL: u64 = 8;
+/// Free space required relative to the estimate, leaving room for metadata,
+/// exports and other programs.
+pub const HEADROOM: f64 = 1.25;
+
+/// Bytes needed to store `images` square images of `lines` × `lines` pixels.
+pub fn estimate_bytes(lines: u32, images: usize) -> u64 {
+    (lines as u64)
+        .saturating_mul(lines as u64)
+        .saturating_mul(BYTES_PER_PIXEL)
+        .saturating_mul(images as u64)
+}
+
+/// Checks that the filesystem holding `dir` has room for `required` bytes plus
+/// [`HEADROOM`], returning a description of the shortfall otherwise.
+pub fn check_space(dir: &Path, required: u64) -> Result<(), String> {
+    let available = fs2::available_space(dir).map_err(|e| e.to_string())?;
+    let needed = (required as f64 * HEADROOM) as u64;
+
+    if available >= needed {
+        Ok(())
+    } else {
+        Err(format!(
+            "needs about {:.1} MB but only {:.1} MB is free in {}",
+            needed as f64 / 1e6,
+            available as f64 / 1e6,
+            dir.display()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn estimate_counts_every_pixel_of_every_image() {
+        assert_eq!(estimate_bytes(512, 10), 512 * 512 * 8 * 10);
+        assert_eq!(estimate_bytes(0, 10), 0);
+        assert_eq!(estimate_bytes(u32::MAX, usize::MAX), u64::MAX);
+    }
+
+    #[test]
+    fn runs_that_do_not_fit_are_refused() {
+        let dir = std::env::temp_dir();
+
+        assert_eq!(check_space(&dir, 0), Ok(()));
+        let refused = check_space(&dir, u64::MAX / 2).unwrap_err();
+        assert!(refused.starts_with("needs about"), "{refused}");
+        assert!(refused.ends_with(&dir.display().to_string()), "{refused}");
+    }
+}
@@ -0,0 +1,406 @@
+//! Generation of the bias values a task sweeps through.
+use crate::core::hardware;
+use std::io;
+use std::path::Path;
+
+/// How the bias values of a new task are chosen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SweepMode {
+    /// Evenly spaced from the start to the stop voltage.
+    #[default]
+    Range,
+    /// An explicit, comma-separated list of voltages.
+    List,
+}
+
+/// Bias values a new task expands into, one image per value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BiasSpec {
+    /// Evenly spaced values, as produced by [`range`].
+    Linear { start: f64, stop: f64, step: f64 },
+    /// `points` values spaced geometrically, as produced by [`log_range`].
+    Logarithmic {
+        start: f64,
+        stop: f64,
+        points: usize,
+    },
+    /// Exactly these values, in this order.
+    Explicit(Vec<f64>),
+}
+
+impl BiasSpec {
+    /// The values, or `None` when there are more than [`MAX_VALUES`].
+    pub fn values(&self) -> Option<Vec<f64>> {
+        if self.count() > MAX_VALUES {
+            return None;
+        }
+
+        Some(match self {
+            BiasSpec::Linear { start, stop, step } => range(*start, *stop, *step)?,
+            BiasSpec::Logarithmic {
+                start,
+                stop,
+                points,
+            } => log_range(*start, *stop, *points),
+            BiasSpec::Explicit(biases) => biases.clone(),
+        })
+    }
+
+    /// Number of values, without generating them.
+    pub fn count(&self) -> usize {
+        match self {
+            BiasSpec::Linear { start, stop, step } => count(*start, *stop, *step),
+            BiasSpec::Logarithmic {
+                start,
+                stop,
+                points,
+            } => check_log_range(*start, *stop).map_or(0, |_| *points),
+            BiasSpec::Explicit(biases) => biases.len(),
+        }
+    }
+}
+
+/// Order the bias values of a new task are scanned in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SweepDirection {
+    /// From the start to the stop voltage, or in list order.
+    #[default]
+    AsEntered,
+    /// Lowest voltage first.
+    Ascending,
+    /// Highest voltage first.
+    Descending,
+}
+
+impl SweepDirection {
+    pub const ALL: [SweepDirection; 3] = [
+        SweepDirection::AsEntered,
+        SweepDirection::Ascending,
+        SweepDirection::Descending,
+    ];
+
+    /// Puts `values` in this order.
+    pub fn apply(&self, mut values: Vec<f64>) -> Vec<f64> {
+        match self {
+            SweepDirection::AsEntered => {}
+            SweepDirection::Ascending => values.sort_by(f64::total_cmp),
+            SweepDirection::Descending => values.sort_by(|a, b| b.total_cmp(a)),
+        }
+        values
+    }
+}
+
+impl std::fmt::Display for SweepDirection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            SweepDirection::AsEntered => "As entered",
+            SweepDirection::Ascending => "Ascending",
+            SweepDirection::Descending => "Descending",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Most values a sweep is generated with. Far more images than could ever be
+/// scanned, yet small enough to allocate; a larger count means a mistyped
+/// step.
+pub const MAX_VALUES: usize = 100_000;
+
+/// Relative slack for spans that are a whole number of steps but lose a
+/// little to rounding, e.g. 0.3 / 0.1.
+const STEP_TOLERANCE: f64 = 1e-9;
+
+/// Number of values [`range`] produces: `start`, then one more per whole
+/// `step` towards `stop`, so `stop` itself is included when the span divides
+/// evenly. `start == stop` gives one value; a zero or non-finite step with
+/// distinct endpoints gives none.
+pub fn count(start: f64, stop: f64, step: f64) -> usize {
+    let span = (stop - start).abs();
+    let step = step.abs();
+
+    if span == 0.0 {
+        1
+    } else if step == 0.0 || !step.is_finite() || !span.is_finite() {
+        0
+    } else {
+        // Float-to-integer casts saturate, so an enormous sweep counts
+        // usize::MAX rather than a small wrapped count.
+        (((span / step) * (1.0 + STEP_TOLERANCE)).floor() as usize).saturating_add(1)
+    }
+}
+
+/// Values from `start` towards `stop`, `step` apart, as counted by [`count`].
+/// The sign of `step` is ignored: the values always head towards `stop`.
+/// `None` when there would be more than [`MAX_VALUES`], so that a mistyped
+/// step cannot exhaust memory.
+pub fn range(start: f64, stop: f64, step: f64) -> Option<Vec<f64>> {
+    let step = directed_step(start, stop, step);
+    let count = count(start, stop, step);
+    if count > MAX_VALUES {
+        return None;
+    }
+
+    Some((0..count).map(|i| start + i as f64 * step).collect())
+}
+
+/// `step` with its sign pointing from `start` towards `stop`, the spacing of
+/// consecutive values of [`range`].
+pub fn directed_step(start: f64, stop: f64, step: f64) -> f64 {
+    if stop < start {
+        -step.abs()
+    } else {
+        step.abs()
+    }
+}
+
+/// Whether `start` and `stop` can be spaced geometrically: both nonzero and
+/// of the same sign.
+pub fn check_log_range(start: f64, stop: f64) -> Result<(), String> {
+    if start == 0.0 || stop == 0.0 || !start.is_finite() || !stop.is_finite() {
+        Err(String::from("Log spacing needs nonzero voltages"))
+    } else if start.signum() != stop.signum() {
+        Err(String::from("Log spacing cannot cross zero volts"))
+    } else {
+        Ok(())
+    }
+}
+
+/// `points` values from `start` to `stop`, both included, with a constant
+/// ratio between neighbours, e.g. 0.01, 0.1, 1 for three points. Empty when
+/// [`check_log_range`] rejects the range.
+pub fn log_range(start: f64, stop: f64, points: usize) -> Vec<f64> {
+    if check_log_range(start, stop).is_err() {
+        return vec![];
+    }
+
+    let span = (stop / start).ln();
+    let last = points.saturating_sub(1).max(1) as f64;
+
+    (0..points)
+        .map(|i| {
+            if i + 1 == points && points > 1 {
+                stop
+            } else {
+                start * (span * i as f64 / last).exp()
+            }
+        })
+        .collect()
+}
+
+/// Every combination of an `outer` and an `inner` value, as `(outer, inner)`.
+///
+/// The inner value varies fastest, so a bias × set point grid scans every set
+/// point at the first bias before moving on to the next bias.
+pub fn grid(outer: &[f64], inner: &[f64]) -> Vec<(f64, f64)> {
+    outer
+        .iter()
+        .flat_map(|&o| inner.iter().map(move |&i| (o, i)))
+        .collect()
+}
+
+/// Parses a comma-separated list of bias voltages, e.g. `"-1, -0.5, 0, 0.5, 1"`.
+///
+/// Returns the accepted voltages in the order given, along with a description
+/// of every entry that was rejected for not being a number or for lying
+/// outside the hardware bias range. Empty entries are ignored.
+pub fn parse_bias_list(list: &str) -> (Vec<f64>, Vec<String>) {
+    let mut biases = vec![];
+    let mut rejects = vec![];
+
+    for entry in list.split(',').map(str::trim).filter(|entry| !entry.is_empty()) {
+        match entry.parse::<f64>() {
+            Ok(bias) => match hardware::check("bias", bias, hardware::BIAS) {
+                Ok(bias) => biases.push(bias),
+                Err(e) => rejects.push(e),
+            },
+            Err(_) => rejects.push(format!("\"{entry}\" is not a number")),
+        }
+    }
+
+    (biases, rejects)
+}
+
+/// Reads a bias list from a text file with one voltage per line, or several
+/// separated by commas, and joins it into the form [`parse_bias_list`] reads.
+pub fn load_bias_list(path: &Path) -> io::Result<String> {
+    let contents = std::fs::read_to_string(path)?;
+
+    Ok(contents
+        .lines()
+        .map(|line| line.trim().trim_end_matches(','))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn count_includes_stop_when_the_span_divides_evenly() {
+        assert_eq!(count(0.0, 1.0, 0.25), 5);
+        assert_eq!(count(0.0, 0.3, 0.1), 4);
+        assert_eq!(count(1.0, -1.0, 0.5), 5);
+    }
+
+    #[test]
+    fn count_stops_short_of_an_uneven_stop() {
+        assert_eq!(count(0.0, 1.0, 0.3), 4);
+        assert_eq!(range(0.0, 1.0, 0.3).unwrap().last(), Some(&(3.0 * 0.3)));
+    }
+
+    #[test]
+    fn zero_steps_and_empty_spans() {
+        assert_eq!(count(0.5, 0.5, 0.0), 1);
+        assert_eq!(count(0.5, 0.5, 0.1), 1);
+        assert_eq!(count(0.0, 1.0, 0.0), 0);
+        assert_eq!(range(0.0, 1.0, 0.0), Some(vec![]));
+    }
+
+    #[test]
+    fn range_heads_towards_stop_whatever_the_sign_of_step() {
+        assert_eq!(range(1.0, 0.0, 0.5), Some(vec![1.0, 0.5, 0.0]));
+        assert_eq!(range(0.0, 1.0, -0.5), Some(vec![0.0, 0.5, 1.0]));
+    }
+
+    #[test]
+    fn sweeps_too_long_to_generate_are_refused() {
+        // A 1 pV step over ±5 V
+        assert!(count(-5.0, 5.0, 1e-12) > MAX_VALUES);
+        assert_eq!(range(-5.0, 5.0, 1e-12), None);
+        let linear = BiasSpec::Linear {
+            start: -5.0,
+            stop: 5.0,
+            step: 1e-12,
+        };
+        assert_eq!(linear.values(), None);
+
+        let logarithmic = BiasSpec::Logarithmic {
+            start: 0.1,
+            stop: 1.0,
+            points: MAX_VALUES + 1,
+        };
+        assert_eq!(logarithmic.values(), None);
+
+        let longest = range(0.0, 1.0, 1.0 / (MAX_VALUES - 1) as f64).unwrap();
+        assert_eq!(longest.len(), MAX_VALUES);
+    }
+
+    #[test]
+    fn counts_past_u16_do_not_wrap() {
+        assert_eq!(count(0.0, 65534.0, 1.0), 65535);
+        assert_eq!(count(0.0, 65535.0, 1.0), 65536);
+        assert_eq!(count(0.0, 1e6, 1.0), 1_000_001);
+        assert_eq!(range(0.0, 65535.0, 1.0).unwrap().len(), 65536);
+    }
+
+    #[test]
+    fn count_saturates_instead_of_overflowing() {
+        assert_eq!(count(0.0, 1e300, 1e-300), usize::MAX);
+    }
+
+    #[test]
+    fn bias_list_keeps_valid_voltages_in_order() {
+        let (biases, rejects) = parse_bias_list("-1, -0.5,0 , 0.5, 1");
+        assert_eq!(biases, [-1.0, -0.5, 0.0, 0.5, 1.0]);
+        assert!(rejects.is_empty());
+    }
+
+    #[test]
+    fn bias_list_rejects_non_numbers_and_out_of_range_voltages() {
+        let (biases, rejects) = parse_bias_list("0.1, abc, 7, -5, 1V");
+        assert_eq!(biases, [0.1, -5.0]);
+        assert_eq!(
+            rejects,
+            [
+                "\"abc\" is not a number",
+                "bias 7 outside [-5, 5]",
+                "\"1V\" is not a number",
+            ]
+        );
+    }
+
+    #[test]
+    fn bias_list_skips_empty_entries() {
+        assert_eq!(parse_bias_list("0.1,, ,0.2,"), (vec![0.1, 0.2], vec![]));
+        assert_eq!(parse_bias_list(""), (vec![], vec![]));
+    }
+
+    #[test]
+    fn bias_list_file_is_joined_into_one_list() {
+        let path = std::env::temp_dir().join("stm_controller_test_biases.txt");
+        std::fs::write(&path, "-1\n-0.5,\n\n0, 0.5\n  1  \n").unwrap();
+
+        let list = load_bias_list(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(list.unwrap(), "-1, -0.5, 0, 0.5, 1");
+        assert!(load_bias_list(&path).is_err());
+    }
+
+    #[test]
+    fn grid_varies_the_inner_value_fastest() {
+        assert_eq!(
+            grid(&[1.0, 2.0, 3.0], &[10.0, 20.0]),
+            [
+                (1.0, 10.0),
+                (1.0, 20.0),
+                (2.0, 10.0),
+                (2.0, 20.0),
+                (3.0, 10.0),
+                (3.0, 20.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn direction_orders_a_descending_range() {
+        let values = range(1.0, -1.0, 0.5).unwrap();
+
+        assert_eq!(
+            SweepDirection::AsEntered.apply(values.clone()),
+            [1.0, 0.5, 0.0, -0.5, -1.0]
+        );
+        assert_eq!(
+            SweepDirection::Ascending.apply(values.clone()),
+            [-1.0, -0.5, 0.0, 0.5, 1.0]
+        );
+        assert_eq!(
+            SweepDirection::Descending.apply(values),
+            [1.0, 0.5, 0.0, -0.5, -1.0]
+        );
+    }
+
+    #[test]
+    fn log_range_spaces_values_by_decades() {
+        let values = log_range(0.01, 1.0, 3);
+        assert_eq!(values.len(), 3);
+        for (value, expected) in values.iter().zip([0.01, 0.1, 1.0]) {
+            assert!((value - expected).abs() < 1e-12, "{value} != {expected}");
+        }
+
+        let negative = log_range(-0.01, -1.0, 3);
+        assert!((negative[1] + 0.1).abs() < 1e-12);
+    }
+
+    #[test]
+    fn log_range_crossing_zero_is_rejected() {
+        assert_eq!(
+            check_log_range(-0.1, 1.0),
+            Err(String::from("Log spacing cannot cross zero volts"))
+        );
+        assert_eq!(
+            check_log_range(0.0, 1.0),
+            Err(String::from("Log spacing needs nonzero voltages"))
+        );
+        assert!(log_range(-0.1, 1.0, 3).is_empty());
+
+        let spec = BiasSpec::Logarithmic {
+            start: -0.1,
+            stop: 1.0,
+            points: 3,
+        };
+        assert_eq!(spec.count(), 0);
+    }
+}
@@ -0,0 +1,92 @@
+//! Synthetic height data for exercising the plot without hardware.
+use std::f32::consts::PI;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TestPattern {
+    /// Squares `period` pixels wide alternating between 0 and `amplitude`.
+    Checkerboard,
+    /// A ramp from 0 at the left edge to `amplitude` at the right edge.
+    Gradient,
+    /// A square atomic lattice, `amplitude * cos(kx) * cos(ky)` with a lattice
+    /// constant of `period` pixels, spanning `[-amplitude, amplitude]`.
+    Lattice,
+}
+
+impl TestPattern {
+    pub const ALL: [TestPattern; 3] = [
+        TestPattern::Checkerboard,
+        TestPattern::Gradient,
+        TestPattern::Lattice,
+    ];
+
+    /// Generates a `lines` × `lines` image in row-major order.
+    pub fn generate(&self, lines: u32, period: f32, amplitude: f32) -> Vec<f32> {
+        let lines = lines as usize;
+        let period = period.max(1.0);
+        let k = 2.0 * PI / period;
+
+        (0..lines * lines)
+            .map(|i| {
+                let (row, col) = ((i / lines) as f32, (i % lines) as f32);
+
+                match self {
+                    TestPattern::Checkerboard => {
+                        let cell = (row / period).floor() + (col / period).floor();
+                        if cell as u32 % 2 == 0 {
+                            0.0
+                        } else {
+                            amplitude
+                        }
+                    }
+                    TestPattern::Gradient => amplitude * col / (lines.max(2) - 1) as f32,
+                    TestPattern::Lattice => amplitude * (k * col).cos() * (k * row).cos(),
+                }
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for TestPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            TestPattern::Checkerboard => "Checkerboard",
+            TestPattern::Gradient => "Gradient",
+            TestPattern::Lattice => "Atomic lattice",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn patterns_fill_the_image_within_their_amplitude() {
+        for pattern in TestPattern::ALL {
+            let data = pattern.generate(16, 4.0, 2.0);
+
+            assert_eq!(data.len(), 16 * 16, "{pattern}");
+            let min = data.iter().copied().fold(f32::MAX, f32::min);
+            let max = data.iter().copied().fold(f32::MIN, f32::max);
+            let lower = match pattern {
+                TestPattern::Lattice => -2.0,
+                _ => 0.0,
+            };
+            assert!(min >= lower - 1e-6 && max <= 2.0 + 1e-6, "{pattern}");
+            assert!(max - min > 1.0, "{pattern} is flat");
+        }
+    }
+
+    #[test]
+    fn checkerboard_and_gradient_hit_their_extremes() {
+        let checkerboard = TestPattern::Checkerboard.generate(4, 2.0, 1.0);
+        assert_eq!(checkerboard[..4], [0.0, 0.0, 1.0, 1.0]);
+        assert_eq!(checkerboard[8..12], [1.0, 1.0, 0.0, 0.0]);
+
+        let gradient = TestPattern::Gradient.generate(5, 1.0, 8.0);
+        assert_eq!(gradient[..5], [0.0, 2.0, 4.0, 6.0, 8.0]);
+        assert_eq!(TestPattern::Lattice.generate(4, 4.0, 3.0)[0], 3.0);
+    }
+}
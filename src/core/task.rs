@@ -1,13 +1,24 @@
-use iced::widget::{horizontal_space, row, text};
-use iced::{Element, Length};
+use iced::widget::{button, column, horizontal_space, pick_list, row, text, text_input};
+use iced::{theme, Alignment, Element, Length};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
 
+use crate::core::eta::format_duration;
+use crate::core::export::OutputFormat;
 use crate::core::icons::*;
-use crate::native::taskdisplay::TaskDisplay;
+use crate::native::taskdisplay::{Mode, TaskDisplay};
 use crate::style::taskdisplay::TaskDisplayStyles;
 
+#[derive(Serialize, Deserialize)]
 pub struct TaskList<T> {
     pub tasks: Vec<Task<T>>,
     pub current_task: Option<usize>,
+    /// Row highlighted for keyboard actions, moved with the arrow keys while
+    /// the task list has focus.
+    #[serde(skip)]
+    pub selected: Option<usize>,
 }
 
 impl<T> Default for TaskList<T> {
@@ -15,22 +26,91 @@ impl<T> Default for TaskList<T> {
         Self {
             tasks: Vec::default(),
             current_task: None,
+            selected: None,
         }
     }
 }
 
-#[derive(Debug, Clone)]
+/// Source of [`Task::id`], counting up over the life of the process.
+static NEXT_TASK_ID: AtomicU64 = AtomicU64::new(0);
+
+fn next_task_id() -> u64 {
+    NEXT_TASK_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Content of a task that [`TaskList::duplicate`] can queue again.
+pub trait Requeue {
+    /// A copy to run again, without the results of an earlier run.
+    fn requeued(&self) -> Self;
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Task<T> {
     content: Vec<T>,
     description: String,
     index: usize,
+    /// Stays with the task while `index` follows it around the queue.
+    #[serde(skip, default = "next_task_id")]
+    id: u64,
     state: TaskState,
+    #[serde(default)]
+    notes: String,
+    /// Files written once the task completes.
+    #[serde(default)]
+    output_format: OutputFormat,
+    /// Percentage of the task done, from 0 to 100.
+    #[serde(skip)]
+    progress: f32,
+    /// When the task first started running since it was last idle.
+    #[serde(skip)]
+    started_at: Option<Instant>,
+    /// When the task completed or failed.
+    #[serde(skip)]
+    finished_at: Option<Instant>,
+    #[serde(skip)]
+    editing: bool,
+    /// Whether the images are listed under the edit row.
+    #[serde(skip)]
+    show_images: bool,
 }
 
-#[derive(Debug, Clone)]
+/// Where [`TaskList::insert`] places a new task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InsertPosition {
+    /// After every other task.
+    #[default]
+    End,
+    /// Directly after the current task.
+    AfterCurrent,
+    /// Ahead of every task that has not started, so it runs next.
+    Front,
+}
+
+impl InsertPosition {
+    pub const ALL: [InsertPosition; 3] = [
+        InsertPosition::End,
+        InsertPosition::AfterCurrent,
+        InsertPosition::Front,
+    ];
+}
+
+impl std::fmt::Display for InsertPosition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            InsertPosition::End => "Add at end",
+            InsertPosition::AfterCurrent => "Add after current",
+            InsertPosition::Front => "Add to front",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum TaskState {
     Idle,
     Running,
+    /// Started but suspended between images until play is pressed again.
+    Paused,
     Completed,
     Failed(String),
 }
@@ -40,6 +120,21 @@ pub enum TaskMessage {
     Finished,
     Edit,
     Delete,
+    Select,
+    /// Export the task's images in its output format.
+    Save,
+    /// Run a failed task again from the start.
+    Retry,
+    /// Queue a copy of the task right after it.
+    Duplicate,
+    /// Show or hide the task's images.
+    ToggleImages,
+    /// Leave out the image at this index, before the task runs.
+    RemoveImage(usize),
+    /// New notes for the image at this index.
+    ImageNotesChanged(usize, String),
+    NotesChanged(String),
+    OutputFormatSelected(OutputFormat),
 }
 
 impl Default for TaskState {
@@ -48,72 +143,505 @@ impl Default for TaskState {
     }
 }
 
+impl<T> TaskList<T> {
+    /// Inserts `task` at `position` and returns its index. Every task is
+    /// re-indexed and the selection keeps pointing at the same task; the
+    /// current task only changes when [`InsertPosition::Front`] puts the new
+    /// task ahead of an idle current task.
+    pub fn insert(&mut self, mut task: Task<T>, position: InsertPosition) -> usize {
+        let current = self.current_task;
+        let current_idle = current.map_or(false, |c| self.tasks[c].is_idle());
+
+        let idx = match (position, current) {
+            (InsertPosition::End, _) | (_, None) => self.tasks.len(),
+            (InsertPosition::AfterCurrent, Some(c)) => c + 1,
+            (InsertPosition::Front, Some(c)) if current_idle => c,
+            (InsertPosition::Front, Some(c)) => c + 1,
+        };
+
+        task.index = idx;
+        self.tasks.insert(idx, task);
+        self.reindex();
+
+        if let Some(selected) = self.selected.filter(|&s| s >= idx) {
+            self.selected = Some(selected + 1);
+        }
+        if self.current_task.is_none() {
+            self.current_task = Some(0);
+        }
+
+        idx
+    }
+
+    /// Puts `task` at exactly `idx` and re-indexes the queue. Tasks after it,
+    /// and the current task and selection with them, move down one place; a
+    /// current task at `idx` becomes the new task.
+    pub fn insert_at(&mut self, idx: usize, task: Task<T>) {
+        self.tasks.insert(idx, task);
+        self.reindex();
+
+        if let Some(current) = self.current_task.filter(|&c| c > idx) {
+            self.current_task = Some(current + 1);
+        }
+        if let Some(selected) = self.selected.filter(|&s| s >= idx) {
+            self.selected = Some(selected + 1);
+        }
+        if self.current_task.is_none() {
+            self.current_task = Some(0);
+        }
+    }
+
+    /// Takes the task at `idx` out of the queue and re-indexes the rest. A
+    /// current task at `idx` moves on to the task that takes its place, and a
+    /// selection of it is cleared.
+    pub fn remove(&mut self, idx: usize) -> Task<T> {
+        let task = self.tasks.remove(idx);
+        self.reindex();
+
+        self.current_task = match self.current_task {
+            _ if self.tasks.is_empty() => None,
+            Some(current) if current > idx => Some(current - 1),
+            Some(current) => Some(current.min(self.tasks.len() - 1)),
+            None => None,
+        };
+        self.selected = match self.selected {
+            Some(selected) if selected == idx => None,
+            Some(selected) if selected > idx => Some(selected - 1),
+            selected => selected,
+        };
+
+        task
+    }
+
+    /// Removes every completed task and re-indexes the rest. The current task
+    /// keeps pointing at the same task or, if it was removed, moves to the
+    /// next one left; a removed selection is cleared.
+    ///
+    /// Indices of the remaining tasks change, so this must not be called
+    /// while tasks are running.
+    pub fn clear_completed(&mut self) {
+        let kept: Vec<bool> = self
+            .tasks
+            .iter()
+            .map(|task| !matches!(task.state, TaskState::Completed))
+            .collect();
+        // Position in the cleared list of the first task kept from `i` on
+        let remap = |i: usize| kept[..i].iter().filter(|&&k| k).count();
+
+        self.current_task = self
+            .current_task
+            .map(remap)
+            .filter(|_| kept.iter().any(|&k| k));
+        self.selected = self.selected.filter(|&s| kept[s]).map(remap);
+
+        let mut kept = kept.into_iter();
+        self.tasks.retain(|_| kept.next().unwrap_or(true));
+        self.reindex();
+
+        if let Some(current) = self.current_task {
+            self.current_task = Some(current.min(self.tasks.len() - 1));
+        }
+    }
+
+    /// Where the task with `id` currently sits in the queue.
+    pub fn position(&self, id: u64) -> Option<usize> {
+        self.tasks.iter().position(|task| task.id == id)
+    }
+
+    /// Brings each task's index back in line with its position in the list.
+    pub fn reindex(&mut self) {
+        for (i, task) in self.tasks.iter_mut().enumerate() {
+            task.index = i;
+        }
+    }
+
+    /// Percentage of the whole queue done, each task weighted by its number
+    /// of images. An empty queue reads as no progress.
+    pub fn overall_progress(&self) -> f32 {
+        let images: usize = self.tasks.iter().map(|task| task.content.len()).sum();
+        if images == 0 {
+            return 0.0;
+        }
+
+        let done: f32 = self
+            .tasks
+            .iter()
+            .map(|task| task.content.len() as f32 * task.progress)
+            .sum();
+
+        done / images as f32
+    }
+
+    /// Moves the selection down one row, clamping at the last task.
+    pub fn select_next(&mut self) {
+        if self.tasks.is_empty() {
+            return;
+        }
+        self.selected = Some(self.selected.map_or(0, |i| (i + 1).min(self.tasks.len() - 1)));
+    }
+
+    /// Moves the selection up one row, clamping at the first task.
+    pub fn select_previous(&mut self) {
+        if self.tasks.is_empty() {
+            return;
+        }
+        self.selected = Some(
+            self.selected
+                .map_or(self.tasks.len() - 1, |i| i.saturating_sub(1)),
+        );
+    }
+}
+
+impl<T> TaskList<T>
+where
+    T: Clone + Requeue,
+{
+    /// Inserts an idle copy of the task at `idx` right after it, with
+    /// " (copy)" added to its description, and returns the copy's index. The
+    /// copy's content is [`Requeue::requeued`], so nothing measured by the
+    /// original carries over.
+    pub fn duplicate(&mut self, idx: usize) -> usize {
+        // Keep the content out of the clone, it may hold whole scans
+        let content = std::mem::take(&mut self.tasks[idx].content);
+        let mut copy = self.tasks[idx].clone();
+        copy.content = content.iter().map(T::requeued).collect();
+        self.tasks[idx].content = content;
+
+        copy.id = next_task_id();
+        copy.description.push_str(" (copy)");
+        copy.editing = false;
+        copy.state(TaskState::Idle);
+
+        let copy_idx = idx + 1;
+        self.tasks.insert(copy_idx, copy);
+        self.reindex();
+
+        if let Some(current) = self.current_task.filter(|&c| c >= copy_idx) {
+            self.current_task = Some(current + 1);
+        }
+        if let Some(selected) = self.selected.filter(|&s| s >= copy_idx) {
+            self.selected = Some(selected + 1);
+        }
+
+        copy_idx
+    }
+}
+
+impl<T> TaskList<T>
+where
+    T: Serialize,
+{
+    /// Writes the queue, including each task's notes, to a JSON session file.
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json)
+    }
+}
+
+impl<T> TaskList<T>
+where
+    T: DeserializeOwned,
+{
+    /// Reads a queue previously written by [`TaskList::save`].
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        let json = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&json)?)
+    }
+}
+
 impl<T> Task<T> {
     pub fn new(content: Vec<T>, description: String, index: usize) -> Self {
         Self {
             content,
             description,
             index,
+            id: next_task_id(),
             state: TaskState::Idle,
+            notes: String::new(),
+            output_format: OutputFormat::default(),
+            progress: 0.0,
+            started_at: None,
+            finished_at: None,
+            editing: false,
+            show_images: false,
         }
     }
 
     pub fn update(&mut self, msg: TaskMessage) {
         match msg {
             TaskMessage::Finished => {
-                self.state = TaskState::Completed;
+                self.state(TaskState::Completed);
+            }
+            TaskMessage::Retry if self.is_failed() => {
+                self.state(TaskState::Idle);
+            }
+            TaskMessage::Edit => {
+                self.editing = !self.editing;
+            }
+            TaskMessage::NotesChanged(notes) => {
+                self.notes = notes;
+            }
+            TaskMessage::OutputFormatSelected(format) => {
+                self.output_format = format;
+            }
+            TaskMessage::ToggleImages => {
+                self.show_images = !self.show_images;
+            }
+            TaskMessage::RemoveImage(i) if self.is_idle() => {
+                self.remove_image(i);
             }
             _ => {}
         }
     }
 
-    pub fn view(&self) -> Element<TaskMessage> {
+    /// Shows the task, with its edit row when editing. Listed images are
+    /// named by `label`, next to their `notes`.
+    pub fn view(
+        &self,
+        selected: bool,
+        label: impl Fn(&T) -> String,
+        notes: impl Fn(&T) -> &str,
+    ) -> Element<TaskMessage> {
+        let display = self.display(selected);
+
+        if self.editing {
+            let mut edit = column![
+                display,
+                row![
+                    text_input("Notes...", &self.notes)
+                        .on_input(TaskMessage::NotesChanged)
+                        .padding(10),
+                    pick_list(
+                        &OutputFormat::ALL[..],
+                        Some(self.output_format),
+                        TaskMessage::OutputFormatSelected
+                    ),
+                    button("Save").on_press(TaskMessage::Save),
+                    button("Duplicate").on_press(TaskMessage::Duplicate),
+                    button("Delete").on_press(TaskMessage::Delete),
+                    button(text(format!("Images ({})", self.content.len())))
+                        .on_press(TaskMessage::ToggleImages),
+                ]
+                .spacing(5),
+            ]
+            .spacing(5);
+
+            if self.show_images {
+                // The last image stays, and only tasks yet to run can change
+                let removable = self.is_idle() && self.content.len() > 1;
+                edit = edit.push(column(
+                    self.content
+                        .iter()
+                        .enumerate()
+                        .map(|(i, image)| {
+                            let mut remove = button("Remove").padding(0).style(theme::Button::Text);
+                            if removable {
+                                remove = remove.on_press(TaskMessage::RemoveImage(i));
+                            }
+                            row![
+                                text(label(image)),
+                                text_input("Image notes...", notes(image))
+                                    .on_input(move |notes| TaskMessage::ImageNotesChanged(i, notes))
+                                    .padding(2),
+                                remove
+                            ]
+                            .spacing(5)
+                            .align_items(Alignment::Center)
+                            .into()
+                        })
+                        .collect(),
+                ));
+            }
+            edit.into()
+        } else {
+            display
+        }
+    }
+
+    /// Colors and fill of the task bar: empty while idle, sweeping while a
+    /// running task has no progress yet, full once completed, and otherwise
+    /// filled up to the progress.
+    fn bar(&self) -> (TaskDisplayStyles, Mode) {
+        match self.state {
+            TaskState::Idle => (TaskDisplayStyles::Waiting, Mode::Determinate(0.0)),
+            TaskState::Running if self.progress > 0.0 => {
+                (TaskDisplayStyles::Running, Mode::Determinate(self.progress))
+            }
+            TaskState::Running => (TaskDisplayStyles::Running, Mode::Indeterminate),
+            TaskState::Paused => (TaskDisplayStyles::Paused, Mode::Determinate(self.progress)),
+            TaskState::Completed => (TaskDisplayStyles::Completed, Mode::Determinate(100.0)),
+            TaskState::Failed(_) => (TaskDisplayStyles::Failed, Mode::Determinate(self.progress)),
+        }
+    }
+
+    fn display(&self, selected: bool) -> Element<TaskMessage> {
+        let (style, mode) = self.bar();
+        let menu = || {
+            button(three_dots_vertical_icon())
+                .on_press(TaskMessage::Edit)
+                .padding(0)
+                .style(theme::Button::Text)
+        };
+
         match &self.state {
             TaskState::Idle => TaskDisplay::new(row![
                 circle_icon(),
                 horizontal_space(Length::Fill),
                 text(&self.description).size(20),
                 horizontal_space(Length::Fill),
-                three_dots_vertical_icon(),
+                menu(),
             ])
-            .value(0.0)
+            .on_press(TaskMessage::Select)
+            .selected(selected)
+            .mode(mode)
+            .style(style)
             .into(),
             TaskState::Running => TaskDisplay::new(row![
                 running_icon(),
                 horizontal_space(Length::Fill),
+                column![
+                    text(self.description.clone()).size(20),
+                    text(self.running_time(Instant::now())).size(14),
+                ]
+                .align_items(Alignment::Center),
+                horizontal_space(Length::Fill),
+                menu(),
+            ])
+            .on_press(TaskMessage::Select)
+            .selected(selected)
+            .mode(mode)
+            .show_percentage(true)
+            .style(style)
+            .into(),
+            TaskState::Paused => TaskDisplay::new(row![
+                pause_icon(),
+                horizontal_space(Length::Fill),
                 text(self.description.clone()).size(20),
                 horizontal_space(Length::Fill),
-                three_dots_vertical_icon(),
+                menu(),
             ])
-            .value(50.0)
-            .style(TaskDisplayStyles::Running)
+            .on_press(TaskMessage::Select)
+            .selected(selected)
+            .mode(mode)
+            .show_percentage(true)
+            .style(style)
             .into(),
             TaskState::Completed => TaskDisplay::new(row![
                 completed_icon(),
                 horizontal_space(Length::Fill),
-                text(self.description.clone()).size(20),
+                column![
+                    text(self.description.clone()).size(20),
+                    text(format!(
+                        "took {}",
+                        duration_text(self.elapsed_at(Instant::now()))
+                    ))
+                    .size(14),
+                ]
+                .align_items(Alignment::Center),
                 horizontal_space(Length::Fill),
-                three_dots_vertical_icon(),
+                menu(),
             ])
-            .style(TaskDisplayStyles::Completed)
+            .on_press(TaskMessage::Select)
+            .selected(selected)
+            .mode(mode)
+            .style(style)
             .into(),
             TaskState::Failed(error) => TaskDisplay::new(row![
                 failed_icon(),
                 horizontal_space(Length::Fill),
-                text(self.description.clone()).size(20),
+                column![
+                    text(self.description.clone()).size(20),
+                    text(error).size(14),
+                ]
+                .align_items(Alignment::Center),
                 horizontal_space(Length::Fill),
-                three_dots_vertical_icon(),
+                button("Retry")
+                    .on_press(TaskMessage::Retry)
+                    .padding(0)
+                    .style(theme::Button::Text),
+                menu(),
             ])
-            .value(66.0)
-            .style(TaskDisplayStyles::Failed)
+            .on_press(TaskMessage::Select)
+            .selected(selected)
+            .mode(mode)
+            .style(style)
             .into(),
         }
     }
 
+    /// Changes the state of the task. Idle tasks start over from no progress
+    /// and completed ones are done; otherwise the progress is kept, so a failed
+    /// task shows how far it got.
     pub fn state(&mut self, state: TaskState) {
+        self.state_at(state, Instant::now())
+    }
+
+    /// Changes the state of the task as of `now`, timing it from when it
+    /// first runs until it completes or fails.
+    pub fn state_at(&mut self, state: TaskState, now: Instant) {
+        match state {
+            TaskState::Idle => {
+                self.progress = 0.0;
+                self.started_at = None;
+                self.finished_at = None;
+            }
+            TaskState::Running => {
+                self.started_at.get_or_insert(now);
+            }
+            TaskState::Completed => {
+                self.progress = 100.0;
+                self.finished_at = Some(now);
+            }
+            TaskState::Failed(_) => self.finished_at = Some(now),
+            TaskState::Paused => {}
+        }
         self.state = state
     }
 
+    /// Time spent since the task started, up to `now` or until it finished.
+    /// `None` if it never ran.
+    pub fn elapsed_at(&self, now: Instant) -> Option<Duration> {
+        let started_at = self.started_at?;
+        Some(
+            self.finished_at
+                .unwrap_or(now)
+                .saturating_duration_since(started_at),
+        )
+    }
+
+    /// Time left at `now`, extrapolated from the elapsed time and progress.
+    /// `None` before any progress or once the task finished.
+    pub fn remaining_at(&self, now: Instant) -> Option<Duration> {
+        if self.finished_at.is_some() || self.progress <= 0.0 {
+            return None;
+        }
+        let elapsed = self.elapsed_at(now)?.as_secs_f64();
+        let progress = f64::from(self.progress);
+
+        Some(Duration::from_secs_f64(
+            elapsed * (100.0 - progress) / progress,
+        ))
+    }
+
+    /// Elapsed and remaining time of a running task, e.g.
+    /// `"00:01:05 elapsed, 00:03:12 left"`.
+    pub fn running_time(&self, now: Instant) -> String {
+        format!(
+            "{} elapsed, {} left",
+            duration_text(self.elapsed_at(now)),
+            duration_text(self.remaining_at(now))
+        )
+    }
+
+    /// Sets how much of the task is done, as a percentage clamped to 0–100.
+    pub fn set_progress(&mut self, progress: f32) {
+        self.progress = progress.clamp(0.0, 100.0);
+    }
+
+    pub fn progress(&self) -> f32 {
+        self.progress
+    }
+
     pub fn is_idle(&self) -> bool {
         match self.state {
             TaskState::Idle => true,
@@ -121,7 +649,349 @@ impl<T> Task<T> {
         }
     }
 
+    pub fn is_running(&self) -> bool {
+        match self.state {
+            TaskState::Running => true,
+            _ => false,
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        match self.state {
+            TaskState::Paused => true,
+            _ => false,
+        }
+    }
+
+    pub fn is_failed(&self) -> bool {
+        matches!(self.state, TaskState::Failed(_))
+    }
+
     pub fn content(&self) -> &Vec<T> {
         &self.content
     }
+
+    pub fn content_mut(&mut self) -> &mut Vec<T> {
+        &mut self.content
+    }
+
+    /// Drops the image at `i`, if there is one.
+    pub fn remove_image(&mut self, i: usize) {
+        if i < self.content.len() {
+            self.content.remove(i);
+        }
+    }
+
+    pub fn output_format(&self) -> OutputFormat {
+        self.output_format
+    }
+
+    pub fn set_output_format(&mut self, format: OutputFormat) {
+        self.output_format = format;
+    }
+
+    pub fn notes(&self) -> &str {
+        &self.notes
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Names the task for as long as the app runs, wherever it moves in the
+    /// queue. Not saved: loaded tasks get new ids.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn status(&self) -> &TaskState {
+        &self.state
+    }
+}
+
+/// Formats `duration` with [`format_duration`], unknown when `None`.
+fn duration_text(duration: Option<Duration>) -> String {
+    format_duration(duration.map_or(f64::NAN, |duration| duration.as_secs_f64()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::stmimage::{Measurement, STMImage};
+
+    impl Requeue for f64 {
+        fn requeued(&self) -> Self {
+            *self
+        }
+    }
+
+    #[test]
+    fn notes_round_trip_through_a_session_file() {
+        let mut list = TaskList::default();
+        let mut task = Task::new(vec![1.0_f64, 2.0], String::from("overnight"), 0);
+        task.update(TaskMessage::NotesChanged(String::from("tip crashed here")));
+        list.insert(task, InsertPosition::End);
+
+        let path = std::env::temp_dir().join("stm_controller_notes_round_trip.json");
+        list.save(&path).unwrap();
+        let loaded = TaskList::<f64>::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.tasks.len(), 1);
+        assert_eq!(loaded.tasks[0].notes(), "tip crashed here");
+        assert_eq!(loaded.tasks[0].content(), &vec![1.0, 2.0]);
+    }
+
+    #[test]
+    fn tasks_saved_without_notes_load_with_none() {
+        let json = r#"{
+            "content": [1.0],
+            "description": "old session",
+            "index": 0,
+            "state": "Idle"
+        }"#;
+
+        let task: Task<f64> = serde_json::from_str(json).unwrap();
+
+        assert_eq!(task.notes(), "");
+    }
+
+    #[test]
+    fn ids_follow_tasks_shifted_by_inserts() {
+        let mut list = TaskList::default();
+        list.insert(
+            Task::new(vec![1.0_f64], String::from("first"), 0),
+            InsertPosition::End,
+        );
+        let first = list.tasks[0].id();
+
+        list.insert(
+            Task::new(vec![2.0], String::from("second"), 0),
+            InsertPosition::Front,
+        );
+        assert_eq!(list.position(first), Some(1));
+
+        let copy = list.duplicate(1);
+        assert_ne!(list.tasks[copy].id(), first);
+        assert_eq!(list.position(first), Some(1));
+    }
+
+    /// Descriptions of the tasks in `list`, in queue order.
+    fn order(list: &TaskList<f64>) -> Vec<&str> {
+        list.tasks.iter().map(Task::description).collect()
+    }
+
+    /// A queue of "a", "b" and "c" with "b" current.
+    fn queue() -> TaskList<f64> {
+        let mut list = TaskList::default();
+        for name in ["a", "b", "c"] {
+            let task = Task::new(vec![], String::from(name), 0);
+            list.insert(task, InsertPosition::End);
+        }
+        list.current_task = Some(1);
+        list
+    }
+
+    #[test]
+    fn new_tasks_go_where_the_position_says() {
+        let new = || Task::new(vec![], String::from("new"), 0);
+
+        let mut list = queue();
+        assert_eq!(list.insert(new(), InsertPosition::End), 3);
+        assert_eq!(order(&list), ["a", "b", "c", "new"]);
+
+        let mut list = queue();
+        assert_eq!(list.insert(new(), InsertPosition::AfterCurrent), 2);
+        assert_eq!(order(&list), ["a", "b", "new", "c"]);
+        assert_eq!(list.current_task, Some(1));
+
+        // Ahead of an idle current task, the new one runs next
+        let mut list = queue();
+        assert_eq!(list.insert(new(), InsertPosition::Front), 1);
+        assert_eq!(order(&list), ["a", "new", "b", "c"]);
+        assert_eq!(list.current_task, Some(1));
+
+        // A running task is left to finish first
+        let mut list = queue();
+        list.tasks[1].state(TaskState::Running);
+        assert_eq!(list.insert(new(), InsertPosition::Front), 2);
+        assert_eq!(order(&list), ["a", "b", "new", "c"]);
+
+        let indices: Vec<usize> = list.tasks.iter().map(Task::index).collect();
+        assert_eq!(indices, [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn overall_progress_weighs_tasks_by_their_images() {
+        let mut list = TaskList::<f64>::default();
+        assert_eq!(list.overall_progress(), 0.0);
+
+        for images in [2, 2, 4] {
+            let task = Task::new(vec![0.0; images], String::new(), 0);
+            list.insert(task, InsertPosition::End);
+        }
+        list.tasks[0].state(TaskState::Completed);
+        list.tasks[1].state(TaskState::Running);
+        list.tasks[1].set_progress(50.0);
+        assert_eq!(list.overall_progress(), 37.5);
+
+        for task in &mut list.tasks {
+            task.state(TaskState::Completed);
+        }
+        assert_eq!(list.overall_progress(), 100.0);
+    }
+
+    #[test]
+    fn clearing_drops_only_completed_tasks() {
+        let mut list = TaskList::default();
+        let states = [
+            TaskState::Completed,
+            TaskState::Idle,
+            TaskState::Running,
+            TaskState::Completed,
+            TaskState::Idle,
+        ];
+        for (i, state) in states.into_iter().enumerate() {
+            let mut task = Task::new(vec![i as f64], format!("task {i}"), 0);
+            task.state(state);
+            list.insert(task, InsertPosition::End);
+        }
+        list.current_task = Some(2);
+        list.selected = Some(3);
+
+        list.clear_completed();
+
+        assert_eq!(order(&list), ["task 1", "task 2", "task 4"]);
+        let indices: Vec<usize> = list.tasks.iter().map(Task::index).collect();
+        assert_eq!(indices, [0, 1, 2]);
+        assert!(list.tasks[1].is_running());
+        assert_eq!(list.current_task, Some(1));
+        assert_eq!(list.selected, None);
+    }
+
+    #[test]
+    fn running_time_follows_a_mock_clock() {
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        let mut task = Task::new(vec![0.0_f64], String::new(), 0);
+        assert_eq!(task.elapsed_at(at(10)), None);
+
+        task.state_at(TaskState::Running, start);
+        task.set_progress(25.0);
+        assert_eq!(task.running_time(at(65)), "00:01:05 elapsed, 00:03:15 left");
+
+        // The clock stops once the task is done
+        task.state_at(TaskState::Completed, at(100));
+        assert_eq!(task.elapsed_at(at(500)), Some(Duration::from_secs(100)));
+        assert_eq!(task.remaining_at(at(500)), None);
+    }
+
+    #[test]
+    fn removing_an_image_shrinks_the_task_and_the_queue() {
+        let mut list = TaskList::default();
+        let sweep = Task::new(vec![-1.0, -0.5, 0.0, 0.5, 1.0], String::from("sweep"), 0);
+        list.insert(sweep, InsertPosition::End);
+        let mut done = Task::new(vec![2.0; 5], String::from("done"), 0);
+        done.state(TaskState::Completed);
+        list.insert(done, InsertPosition::End);
+        assert_eq!(list.overall_progress(), 50.0);
+
+        list.tasks[0].update(TaskMessage::RemoveImage(2));
+
+        assert_eq!(list.tasks[0].content(), &vec![-1.0, -0.5, 0.5, 1.0]);
+        // Five of the nine images left are done
+        assert!((list.overall_progress() - 500.0 / 9.0).abs() < 1e-4);
+
+        // Only tasks that have not started can be edited
+        list.tasks[1].update(TaskMessage::RemoveImage(0));
+        assert_eq!(list.tasks[1].content().len(), 5);
+    }
+
+    #[test]
+    fn selection_moves_between_rows_and_clamps_at_the_ends() {
+        let mut list = TaskList::<f64>::default();
+        list.select_next();
+        assert_eq!(list.selected, None);
+
+        for value in [1.0, 2.0, 3.0] {
+            let task = Task::new(vec![value], String::new(), 0);
+            list.insert(task, InsertPosition::End);
+        }
+
+        list.select_next();
+        assert_eq!(list.selected, Some(0));
+        list.select_previous();
+        assert_eq!(list.selected, Some(0));
+
+        list.select_next();
+        list.select_next();
+        list.select_next();
+        assert_eq!(list.selected, Some(2));
+
+        // Up with nothing selected starts from the last row
+        list.selected = None;
+        list.select_previous();
+        assert_eq!(list.selected, Some(2));
+    }
+
+    #[test]
+    fn duplicate_is_an_idle_copy_without_the_scans() {
+        let image = STMImage::new(2, 1e-8, 0.0, 0.0, 0.0, 0.1, 0.5, 1e-10, None);
+        let mut list = TaskList::default();
+        for name in ["survey", "detail"] {
+            list.insert(
+                Task::new(vec![image.clone()], String::from(name), 0),
+                InsertPosition::End,
+            );
+        }
+        list.tasks[0].content_mut()[0].set_measurement(Measurement {
+            data: vec![1.0; 4],
+            spectroscopy: vec![],
+        });
+        list.tasks[0].state(TaskState::Completed);
+
+        let copy = list.duplicate(0);
+
+        assert_eq!(copy, 1);
+        assert_eq!(list.tasks[copy].description(), "survey (copy)");
+        assert_eq!(list.tasks[copy].index(), 1);
+        assert_eq!(list.tasks[2].description(), "detail");
+        assert_eq!(list.tasks[2].index(), 2);
+        assert!(list.tasks[copy].is_idle());
+        assert_ne!(list.tasks[copy].id(), list.tasks[0].id());
+
+        let copied = &list.tasks[copy].content()[0];
+        assert_eq!(copied.bias(), 0.5);
+        assert_eq!(copied.data(), None);
+        assert_eq!(copied.captured_at(), None);
+        assert_eq!(list.tasks[0].content()[0].data(), Some(&[1.0; 4][..]));
+
+        list.tasks[copy].content_mut()[0] = image.with_set_point(2e-10);
+        assert_eq!(list.tasks[0].content()[0].set_point(), 1e-10);
+    }
+
+    #[test]
+    fn bar_follows_the_state_and_progress() {
+        use Mode::*;
+        use TaskDisplayStyles::*;
+
+        let mut task = Task::new(vec![1.0_f64], String::from("survey"), 0);
+        assert_eq!(task.bar(), (Waiting, Determinate(0.0)));
+
+        task.state(TaskState::Running);
+        assert_eq!(task.bar(), (Running, Indeterminate));
+        task.set_progress(37.5);
+        assert_eq!(task.bar(), (Running, Determinate(37.5)));
+
+        task.state(TaskState::Paused);
+        assert_eq!(task.bar(), (Paused, Determinate(37.5)));
+        task.state(TaskState::Failed(String::from("tip crashed")));
+        assert_eq!(task.bar(), (Failed, Determinate(37.5)));
+        task.state(TaskState::Completed);
+        assert_eq!(task.bar(), (Completed, Determinate(100.0)));
+    }
 }
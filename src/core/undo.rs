@@ -0,0 +1,154 @@
+//! Undo and redo of changes to the queue.
+use crate::core::task::{Task, TaskList};
+
+/// Number of queue edits that can be undone.
+pub const MAX_EDITS: usize = 20;
+
+/// A change to the queue, with what is needed to make or reverse it.
+#[derive(Debug, Clone)]
+pub enum QueueEdit<T> {
+    /// `task` was added at `idx`.
+    Add { idx: usize, task: Task<T> },
+    /// `task` was deleted from `idx`.
+    Delete { idx: usize, task: Task<T> },
+}
+
+impl<T> QueueEdit<T>
+where
+    T: Clone,
+{
+    /// Makes the change again.
+    pub fn apply(&self, list: &mut TaskList<T>) {
+        match self {
+            QueueEdit::Add { idx, task } => list.insert_at(*idx, task.clone()),
+            QueueEdit::Delete { idx, .. } => {
+                list.remove(*idx);
+            }
+        }
+    }
+
+    /// Reverses the change.
+    pub fn revert(&self, list: &mut TaskList<T>) {
+        match self {
+            QueueEdit::Add { idx, .. } => {
+                list.remove(*idx);
+            }
+            QueueEdit::Delete { idx, task } => list.insert_at(*idx, task.clone()),
+        }
+    }
+}
+
+/// The last [`MAX_EDITS`] queue edits, and those undone since.
+#[derive(Debug, Clone)]
+pub struct History<T> {
+    undo: Vec<QueueEdit<T>>,
+    redo: Vec<QueueEdit<T>>,
+}
+
+impl<T> Default for History<T> {
+    fn default() -> Self {
+        Self {
+            undo: Vec::new(),
+            redo: Vec::new(),
+        }
+    }
+}
+
+impl<T> History<T>
+where
+    T: Clone,
+{
+    /// Remembers an edit just made. Edits undone before it can no longer be
+    /// redone.
+    pub fn record(&mut self, edit: QueueEdit<T>) {
+        if self.undo.len() == MAX_EDITS {
+            self.undo.remove(0);
+        }
+        self.undo.push(edit);
+        self.redo.clear();
+    }
+
+    /// Reverses the most recent edit, returning whether there was one.
+    pub fn undo(&mut self, list: &mut TaskList<T>) -> bool {
+        let Some(edit) = self.undo.pop() else {
+            return false;
+        };
+        edit.revert(list);
+        self.redo.push(edit);
+        true
+    }
+
+    /// Makes the most recently undone edit again, returning whether there
+    /// was one.
+    pub fn redo(&mut self, list: &mut TaskList<T>) -> bool {
+        let Some(edit) = self.redo.pop() else {
+            return false;
+        };
+        edit.apply(list);
+        self.undo.push(edit);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::task::InsertPosition;
+
+    /// Adds a task of `value` at the end of `list`, recording it in `history`.
+    fn add(list: &mut TaskList<f64>, history: &mut History<f64>, value: f64) {
+        let task = Task::new(vec![value], String::new(), 0);
+        let idx = list.insert(task, InsertPosition::End);
+        let task = list.tasks[idx].clone();
+        history.record(QueueEdit::Add { idx, task });
+    }
+
+    fn values(list: &TaskList<f64>) -> Vec<f64> {
+        list.tasks.iter().map(|task| task.content()[0]).collect()
+    }
+
+    #[test]
+    fn added_task_is_undone_and_redone() {
+        let (mut list, mut history) = (TaskList::default(), History::default());
+        add(&mut list, &mut history, 1.0);
+
+        assert!(history.undo(&mut list));
+        assert!(list.tasks.is_empty());
+        assert!(!history.undo(&mut list));
+
+        assert!(history.redo(&mut list));
+        assert_eq!(values(&list), [1.0]);
+        assert!(!history.redo(&mut list));
+    }
+
+    #[test]
+    fn deleted_task_comes_back_in_place() {
+        let (mut list, mut history) = (TaskList::default(), History::default());
+        for value in [1.0, 2.0, 3.0] {
+            add(&mut list, &mut history, value);
+        }
+
+        let task = list.remove(1);
+        history.record(QueueEdit::Delete { idx: 1, task });
+        assert_eq!(values(&list), [1.0, 3.0]);
+
+        history.undo(&mut list);
+        assert_eq!(values(&list), [1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn new_edits_drop_the_redo_and_the_oldest_edits() {
+        let (mut list, mut history) = (TaskList::default(), History::default());
+        add(&mut list, &mut history, 1.0);
+        history.undo(&mut list);
+        add(&mut list, &mut history, 2.0);
+        assert!(!history.redo(&mut list));
+
+        for value in 0..MAX_EDITS {
+            add(&mut list, &mut history, value as f64);
+        }
+        while history.undo(&mut list) {}
+        // The first task added is older than the last MAX_EDITS edits
+        assert_eq!(values(&list), [2.0]);
+    }
+}
@@ -1,4 +1,8 @@
-#[derive(Debug, Clone)]
+use serde::{Deserialize, Serialize};
+
+/// A pair of coordinates, e.g. a sample position in meters. Serialized as
+/// `{ "x": .., "y": .. }`, so positions are kept when a queue is saved.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Vector2<T>
 where
     T: Default + Clone + Copy,
@@ -6,3 +10,42 @@ where
     x: T,
     y: T,
 }
+
+impl<T> Vector2<T>
+where
+    T: Default + Clone + Copy,
+{
+    pub fn new(x: T, y: T) -> Self {
+        Self { x, y }
+    }
+
+    pub fn x(&self) -> T {
+        self.x
+    }
+
+    pub fn y(&self) -> T {
+        self.y
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_as_named_coordinates() {
+        let position = Vector2::new(1.5e-9, -2.0e-9);
+        let json = serde_json::to_value(&position).unwrap();
+
+        assert_eq!(json, serde_json::json!({ "x": 1.5e-9, "y": -2.0e-9 }));
+        assert_eq!(
+            serde_json::from_value::<Vector2<f64>>(json).unwrap(),
+            position
+        );
+    }
+
+    #[test]
+    fn missing_coordinates_are_rejected() {
+        assert!(serde_json::from_str::<Vector2<f64>>(r#"{"x":1.0}"#).is_err());
+    }
+}
@@ -1,45 +1,197 @@
-use crate::core::{stmimage::STMImage, jlcontext::JuliaContext};
-use std::path::PathBuf;
+use crate::core::{
+    jlcontext::ScanEvent,
+    stmimage::{Measurement, STMImage},
+    sweep,
+};
+use crossbeam_channel::Sender;
 use jlrs::prelude::*;
 
+/// Garbage collection time within one line, in nanoseconds, from which the
+/// line is reported as stalled.
+const GC_STALL_NS: u64 = 50_000_000;
+
+/// Scans an image line by line, so that progress can be reported between lines,
+/// then measures each of its spectroscopies at every position.
+pub struct ScanJob {
+    pub image: STMImage,
+    /// Where each completed line is reported, with the id of the task the
+    /// image belongs to.
+    pub progress: Option<(u64, Sender<ScanEvent>)>,
+}
+
 #[async_trait(?Send)]
-impl AsyncTask for STMImage {
-    type Output = Bool;
-
-    // Include the custom code MyTask needs.
-    async fn register<'frame>(mut frame: AsyncGcFrame<'frame>) -> JlrsResult<()> {
-        unsafe {
-            let path = PathBuf::from("../procedures/lockin_test.jl");
-            if path.exists() {
-                Value::include(frame.as_extended_target(), "../procedures/lockin_test.jl")?.into_jlrs_result()?;
-            }
-        }
+impl AsyncTask for ScanJob {
+    type Output = Measurement;
+
+    // The procedure script is included by `JuliaContext::include_procedure`,
+    // which knows where it lives.
+    async fn register<'frame>(_frame: AsyncGcFrame<'frame>) -> JlrsResult<()> {
         Ok(())
     }
 
      // This is the async variation of the closure you provide `Julia::scope` when using the sync
     // runtime.
     async fn run<'frame>(&mut self, mut frame: AsyncGcFrame<'frame>) -> JlrsResult<Self::Output> {
-        // Convert the two arguments to values Julia can work with.
-        // let dims = Value::new(&mut frame, self.dims);
-        // let iters = Value::new(&mut frame, self.iters);
-
-        // Get `read_lockin` in `Test`, call it on another thread with `call_async`, and await
-        // the result before casting it to an `f64` (which that function returns). A function that
-        // is called with `call_async` is executed on another thread by calling
-        // `Base.threads.@spawn`.
-        // The module and function don't have to be rooted because the module is never redefined,
-        // so they're globally rooted.
-        unsafe {
-            Module::main(&frame)
-                .submodule(&frame, "Test")?
-                .wrapper()
-                .function(&frame, "read_lockin")?
-                .wrapper()
-                .call_async(&mut frame, &mut [])
-                .await
-                .into_jlrs_result()?
-                .unbox::<Bool>()
+        // Convert the scan parameters to values Julia can work with.
+        let image = &self.image;
+        let (lines, [size, x_offset, y_offset, rotation, line_time, bias, set_point]) =
+            scan_parameters(image);
+        let lines = Value::new(&mut frame, lines);
+        let size = Value::new(&mut frame, size);
+        let x_offset = Value::new(&mut frame, x_offset);
+        let y_offset = Value::new(&mut frame, y_offset);
+        let rotation = Value::new(&mut frame, rotation);
+        let line_time = Value::new(&mut frame, line_time);
+        let bias = Value::new(&mut frame, bias);
+        let set_point = Value::new(&mut frame, set_point);
+
+        let mut data = Vec::with_capacity((image.lines() as usize).pow(2));
+        for line in 0..image.lines() {
+            let line = Value::new(&mut frame, line);
+            let gc_before = gc_time_ns(&mut frame)?;
+
+            // Get `read_lockin` in `Test`, call it on another thread with `call_async`, and await
+            // the line before copying out the `Vector{Float64}` that function returns. A function
+            // that is called with `call_async` is executed on another thread by calling
+            // `Base.threads.@spawn`.
+            // The module and function don't have to be rooted because the module is never
+            // redefined, so they're globally rooted.
+            let line_data = unsafe {
+                Module::main(&frame)
+                    .submodule(&frame, "Test")?
+                    .wrapper()
+                    .function(&frame, "read_lockin")?
+                    .wrapper()
+                    .call_async(
+                        &mut frame,
+                        &mut [
+                            line, lines, size, x_offset, y_offset, rotation, line_time, bias,
+                            set_point,
+                        ],
+                    )
+                    .await
+                    .into_jlrs_result()?
+                    .cast::<Array>()?
+                    .copy_inline_data::<f64>()?
+                    .splat()
+                    .0
+                    .into_vec()
+            };
+            data.extend(line_data);
+            let gc_time = gc_time_ns(&mut frame)?.saturating_sub(gc_before);
+
+            if let Some((task, events)) = &self.progress {
+                // Nobody listening is not a reason to stop scanning.
+                for event in line_events(*task, gc_time) {
+                    let _ = events.send(event);
+                }
+            }
+        }
+
+        let mut spectroscopy = vec![];
+        for sts in image.spectroscopy().unwrap_or_default() {
+            let (start, stop) = (sts.start_voltage(), sts.stop_voltage());
+            let points = Value::new(&mut frame, sts.voltages().len() as u64);
+            let step = Value::new(
+                &mut frame,
+                sweep::directed_step(start, stop, sts.step_voltage()),
+            );
+            let start = Value::new(&mut frame, start);
+
+            let mut curves = vec![];
+            for position in image.sts_positions(sts.sts_type()) {
+                let x = Value::new(&mut frame, position.x());
+                let y = Value::new(&mut frame, position.y());
+
+                // Called like `read_lockin` above
+                let curve = unsafe {
+                    Module::main(&frame)
+                        .submodule(&frame, "Test")?
+                        .wrapper()
+                        .function(&frame, "read_sts")?
+                        .wrapper()
+                        .call_async(&mut frame, &mut [x, y, start, step, points, set_point])
+                        .await
+                        .into_jlrs_result()?
+                        .cast::<Array>()?
+                        .copy_inline_data::<f64>()?
+                        .splat()
+                        .0
+                        .into_vec()
+                };
+                curves.push(curve);
+            }
+            spectroscopy.push(curves);
         }
+
+        Ok(Measurement { data, spectroscopy })
+    }
+}
+
+/// Parameters `read_lockin` is called with after the line number, in order:
+/// the lines per frame, then size, X and Y offset, rotation, line time, bias
+/// and set point.
+fn scan_parameters(image: &STMImage) -> (u32, [f64; 7]) {
+    (
+        image.lines(),
+        [
+            image.size(),
+            image.x_offset(),
+            image.y_offset(),
+            image.rotation(),
+            image.line_time(),
+            image.bias(),
+            image.set_point(),
+        ],
+    )
+}
+
+/// Events reporting that task `task` finished a line during which Julia spent
+/// `gc_time` nanoseconds collecting garbage. The stall goes first, as it flags
+/// the line its `Line` event completes.
+fn line_events(task: u64, gc_time: u64) -> Vec<ScanEvent> {
+    if gc_time >= GC_STALL_NS {
+        vec![ScanEvent::GcStalled(task), ScanEvent::Line(task)]
+    } else {
+        vec![ScanEvent::Line(task)]
+    }
+}
+
+/// Total time Julia spent collecting garbage since it started, in nanoseconds.
+fn gc_time_ns(frame: &mut AsyncGcFrame) -> JlrsResult<u64> {
+    unsafe {
+        Value::eval_string(&mut *frame, "Base.gc_time_ns()")
+            .into_jlrs_result()?
+            .unbox::<u64>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::calibration::Calibration;
+
+    #[test]
+    fn line_with_a_long_gc_pause_is_reported_as_stalled() {
+        assert_eq!(line_events(3, 0), [ScanEvent::Line(3)]);
+        assert_eq!(line_events(3, GC_STALL_NS - 1), [ScanEvent::Line(3)]);
+        assert_eq!(
+            line_events(3, GC_STALL_NS),
+            [ScanEvent::GcStalled(3), ScanEvent::Line(3)]
+        );
+    }
+
+    #[test]
+    fn julia_receives_the_calibrated_scan_parameters_in_order() {
+        let image = STMImage::new(64, 1e-8, 2e-9, -3e-9, 45.0, 0.2, 0.5, 1e-10, None);
+        assert_eq!(
+            scan_parameters(&image),
+            (64, [1e-8, 2e-9, -3e-9, 45.0, 0.2, 0.5, 1e-10])
+        );
+
+        let mut calibration = Calibration::default();
+        calibration.bias.scale = 2.0;
+        let (_, parameters) = scan_parameters(&image.calibrated(&calibration));
+        assert_eq!(parameters[5], 1.0);
     }
-}
\ No newline at end of file
+}
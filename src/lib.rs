@@ -0,0 +1,6 @@
+//! Queue, instrument and widget code of the STM controller, shared by the
+//! app in `main.rs` and the integration tests.
+pub mod core;
+pub mod core_async;
+pub mod native;
+pub mod style;
@@ -1,43 +1,154 @@
-mod core;
-mod core_async;
-mod native;
-mod style;
-
 use iced::keyboard;
+use iced::mouse;
+use iced_native::event;
 use iced_native::subscription;
 use iced_native::Event;
 
 use iced::{
     executor, theme,
     widget::{
-        button, column, container, horizontal_rule, horizontal_space, pick_list, row, scrollable,
+        button, checkbox, column, container, horizontal_rule, horizontal_space, pick_list, progress_bar, row, scrollable,
         text, text_input, vertical_rule, vertical_space, Button, PickList, Text, TextInput,
     },
-    Alignment, Application, Command, Element, Length, Renderer, Settings, Subscription, Theme,
+    Alignment, Application, Command, Element, Length, Renderer, Subscription, Theme,
 };
 use iced_graphics::widget::canvas::Canvas;
 
-use crate::core::{
+use stm_controller::core::{
+    audit::AuditLog,
+    calibration::{Calibration, DEFAULT_PROFILE},
+    batch::BatchImport,
+    eta::{
+        format_duration, format_finish_clock, LineRateEstimator, UNKNOWN_CLOCK, UNKNOWN_DURATION,
+    },
+    export::{export_task, FileExporter, OutputFormat},
+    hardware,
+    headless,
     icons::*,
-    stmimage::STMImage,
-    task::{Task, TaskList, TaskMessage, TaskState},
+    notify::{self, CompletionHook},
+    settings::{Settings, ThemeChoice},
+    stmimage::{Measurement, STMImage, STSType, STS},
+    storage::{check_space, estimate_bytes, DEFAULT_OUTPUT_DIR},
+    sweep::{self, parse_bias_list, BiasSpec, SweepDirection, SweepMode},
+    task::{InsertPosition, Task, TaskList, TaskMessage, TaskState},
+    undo::{History, QueueEdit},
     vector2::Vector2,
-    jlcontext::JuliaContext
+    jlcontext::{Dispatched, JuliaContext, ScanEvent, VersionInfo, WORKERS},
+    scangeometry::ScanGeometry,
+    synthetic::TestPattern,
 };
-use native::image_plot::Plot;
-use native::scientificspinbox::{Bounds, ExponentialNumber, ScientificSpinBox};
-use style::toolbartheme::ToolBarTheme;
+use stm_controller::core_async::stmimage::ScanJob;
+use stm_controller::native::busy::Busy;
+use stm_controller::native::focus;
+use stm_controller::native::image_plot::{Colormap, Interpolation, Plot};
+use stm_controller::native::modal::{Dialog, Modal};
+use stm_controller::native::motion;
+use stm_controller::native::scientificspinbox::{Bounds, ExponentialNumber, ScientificSpinBox};
+use stm_controller::native::taskdisplay::percentage;
+use stm_controller::style::toolbartheme::ToolBarTheme;
 
-use itertools_num::linspace;
+use chrono::Local;
 use std::cmp::min;
-use crossbeam_channel;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Idle time after the last voltage edit before the estimates are refreshed.
+const RECOMPUTE_DEBOUNCE: Duration = Duration::from_millis(300);
+/// How often the progress reported by running scans is picked up.
+const PROGRESS_POLL: Duration = Duration::from_millis(100);
+/// Points per side offered for spectroscopy grids.
+const STS_GRID_SIZES: [u32; 5] = [2, 3, 4, 5, 8];
+/// Queue handed over to a relaunched app after the Julia runtime stopped.
+const RECOVERED_QUEUE: &str = "stm_controller_recovered_queue.json";
 
 fn main() -> iced::Result {
+    // `stm_controller --run <queue.json>` runs a saved queue without a window
+    // and `stm_controller --open <queue.json>` starts with the queue loaded
+    let args: Vec<String> = std::env::args().collect();
+    let mut queue = None;
+    if let [_, command, path] = args.as_slice() {
+        if command == "--run" || command == "run" {
+            let result = run_headless(Path::new(path));
+            if let Err(e) = &result {
+                eprintln!("{}", e);
+            }
+            std::process::exit(headless::exit_code(&result));
+        }
+        if command == "--open" {
+            queue = Some(PathBuf::from(path));
+        }
+    }
 
-    R9Control::run(Settings {
-        ..Settings::default()
+    R9Control::run(iced::Settings {
+        flags: queue,
+        ..iced::Settings::default()
     })
+}
+
+/// Starts Julia on the procedure script of `settings`, calibrated by the
+/// hardware profile. Uncalibrated values must not reach the instrument, so an
+/// unreadable profile fails the start like Julia itself failing would.
+fn start_julia(settings: &Settings) -> Result<JuliaContext, String> {
+    let calibration = Calibration::from_default_profile().map_err(|e| {
+        format!(
+            "Could not read the hardware profile {}: {}",
+            DEFAULT_PROFILE, e
+        )
+    })?;
+
+    let mut jlcontext = JuliaContext::try_with_procedure(&settings.procedure_path)
+        .map_err(|e| format!("Could not start Julia: {}", e))?;
+    jlcontext
+        .load::<ScanJob>()
+        .map_err(|e| format!("Could not start Julia: {}", e))?;
+    jlcontext.calibration = calibration;
+    Ok(jlcontext)
+}
+
+/// Runs the queue saved at `path` on Julia, printing progress and a summary
+/// to stdout. The file is rewritten with the final state of every task. Fails
+/// if the queue could not be run or any of its tasks failed.
+fn run_headless(path: &Path) -> Result<(), String> {
+    let mut tasklist = TaskList::<STMImage>::load(path)
+        .map_err(|e| format!("Could not read {:?}: {}", path, e))?;
+    let settings = Settings::from_default_path();
+    let mut jlcontext = start_julia(&settings)?;
+    jlcontext.include_procedure()?;
+
+    let audit_log = AuditLog::default();
+    let mut exporter = FileExporter::default();
+    let output_dir = Path::new(DEFAULT_OUTPUT_DIR);
+
+    let result = headless::run(
+        &mut tasklist,
+        &mut jlcontext,
+        &mut std::io::stdout(),
+        |task| record_state(&audit_log, &mut exporter, output_dir, task),
+    );
 
+    tasklist
+        .save(path)
+        .map_err(|e| format!("Could not write {:?}: {}", path, e))?;
+    result
+}
+
+/// Logs a task's new state and exports it to `output_dir` once it completed.
+fn record_state(
+    audit_log: &AuditLog,
+    exporter: &mut FileExporter,
+    output_dir: &Path,
+    task: &Task<STMImage>,
+) {
+    if let Err(e) = audit_log.record(task) {
+        eprintln!("Could not write to {:?}: {}", audit_log.path(), e);
+    }
+
+    if matches!(task.status(), TaskState::Completed) {
+        if let Err(e) = export_task(exporter, task, output_dir) {
+            eprintln!("Could not export {:?}: {}", task.description(), e);
+        }
+    }
 }
 
 struct R9Control {
@@ -45,39 +156,727 @@ struct R9Control {
     size: ExponentialNumber,
     x_offset: ExponentialNumber,
     y_offset: ExponentialNumber,
+    rotation: ExponentialNumber,
     line_time: ExponentialNumber,
     // scan_speed: ExponentialNumber,
     start_voltage: ExponentialNumber,
     stop_voltage: ExponentialNumber,
     step_voltage: ExponentialNumber,
-    total_images: u16,
+    sweep_mode: SweepMode,
+    sweep_direction: SweepDirection,
+    /// Sweep the set point as well, queueing every bias × set point pair.
+    sweep_2d: bool,
+    set_point: ExponentialNumber,
+    start_set_point: ExponentialNumber,
+    stop_set_point: ExponentialNumber,
+    step_set_point: ExponentialNumber,
+    insert_position: InsertPosition,
+    /// Images of the task most recently queued from the form.
+    last_queued: Option<Vec<STMImage>>,
+    /// Why the form cannot be queued, checked after every message.
+    form_error: Option<String>,
+    /// Whether the form was edited since queued, checked after every message.
+    form_dirty: bool,
+    lock_form_while_running: bool,
+    /// Space the bias range geometrically rather than by the step voltage.
+    log_spacing: bool,
+    /// Number of biases in a log-spaced range, as typed.
+    log_points: String,
+    bias_list: String,
+    bias_list_rejects: String,
+    /// File the bias list is loaded from.
+    bias_list_path: String,
+    total_images: usize,
     time_to_finish: String,
+    /// Local time the form's images would be done by, e.g. `"done ~14:32"`.
+    finish_clock: String,
     name: String,
     tasklist: TaskList<STMImage>,
-    jlcontext: JuliaContext
+    /// Stop auto-advancing once the running task finishes. Checked at the task
+    /// boundary in `advance_queue` and cleared once it has halted the queue, so
+    /// Play resumes from the next task as usual.
+    pause_after_current: bool,
+    /// Run up to `WORKERS` queued tasks at once instead of one after another.
+    parallel_tasks: bool,
+    /// Turns off cursor blinking and other time-driven animations.
+    reduce_motion: bool,
+    /// Tags tasks dispatched to the worker pool. Bumped on Stop so results of
+    /// interrupted tasks are ignored when they come back.
+    run_generation: u64,
+    /// Whether arrow keys move the task selection. Gained by clicking a task,
+    /// lost when focus moves to the form.
+    tasklist_focused: bool,
+    /// Whether arrow keys nudge the scan offset. Gained by clicking the plot
+    /// or pressing F6.
+    plot_focused: bool,
+    /// Snap scan-area moves from the plot to multiples of `grid_spacing`.
+    snap_to_grid: bool,
+    grid_spacing: ExponentialNumber,
+    /// Clicks on the plot place spectroscopy instead of moving the scan area.
+    placing_sts: bool,
+    /// Spectroscopy taken after each image of the next queued task, over the
+    /// bias range of the form.
+    spectroscopy: Vec<STSType>,
+    /// Points per side of the next spectroscopy grid.
+    sts_grid_size: u32,
+    /// Recent additions and deletions in the queue, for Ctrl+Z.
+    history: History<STMImage>,
+    audit_log: AuditLog,
+    /// Output format given to newly queued tasks.
+    default_output_format: OutputFormat,
+    exporter: FileExporter,
+    /// Directory completed and saved tasks are written to.
+    output_dir: PathBuf,
+    batch_path: String,
+    batch_report: String,
+    /// Open dialogs, the last one on top and the only one shown.
+    dialogs: Vec<Dialog<Message>>,
+    plot_data: Option<Vec<f32>>,
+    plot_lines: u32,
+    interpolation: Interpolation,
+    colormap: Colormap,
+    /// Bumped on every voltage edit; only the latest scheduled recompute runs.
+    estimates_generation: u64,
+    /// Measured line timing of each running task by id, for its progress and
+    /// live ETA.
+    line_rates: HashMap<u64, LineRateEstimator>,
+    /// Live ETA of the current task.
+    running_eta: String,
+    disk_warning: Option<String>,
+    /// Set from dispatching a task until Julia reports back on it.
+    is_dispatching: bool,
+    /// One-off scan of the form, run outside the queue and dropped once done.
+    quick_scan: Option<Task<STMImage>>,
+    /// Whether the settings panel under the toolbar is shown.
+    show_settings: bool,
+    /// Julia script defining the procedures tasks run, as typed in settings.
+    procedure_path: String,
+    theme: ThemeChoice,
+    /// Name recorded with every image scanned, as typed in settings.
+    operator: String,
+    notify_on_finish: bool,
+    /// Called once a run leaves no task to do, when `notify_on_finish`.
+    on_queue_finished: CompletionHook,
+    /// Where the settings are saved on exit, `None` to leave them unsaved.
+    settings_path: Option<PathBuf>,
+    /// `None` when Julia could not be started: tasks can still be queued,
+    /// edited and saved, but not run.
+    jlcontext: Option<JuliaContext>,
 }
 
 impl Default for R9Control {
     fn default() -> Self {
+        let settings = Settings::from_default_path();
+        match start_julia(&settings) {
+            Ok(jlcontext) => Self::with_settings(settings, Some(jlcontext)),
+            Err(e) => {
+                let mut app = Self::with_settings(settings, None);
+                app.open_dialog(
+                    Dialog::new(
+                        "Julia unavailable",
+                        format!(
+                            "{}\n\n\
+                             Tasks can be queued but not run until the app is restarted.",
+                            e
+                        ),
+                    )
+                    .close_button("OK"),
+                );
+                app
+            }
+        }
+    }
+}
 
-        let jlcontext = JuliaContext::default();
-        jlcontext.load::<STMImage>();
+impl R9Control {
+    /// The app as `settings` left it, running tasks on `jlcontext`.
+    fn with_settings(settings: Settings, jlcontext: Option<JuliaContext>) -> Self {
+        motion::set_reduced(settings.reduce_motion);
 
         Self {
-            lines: None,
-            size: ExponentialNumber::new(50.0, -9),
+            lines: settings.lines,
+            size: ExponentialNumber::from_scientific(settings.size),
             x_offset: ExponentialNumber::new(0.0, -9),
             y_offset: ExponentialNumber::new(0.0, -9),
+            rotation: ExponentialNumber::new(0.0, 0),
             line_time: ExponentialNumber::new(0.0, 0),
             // scan_speed: ExponentialNumber::new(0.0, -9),
             start_voltage: ExponentialNumber::new(0.0, 0),
             stop_voltage: ExponentialNumber::new(0.0, 0),
             step_voltage: ExponentialNumber::new(0.0, 0),
+            sweep_direction: SweepDirection::default(),
+            sweep_mode: SweepMode::default(),
+            sweep_2d: false,
+            set_point: ExponentialNumber::new(100.0, -12),
+            start_set_point: ExponentialNumber::new(10.0, -12),
+            stop_set_point: ExponentialNumber::new(100.0, -12),
+            step_set_point: ExponentialNumber::new(10.0, -12),
+            insert_position: InsertPosition::default(),
+            last_queued: None,
+            form_error: None,
+            form_dirty: false,
+            lock_form_while_running: false,
+            log_spacing: false,
+            log_points: String::from("5"),
+            bias_list: String::from(""),
+            bias_list_rejects: String::from(""),
+            bias_list_path: String::from(""),
             total_images: 0,
-            time_to_finish: String::from(""),
+            time_to_finish: String::from(UNKNOWN_DURATION),
+            finish_clock: String::from(UNKNOWN_CLOCK),
             name: String::from(""),
             tasklist: TaskList::default(),
-            jlcontext
+            pause_after_current: false,
+            parallel_tasks: false,
+            reduce_motion: settings.reduce_motion,
+            run_generation: 0,
+            tasklist_focused: false,
+            plot_focused: false,
+            snap_to_grid: false,
+            grid_spacing: ExponentialNumber::new(10.0, -9),
+            placing_sts: false,
+            spectroscopy: Vec::new(),
+            sts_grid_size: 3,
+            history: History::default(),
+            audit_log: AuditLog::default(),
+            default_output_format: settings.output_format,
+            exporter: FileExporter::default(),
+            output_dir: PathBuf::from(DEFAULT_OUTPUT_DIR),
+            batch_path: String::from(""),
+            batch_report: String::from(""),
+            dialogs: Vec::new(),
+            plot_data: None,
+            plot_lines: 0,
+            interpolation: Interpolation::default(),
+            colormap: Colormap::default(),
+            estimates_generation: 0,
+            line_rates: HashMap::new(),
+            running_eta: String::from(""),
+            disk_warning: None,
+            is_dispatching: false,
+            quick_scan: None,
+            show_settings: false,
+            procedure_path: settings.procedure_path,
+            theme: settings.theme,
+            operator: settings.operator,
+            notify_on_finish: settings.notify_on_finish,
+            on_queue_finished: Box::new(notify::desktop_notification),
+            settings_path: Settings::default_path(),
+            jlcontext,
+        }
+    }
+}
+
+impl R9Control {
+    /// Why the form cannot be queued as it is, if it cannot. Images are
+    /// counted rather than generated, so a mistyped step costs nothing.
+    fn check_form(&self) -> Option<String> {
+        if let Err(e) = self.check_bounds() {
+            return Some(e);
+        }
+        if self.sweep_mode == SweepMode::Range {
+            if self.log_spacing {
+                if let Err(e) =
+                    sweep::check_log_range(self.start_voltage.to_f64(), self.stop_voltage.to_f64())
+                {
+                    return Some(e);
+                }
+                if self.log_points().is_none() {
+                    return Some(String::from("Points must be a whole number of at least 2"));
+                }
+            } else if self.step_voltage.to_f64() <= 0.0 {
+                return Some(String::from("Step voltage must be positive"));
+            }
+        }
+        if self.sweep_2d && self.step_set_point.to_f64() <= 0.0 {
+            return Some(String::from("Set point step must be positive"));
+        }
+        match self.form_image_count() {
+            0 => Some(String::from("No images to queue")),
+            count if count > sweep::MAX_VALUES => Some(format!(
+                "Too many images to queue, at most {} fit in a task",
+                sweep::MAX_VALUES
+            )),
+            _ => None,
+        }
+    }
+
+    /// Checks the voltages and set points the form sweeps between against the
+    /// hardware ranges. Listed voltages are checked as the list is parsed.
+    fn check_bounds(&self) -> Result<(), String> {
+        if self.sweep_mode == SweepMode::Range {
+            hardware::check("Start voltage", self.start_voltage.to_f64(), hardware::BIAS)?;
+            hardware::check("Stop voltage", self.stop_voltage.to_f64(), hardware::BIAS)?;
+        }
+        if self.sweep_2d {
+            let (start, stop) = (self.start_set_point.to_f64(), self.stop_set_point.to_f64());
+            hardware::check("Start set point", start, hardware::SET_POINT)?;
+            hardware::check("Stop set point", stop, hardware::SET_POINT)?;
+        } else {
+            hardware::check("Set point", self.set_point.to_f64(), hardware::SET_POINT)?;
+        }
+        Ok(())
+    }
+
+    /// Re-checks the form, for `view` to show without redoing the work on
+    /// every redraw.
+    fn refresh_form_state(&mut self) {
+        self.form_error = self.check_form();
+        self.form_dirty = self.form_is_dirty();
+    }
+
+    /// Queues the current form as a new task, leaving the form untouched.
+    /// Does nothing while the form is invalid.
+    fn add_to_queue(&mut self) {
+        if self.check_form().is_some() {
+            return;
+        }
+
+        let id = self.tasklist.tasks.len();
+        let images = self.form_images();
+        self.last_queued = Some(images.clone());
+
+        let mut task = Task::new(images, self.name.clone(), id);
+        task.set_output_format(self.default_output_format);
+        let idx = self.tasklist.insert(task, self.insert_position);
+        self.record_add(idx);
+    }
+
+    /// One-image task "Scan now" runs outside the queue: the first image the
+    /// form describes.
+    fn quick_scan_task(&self) -> Option<Task<STMImage>> {
+        let image = self.form_images().into_iter().next()?;
+        Some(Task::new(
+            vec![image],
+            String::from("Scan now"),
+            self.tasklist.tasks.len(),
+        ))
+    }
+
+    /// Remembers that the task at `idx` was just added, so it can be undone.
+    fn record_add(&mut self, idx: usize) {
+        let task = self.tasklist.tasks[idx].clone();
+        self.history.record(QueueEdit::Add { idx, task });
+    }
+
+    /// Whether any task holds a worker. Deleting, clearing or undoing would
+    /// re-index the queue under it, so those wait until the run is over.
+    fn queue_busy(&self) -> bool {
+        !self.running_tasks().is_empty() || !self.paused_tasks().is_empty()
+    }
+
+    /// Images of the task the form currently describes, none if there are
+    /// more than a task can hold.
+    fn form_images(&self) -> Vec<STMImage> {
+        if self.form_image_count() > sweep::MAX_VALUES {
+            return vec![];
+        }
+
+        let image = |bias| {
+            STMImage::new(
+                self.lines.unwrap_or(256),
+                self.size.to_f64(),
+                self.x_offset.to_f64(),
+                self.y_offset.to_f64(),
+                self.rotation.to_f64(),
+                self.line_time.to_f64(),
+                bias,
+                self.set_point.to_f64(),
+                self.form_spectroscopy(),
+            )
+        };
+
+        if self.sweep_2d {
+            sweep::grid(&self.biases(), &self.set_points())
+                .into_iter()
+                .map(|(bias, set_point)| image(bias).with_set_point(set_point))
+                .collect()
+        } else {
+            self.biases().into_iter().map(image).collect()
+        }
+    }
+
+    /// Spectroscopy placed on the plot, swept over the bias range of the form.
+    fn form_spectroscopy(&self) -> Option<Vec<STS>> {
+        if self.spectroscopy.is_empty() {
+            return None;
+        }
+
+        Some(
+            self.spectroscopy
+                .iter()
+                .map(|sts_type| {
+                    STS::new(
+                        sts_type.clone(),
+                        self.start_voltage.to_f64(),
+                        self.stop_voltage.to_f64(),
+                        self.step_voltage.to_f64(),
+                    )
+                })
+                .collect(),
+        )
+    }
+
+    fn running_task(&self) -> Option<&Task<STMImage>> {
+        self.tasklist
+            .current_task
+            .map(|idx| &self.tasklist.tasks[idx])
+            .filter(|task| task.is_running())
+    }
+
+    /// Whether the form no longer describes the running task or, when nothing
+    /// runs, the last task queued from it.
+    fn form_is_dirty(&self) -> bool {
+        let reference = match self.running_task() {
+            Some(task) => Some(task.content()),
+            None => self.last_queued.as_ref(),
+        };
+
+        reference.map_or(false, |images| *images != self.form_images())
+    }
+
+    /// Whether edits to the form are currently being refused.
+    fn form_locked(&self) -> bool {
+        self.lock_form_while_running && self.running_task().is_some()
+    }
+
+    /// Number of biases in a log-spaced range, if the typed one is usable.
+    fn log_points(&self) -> Option<usize> {
+        self.log_points
+            .trim()
+            .parse()
+            .ok()
+            .filter(|&points| points >= 2)
+    }
+
+    /// Bias values the form describes, before they are put in order.
+    fn bias_spec(&self) -> BiasSpec {
+        match self.sweep_mode {
+            SweepMode::Range if self.log_spacing => BiasSpec::Logarithmic {
+                start: self.start_voltage.to_f64(),
+                stop: self.stop_voltage.to_f64(),
+                points: self.log_points().unwrap_or(0),
+            },
+            SweepMode::Range => BiasSpec::Linear {
+                start: self.start_voltage.to_f64(),
+                stop: self.stop_voltage.to_f64(),
+                step: self.step_voltage.to_f64(),
+            },
+            SweepMode::List => BiasSpec::Explicit(parse_bias_list(&self.bias_list).0),
+        }
+    }
+
+    /// Bias values of the task the form currently describes, none if there
+    /// are too many to generate.
+    fn biases(&self) -> Vec<f64> {
+        self.sweep_direction
+            .apply(self.bias_spec().values().unwrap_or_default())
+    }
+
+    /// Set point values of a 2D sweep, none if there are too many to generate.
+    fn set_points(&self) -> Vec<f64> {
+        sweep::range(
+            self.start_set_point.to_f64(),
+            self.stop_set_point.to_f64(),
+            self.step_set_point.to_f64(),
+        )
+        .unwrap_or_default()
+    }
+
+    /// Number of images the form describes, counted without generating them.
+    fn form_image_count(&self) -> usize {
+        let biases = self.bias_spec().count();
+        if !self.sweep_2d {
+            return biases;
+        }
+
+        biases.saturating_mul(sweep::count(
+            self.start_set_point.to_f64(),
+            self.stop_set_point.to_f64(),
+            self.step_set_point.to_f64(),
+        ))
+    }
+
+    /// Refreshes the image count and time estimate from the form.
+    fn recompute_estimates(&mut self) {
+        self.total_images = self.form_image_count();
+        self.refresh_time_to_finish();
+    }
+
+    /// Recomputes how long the form's images take to scan and the wall time
+    /// they would be done by if started now.
+    fn refresh_time_to_finish(&mut self) {
+        let remaining = seconds_remaining(
+            self.lines.unwrap_or(0) as f64,
+            self.line_time.to_f64(),
+            self.total_images as f64,
+        );
+
+        self.time_to_finish =
+            remaining.map_or_else(|| String::from(UNKNOWN_DURATION), format_duration);
+        self.finish_clock = remaining.map_or_else(
+            || String::from(UNKNOWN_CLOCK),
+            |secs| format_finish_clock(Local::now(), secs),
+        );
+    }
+
+    /// Recomputes the image count and time estimate once the voltage fields
+    /// have been left alone for `RECOMPUTE_DEBOUNCE`, so intermediate
+    /// keystrokes never reach the estimates.
+    fn schedule_recompute(&mut self) -> Command<Message> {
+        self.estimates_generation += 1;
+        let generation = self.estimates_generation;
+
+        Command::perform(
+            async move {
+                tokio::time::sleep(RECOMPUTE_DEBOUNCE).await;
+                generation
+            },
+            Message::RecomputeEstimates,
+        )
+    }
+
+    /// Checks there is room to store every image from task `from` to the end
+    /// of the queue.
+    fn check_disk_space(&self, from: usize) -> Result<(), String> {
+        let required: u64 = self.tasklist.tasks[from..]
+            .iter()
+            .flat_map(|task| task.content())
+            .map(|image| estimate_bytes(image.lines(), 1))
+            .sum();
+
+        check_space(&self.output_dir, required)
+    }
+
+    /// Grid spacing in meters, when snapping is on.
+    fn grid(&self) -> Option<f64> {
+        self.snap_to_grid.then(|| self.grid_spacing.to_f64())
+    }
+
+    /// Preferences to restore on the next launch.
+    fn settings(&self) -> Settings {
+        Settings {
+            size: self.size.to_f64(),
+            lines: self.lines,
+            theme: self.theme,
+            procedure_path: self.procedure_path.clone(),
+            operator: self.operator.clone(),
+            notify_on_finish: self.notify_on_finish,
+            output_format: self.default_output_format,
+            reduce_motion: self.reduce_motion,
+        }
+    }
+
+    /// Calls the completion hook if the task that just finished was the last
+    /// one the run had to do: nothing runs or waits paused, and no idle task
+    /// is left from the current one on.
+    fn notify_if_finished(&self) {
+        let idle_left = self.tasklist.current_task.map_or(false, |from| {
+            self.tasklist.tasks[from..].iter().any(|task| task.is_idle())
+        });
+        if !self.notify_on_finish || self.queue_busy() || idle_left {
+            return;
+        }
+
+        let count = |finished: fn(&TaskState) -> bool| {
+            self.tasklist
+                .tasks
+                .iter()
+                .filter(|task| finished(task.status()))
+                .count()
+        };
+        let done = count(|state| matches!(state, TaskState::Completed));
+        let failed = count(|state| matches!(state, TaskState::Failed(_)));
+        (self.on_queue_finished)(&format!("{} tasks done, {} failed", done, failed));
+    }
+
+    /// Whether Julia is up to run tasks. Tells the user why not otherwise,
+    /// offering to relaunch the app if the runtime stopped, as Julia cannot be
+    /// started twice in one process.
+    fn ensure_julia(&mut self) -> bool {
+        let Some(jlcontext) = self.jlcontext.as_ref() else {
+            self.open_dialog(
+                Dialog::new(
+                    "Julia unavailable",
+                    "Tasks cannot run without the Julia runtime.",
+                )
+                .close_button("OK"),
+            );
+            return false;
+        };
+
+        if !jlcontext.is_alive() {
+            self.open_dialog(
+                Dialog::new(
+                    "Julia stopped",
+                    "The Julia runtime stopped. Restart the app to run tasks again. \
+                     The queue is kept.",
+                )
+                .button("Restart app", Message::RelaunchApp)
+                .close_button("Later"),
+            );
+            return false;
+        }
+        true
+    }
+
+    /// Writes the settings to `settings_path`, if any.
+    fn save_settings(&self) {
+        let Some(path) = &self.settings_path else {
+            return;
+        };
+        if let Err(e) = self.settings().save(path) {
+            eprintln!("Could not save settings: {}", e);
+        }
+    }
+
+    /// Shows `dialog` on top of any already open.
+    fn open_dialog(&mut self, dialog: Dialog<Message>) {
+        self.dialogs.push(dialog);
+    }
+
+    /// Changes the state of the task at `idx` and records the transition in the
+    /// acquisition log.
+    fn set_task_state(&mut self, idx: usize, state: TaskState) {
+        let task = &mut self.tasklist.tasks[idx];
+        task.state(state);
+
+        record_state(&self.audit_log, &mut self.exporter, &self.output_dir, task);
+    }
+
+    /// Stores the measurement of each image of the task at `idx`, in order,
+    /// and plots the last image scanned.
+    fn store_data(&mut self, idx: usize, data: Vec<Measurement>) {
+        let images = self.tasklist.tasks[idx].content_mut();
+
+        for (image, measurement) in images.iter_mut().zip(data) {
+            image.set_measurement(measurement);
+            image.set_operator(&self.operator);
+        }
+
+        if let Some((image, data)) = images
+            .iter()
+            .rev()
+            .find_map(|image| image.data().map(|data| (image, data)))
+        {
+            self.plot_lines = image.lines();
+            self.plot_data = Some(data.iter().map(|&z| z as f32).collect());
+        }
+    }
+
+    /// Whether Pause holds dispatched tasks before their next image.
+    fn is_suspended(&self) -> bool {
+        self.jlcontext
+            .as_ref()
+            .map_or(false, JuliaContext::is_suspended)
+    }
+
+    /// Indices of every task currently running.
+    fn running_tasks(&self) -> Vec<usize> {
+        self.tasklist
+            .tasks
+            .iter()
+            .filter(|task| task.is_running())
+            .map(|task| task.index())
+            .collect()
+    }
+
+    /// Indices of every task paused midway.
+    fn paused_tasks(&self) -> Vec<usize> {
+        self.tasklist
+            .tasks
+            .iter()
+            .filter(|task| task.is_paused())
+            .map(|task| task.index())
+            .collect()
+    }
+
+    /// Marks every paused task as running again, once Julia was told to
+    /// resume. Returns whether any task was paused.
+    fn resume_paused(&mut self) -> bool {
+        let paused = self.paused_tasks();
+        for &idx in &paused {
+            // The time spent paused is not a slow line
+            let task = self.tasklist.tasks[idx].id();
+            if let Some(line_rate) = self.line_rates.get_mut(&task) {
+                line_rate.flag_stall();
+            }
+            self.set_task_state(idx, TaskState::Running);
+        }
+        !paused.is_empty()
+    }
+
+    /// Dispatches idle tasks, in queue order from the current one, until
+    /// `WORKERS` tasks are running. Each result comes back tagged with its task
+    /// id, since tasks inserted meanwhile shift the indices, and the run
+    /// generation it was started in. The current task
+    /// follows the earliest task still running.
+    fn fill_workers(&mut self) -> Command<Message> {
+        let dispatches = self.tasks_to_dispatch();
+        let Some(jlcontext) = self.jlcontext.as_ref() else {
+            return Command::none();
+        };
+        let mut commands = Vec::new();
+
+        for &idx in &dispatches {
+            let generation = self.run_generation;
+            let task = self.tasklist.tasks[idx].id();
+            let images = self.tasklist.tasks[idx].content().clone();
+            commands.push(Command::perform(
+                jlcontext.dispatch(task, images),
+                move |dispatched| Message::WorkerFinished(task, generation, dispatched),
+            ));
+            self.line_rates.insert(task, LineRateEstimator::default());
+        }
+
+        for idx in dispatches {
+            self.set_task_state(idx, TaskState::Running);
+        }
+
+        if let Some(&first) = self.running_tasks().first() {
+            self.tasklist.current_task = Some(first);
+        }
+
+        Command::batch(commands)
+    }
+
+    /// Indices of the tasks Play dispatches: the current task if it is idle,
+    /// or with `parallel_tasks`, idle tasks in queue order from the current
+    /// one until `WORKERS` tasks hold a worker.
+    fn tasks_to_dispatch(&self) -> Vec<usize> {
+        let Some(from) = self.tasklist.current_task else {
+            return vec![];
+        };
+        let tasks = &self.tasklist.tasks;
+        if !self.parallel_tasks {
+            return tasks[from].is_idle().then_some(from).into_iter().collect();
+        }
+
+        // Paused tasks hold on to their worker until resumed
+        let busy = self.running_tasks().len() + self.paused_tasks().len();
+        (from..tasks.len())
+            .filter(|&idx| tasks[idx].is_idle())
+            .take(WORKERS.saturating_sub(busy))
+            .collect()
+    }
+
+    /// Moves past the finished task at `idx` and starts the next one, unless
+    /// the queue is exhausted, was asked to pause after the current task or
+    /// was paused while the task scanned its last image. Either way the pause
+    /// request is used up, so the next Play runs on.
+    fn advance_queue(&mut self, idx: usize) -> Command<Message> {
+        if idx + 1 >= self.tasklist.tasks.len() {
+            self.pause_after_current = false;
+            return Command::none();
+        }
+
+        self.tasklist.current_task = Some(idx + 1);
+
+        if self.pause_after_current || self.is_suspended() {
+            self.pause_after_current = false;
+            Command::none()
+        } else {
+            Command::perform(async {}, |_| Message::PlayPressed)
         }
     }
 }
@@ -89,113 +888,606 @@ enum Message {
     SizeChanged(ExponentialNumber),
     XOffsetChanged(ExponentialNumber),
     YOffsetChanged(ExponentialNumber),
+    RotationChanged(ExponentialNumber),
     LineTimeChanged(ExponentialNumber),
     // ScanSpeedChanged(ExponentialNumber),
     StartVoltageChanged(ExponentialNumber),
     StopVoltageChanged(ExponentialNumber),
     StepVoltageChanged(ExponentialNumber),
+    RecomputeEstimates(u64),
+    SweepModeToggled(bool),
+    LogSpacingToggled(bool),
+    LogPointsChanged(String),
+    SweepDirectionSelected(SweepDirection),
+    Sweep2dToggled(bool),
+    SetPointChanged(ExponentialNumber),
+    StartSetPointChanged(ExponentialNumber),
+    StopSetPointChanged(ExponentialNumber),
+    StepSetPointChanged(ExponentialNumber),
+    BiasListChanged(String),
+    BiasListPathChanged(String),
+    LoadBiasList,
     AddToQueue,
+    AddToQueueAndKeep,
+    InsertPositionSelected(InsertPosition),
+    OutputFormatSelected(OutputFormat),
+    LockFormToggled(bool),
+    BatchPathChanged(String),
+    ImportBatch,
     NameChanged(String),
     PlayPressed,
+    /// Scans the form's first image right away, outside the queue.
+    ScanNow,
+    /// A scan started by [`Message::ScanNow`] came back, tagged with the run
+    /// generation it was started in.
+    QuickScanFinished(u64, Dispatched),
     PausePressed,
+    /// Stop confirmed: interrupt every running and paused task.
+    ConfirmStop,
     StopPressed,
+    PauseAfterCurrentToggled(bool),
+    ParallelTasksToggled(bool),
+    ReduceMotionToggled(bool),
+    /// A task run by the worker pool came back: task id, run generation and
+    /// outcome.
+    WorkerFinished(u64, u64, Dispatched),
+    /// The task run on its own came back: task id, run generation and
+    /// outcome.
+    TaskFinished(u64, u64, Dispatched),
     MenuPressed,
     ImagesButtonPressed,
     GraphButtonPressed,
     SettingsButtonPressed,
-    TaskMessage(TaskMessage),
+    ProcedurePathChanged(String),
+    OperatorChanged(String),
+    NotifyOnFinishToggled(bool),
+    ThemeSelected(ThemeChoice),
+    TestPatternSelected(TestPattern),
+    InterpolationSelected(Interpolation),
+    ColormapSelected(Colormap),
+    TaskMessage(usize, TaskMessage),
     TaskRunning(usize),
+    /// Pick up the progress running scans reported since the last poll.
+    PollProgress,
+    LineCompleted(usize),
+    /// Percentage of a running task done, reported as its lines complete.
+    TaskProgress(usize, f32),
+    /// The Julia runtime paused for garbage collection while running a task.
+    GcStalled(usize),
     TaskCompleted(usize),
-    TaskFailed(usize),
+    /// A task stopped on an error, with the error's message.
+    TaskFailed(usize, String),
     FocusNext,
     FocusPrevious,
+    SelectNextTask,
+    SelectPreviousTask,
+    ClickedOutside,
+    PlotFocused(bool),
+    OffsetChanged(f64, f64),
+    SnapToGridToggled(bool),
+    GridSpacingChanged(ExponentialNumber),
+    PlacingStsToggled(bool),
+    ClearCompletedPressed,
+    /// Reverse the last change to the queue.
+    Undo,
+    /// Make the last undone change to the queue again.
+    Redo,
+    StsPlaced(STSType),
+    StsGridSizeSelected(u32),
+    /// Add a spectroscopy grid over the scan area.
+    AddStsGridPressed,
+    ClearStsPressed,
+    /// Julia cannot start twice in one process: hand the queue over to a new one.
+    RelaunchApp,
+    /// A dialog button was pressed: closes the top dialog, then handles the message.
+    DialogResponse(Box<Message>),
+    DialogDismissed,
 }
 
-impl Application for R9Control {
-    type Executor = executor::Default;
-    type Flags = ();
-    type Message = Message;
-    type Theme = Theme;
-
-    fn new(_flags: ()) -> (Self, Command<Self::Message>) {
-        (R9Control::default(), Command::none())
+impl Message {
+    /// Whether the message edits the scan parameters in the form.
+    fn edits_form(&self) -> bool {
+        matches!(
+            self,
+            Message::LinesChanged(_)
+                | Message::SizeChanged(_)
+                | Message::XOffsetChanged(_)
+                | Message::YOffsetChanged(_)
+                | Message::OffsetChanged(_, _)
+                | Message::RotationChanged(_)
+                | Message::LineTimeChanged(_)
+                | Message::StartVoltageChanged(_)
+                | Message::StopVoltageChanged(_)
+                | Message::StepVoltageChanged(_)
+                | Message::SweepModeToggled(_)
+                | Message::LogSpacingToggled(_)
+                | Message::LogPointsChanged(_)
+                | Message::SweepDirectionSelected(_)
+                | Message::Sweep2dToggled(_)
+                | Message::SetPointChanged(_)
+                | Message::StartSetPointChanged(_)
+                | Message::StopSetPointChanged(_)
+                | Message::StepSetPointChanged(_)
+                | Message::BiasListChanged(_)
+                | Message::LoadBiasList
+                | Message::StsPlaced(_)
+                | Message::AddStsGridPressed
+                | Message::ClearStsPressed
+        )
     }
+}
 
-    fn title(&self) -> String {
-        String::from("STM External Controller")
-    }
+impl R9Control {
+    /// Applies `msg` to the app. Called through [`Application::update`], which
+    /// refreshes the form state afterwards.
+    fn handle(&mut self, msg: Message) -> Command<Message> {
+        if msg.edits_form() && self.form_locked() {
+            return Command::none();
+        }
 
-    fn update(&mut self, msg: Message) -> Command<Self::Message> {
         match msg {
             Message::AddToQueue => {
-                let id = self.tasklist.tasks.len();
+                self.add_to_queue();
+                Command::none()
+            }
+            Message::AddToQueueAndKeep => {
+                if self.check_form().is_none() {
+                    self.add_to_queue();
+                    self.name = increment_name(&self.name);
+                }
+                Command::none()
+            }
+            Message::LockFormToggled(lock) => {
+                self.lock_form_while_running = lock;
+                Command::none()
+            }
+            Message::InsertPositionSelected(position) => {
+                self.insert_position = position;
+                Command::none()
+            }
+            Message::OutputFormatSelected(format) => {
+                self.default_output_format = format;
+                Command::none()
+            }
+            Message::BatchPathChanged(path) => {
+                self.batch_path = path;
+                Command::none()
+            }
+            Message::ImportBatch => {
+                match BatchImport::from_file(Path::new(&self.batch_path)) {
+                    Ok(import) => {
+                        let queued = import.tasks.len();
+                        // The first row goes where tasks from the form would,
+                        // the rest follow it in file order
+                        let mut last: Option<usize> = None;
+                        for (name, image) in import.tasks {
+                            let mut task = Task::new(vec![image], name, 0);
+                            task.set_output_format(self.default_output_format);
+                            let idx = match last {
+                                None => self.tasklist.insert(task, self.insert_position),
+                                Some(last) => {
+                                    self.tasklist.insert_at(last + 1, task);
+                                    last + 1
+                                }
+                            };
+                            self.record_add(idx);
+                            last = Some(idx);
+                        }
+
+                        self.batch_report = format!("Queued {} rows", queued);
+                        for skipped in import.skipped {
+                            self.batch_report
+                                .push_str(&format!("\nSkipped row {}: {}", skipped.row, skipped.reason));
+                        }
+                    }
+                    Err(e) => self.open_dialog(
+                        Dialog::new("Import failed", e.to_string())
+                            .close_button("OK"),
+                    ),
+                }
+                Command::none()
+            }
+            Message::TestPatternSelected(pattern) => {
+                self.plot_lines = self.lines.unwrap_or(256);
+                self.plot_data = Some(pattern.generate(
+                    self.plot_lines,
+                    self.plot_lines as f32 / 8.0,
+                    1.0,
+                ));
+                Command::none()
+            }
+            Message::DialogResponse(msg) => {
+                self.dialogs.pop();
+                self.update(*msg)
+            }
+            Message::DialogDismissed => {
+                self.dialogs.pop();
+                Command::none()
+            }
+            Message::InterpolationSelected(interpolation) => {
+                self.interpolation = interpolation;
+                Command::none()
+            }
+            Message::ColormapSelected(colormap) => {
+                self.colormap = colormap;
+                self.exporter.colormap = colormap;
+                Command::none()
+            }
+            Message::TaskRunning(idx) => {
+                let Some(jlcontext) = self.jlcontext.as_ref() else {
+                    return Command::none();
+                };
 
-                let start = self.start_voltage.to_f64();
-                let stop = self.stop_voltage.to_f64();
-                let step = self.step_voltage.to_f64();
-                let n = ((start - stop).abs() / step).floor() as usize;
+                // Julia scans in the background so the window stays responsive,
+                // and Pause and Stop reach the task between its images
+                let generation = self.run_generation;
+                let task = self.tasklist.tasks[idx].id();
+                let dispatch = jlcontext.dispatch(task, self.tasklist.tasks[idx].content().clone());
 
-                let mut images: Vec<STMImage> = vec![];
+                self.set_task_state(idx, TaskState::Running);
+                self.line_rates.insert(task, LineRateEstimator::default());
 
-                for bias in linspace(start, stop, n) {
-                    images.push(STMImage::new(
-                        self.lines.unwrap_or(256),
-                        self.size.to_f64(),
-                        self.x_offset.to_f64(),
-                        self.y_offset.to_f64(),
-                        self.line_time.to_f64(),
-                        bias,
-                        None,
-                    ));
+                Command::perform(dispatch, move |dispatched| {
+                    Message::TaskFinished(task, generation, dispatched)
+                })
+            }
+            Message::TaskFinished(task, generation, dispatched) => {
+                self.line_rates.remove(&task);
+                if self.line_rates.is_empty() {
+                    self.running_eta.clear();
+                }
+                if generation != self.run_generation {
+                    return Command::none();
                 }
+                // Tasks may have been added ahead of it since it started
+                let Some(idx) = self.tasklist.position(task) else {
+                    return Command::none();
+                };
 
-                self.tasklist
-                    .tasks
-                    .push(Task::new(images, self.name.clone(), id));
-                if self.tasklist.current_task.is_none() {
-                    self.tasklist.current_task = Some(0);
+                // Keep what was scanned even if a later image failed
+                self.store_data(idx, dispatched.data);
+
+                match dispatched.error {
+                    None => self.update(Message::TaskCompleted(idx)),
+                    Some(e) => self.update(Message::TaskFailed(idx, e)),
+                }
+            }
+            Message::PollProgress => {
+                let Some(jlcontext) = self.jlcontext.as_ref() else {
+                    return Command::none();
+                };
+
+                let mut commands = Vec::new();
+                for event in jlcontext.take_events() {
+                    // Scans of a stopped task, or of a quick scan, are not shown
+                    // in the queue
+                    let Some(idx) = self.tasklist.position(event.task()) else {
+                        continue;
+                    };
+                    let task = &self.tasklist.tasks[idx];
+                    if !task.is_running() && !task.is_paused() {
+                        continue;
+                    }
+
+                    commands.push(self.update(match event {
+                        ScanEvent::Line(_) => Message::LineCompleted(idx),
+                        ScanEvent::GcStalled(_) => Message::GcStalled(idx),
+                    }));
+                }
+                Command::batch(commands)
+            }
+            Message::LineCompleted(idx) => {
+                let task = &self.tasklist.tasks[idx];
+                let line_rate = self.line_rates.entry(task.id()).or_default();
+                line_rate.record_line(Instant::now());
+
+                let images = task.content();
+                let total_lines: u32 = images.iter().map(|image| image.lines()).sum();
+                let nominal = images.first().map_or(0.0, |image| image.line_time());
+                let remaining =
+                    line_rate.remaining(total_lines.saturating_sub(line_rate.lines()), nominal);
+
+                // Parallel tasks each keep their own rate; the current one is shown
+                if self.tasklist.current_task == Some(idx) {
+                    self.running_eta = format_duration(remaining);
+                }
+
+                let progress = if total_lines == 0 {
+                    0.0
+                } else {
+                    100.0 * line_rate.lines() as f32 / total_lines as f32
+                };
+                self.update(Message::TaskProgress(idx, progress))
+            }
+            Message::TaskProgress(idx, progress) => {
+                self.is_dispatching = false;
+                if let Some(task) = self.tasklist.tasks.get_mut(idx) {
+                    task.set_progress(progress);
                 }
                 Command::none()
             }
-            Message::TaskRunning(idx) => {
-                self.tasklist.tasks[idx].state(TaskState::Running);
+            Message::GcStalled(idx) => {
+                let task = self.tasklist.tasks.get(idx).map(|task| task.id());
+                if let Some(line_rate) = task.and_then(|task| self.line_rates.get_mut(&task)) {
+                    line_rate.flag_stall();
+                }
                 Command::none()
             }
             Message::PlayPressed => {
-                self.tasklist.current_task.is_some().then(|| {
-                    let id = self.tasklist.current_task.unwrap();
-                    if self.tasklist.tasks[id].is_idle() {
-                        self.tasklist.tasks[id].state(TaskState::Running);
-                        // send async command to Julia to run the task
-                        self.jlcontext.receiver = {
-                            let (sender, receiver) = crossbeam_channel::bounded(1);
-                            self.jlcontext.julia.try_task(self.tasklist.tasks[id].content()[0].clone(), sender).unwrap();
-                            Some(receiver)
-                        };
-
-                        let result = self.jlcontext.receiver.as_ref().unwrap().recv().unwrap().unwrap();
-                        println!("{:?}", result);
+                if !self.ensure_julia() {
+                    return Command::none();
+                }
+
+                // Carry on where the paused tasks stopped, or past the task
+                // boundary a pause held the queue at
+                self.jlcontext.as_ref().unwrap().resume();
+                if self.resume_paused() {
+                    return Command::none();
+                }
+
+                let Some(&id) = self.tasks_to_dispatch().first() else {
+                    return Command::none();
+                };
+                if let Err(e) = self.jlcontext.as_mut().unwrap().include_procedure() {
+                    self.set_task_state(id, TaskState::Failed(e));
+                    return Command::none();
+                }
+                // A second press starts anyway after a disk space warning
+                if self.disk_warning.take().is_none() {
+                    if let Err(e) = self.check_disk_space(id) {
+                        self.disk_warning = Some(format!(
+                            "Low disk space: {}. Press play again to start anyway.",
+                            e
+                        ));
+                        return Command::none();
                     }
-                });
+                }
+                self.is_dispatching = true;
+                if self.parallel_tasks {
+                    return self.fill_workers();
+                }
+                Command::perform(async move { id }, Message::TaskRunning)
+            }
+            Message::ScanNow => {
+                if self.quick_scan.is_some() || self.check_form().is_some() || !self.ensure_julia()
+                {
+                    return Command::none();
+                }
+                let Some(mut task) = self.quick_scan_task() else {
+                    return Command::none();
+                };
+
+                let jlcontext = self.jlcontext.as_mut().unwrap();
+                if let Err(e) = jlcontext.include_procedure() {
+                    self.open_dialog(Dialog::new("Scan failed", e).close_button("OK"));
+                    return Command::none();
+                }
+
+                let generation = self.run_generation;
+                let dispatch = jlcontext.dispatch(task.id(), task.content().clone());
+                task.state(TaskState::Running);
+                record_state(&self.audit_log, &mut self.exporter, &self.output_dir, &task);
+                self.quick_scan = Some(task);
+
+                Command::perform(dispatch, move |dispatched| {
+                    Message::QuickScanFinished(generation, dispatched)
+                })
+            }
+            Message::QuickScanFinished(generation, dispatched) => {
+                if generation != self.run_generation {
+                    return Command::none();
+                }
+                let Some(mut task) = self.quick_scan.take() else {
+                    return Command::none();
+                };
+
+                for (image, measurement) in task.content_mut().iter_mut().zip(dispatched.data) {
+                    image.set_measurement(measurement);
+                    image.set_operator(&self.operator);
+                }
+                if let Some(image) = task.content().first() {
+                    if let Some(data) = image.data() {
+                        self.plot_lines = image.lines();
+                        self.plot_data = Some(data.iter().map(|&z| z as f32).collect());
+                    }
+                }
+                match dispatched.error {
+                    None => task.state(TaskState::Completed),
+                    Some(e) => {
+                        self.open_dialog(Dialog::new("Scan failed", e.clone()).close_button("OK"));
+                        task.state(TaskState::Failed(e));
+                    }
+                }
+                record_state(&self.audit_log, &mut self.exporter, &self.output_dir, &task);
+                Command::none()
+            }
+            Message::PausePressed => {
+                let running = self.running_tasks();
+                if running.is_empty() {
+                    return Command::none();
+                }
+
+                if let Some(jlcontext) = &self.jlcontext {
+                    jlcontext.suspend();
+                }
+                for idx in running {
+                    self.set_task_state(idx, TaskState::Paused);
+                }
+                Command::none()
+            }
+            Message::TaskCompleted(idx) => {
+                self.is_dispatching = false;
+                self.set_task_state(idx, TaskState::Completed);
+                let command = self.advance_queue(idx);
+                self.notify_if_finished();
+                command
+            }
+            Message::TaskFailed(idx, error) => {
+                self.is_dispatching = false;
+                self.set_task_state(idx, TaskState::Failed(error));
+                let command = self.advance_queue(idx);
+                self.notify_if_finished();
+                command
+            }
+            Message::PauseAfterCurrentToggled(pause) => {
+                self.pause_after_current = pause;
+                Command::none()
+            }
+            Message::MenuPressed => {
+                let versions = self
+                    .jlcontext
+                    .as_ref()
+                    .map_or_else(VersionInfo::without_julia, JuliaContext::version_info);
+
+                self.open_dialog(Dialog::new("About", versions.to_string()).close_button("OK"));
+                Command::none()
+            }
+            Message::SettingsButtonPressed => {
+                self.show_settings = !self.show_settings;
+                Command::none()
+            }
+            Message::ProcedurePathChanged(path) => {
+                if let Some(jlcontext) = &mut self.jlcontext {
+                    jlcontext.set_procedure_path(&path);
+                }
+                self.procedure_path = path;
+                Command::none()
+            }
+            Message::OperatorChanged(operator) => {
+                self.operator = operator;
+                Command::none()
+            }
+            Message::NotifyOnFinishToggled(notify) => {
+                self.notify_on_finish = notify;
+                Command::none()
+            }
+            Message::ThemeSelected(theme) => {
+                self.theme = theme;
                 Command::none()
             }
+            Message::ReduceMotionToggled(reduce) => {
+                self.reduce_motion = reduce;
+                motion::set_reduced(reduce);
+                Command::none()
+            }
+            Message::ParallelTasksToggled(parallel) => {
+                self.parallel_tasks = parallel;
+                Command::none()
+            }
+            Message::WorkerFinished(task, generation, dispatched) => {
+                self.line_rates.remove(&task);
+                if self.line_rates.is_empty() {
+                    self.running_eta.clear();
+                }
+                if generation != self.run_generation {
+                    return Command::none();
+                }
+                let Some(idx) = self.tasklist.position(task) else {
+                    return Command::none();
+                };
+                self.is_dispatching = false;
+
+                self.store_data(idx, dispatched.data);
+                match dispatched.error {
+                    None => self.set_task_state(idx, TaskState::Completed),
+                    Some(e) => self.set_task_state(idx, TaskState::Failed(e)),
+                }
+
+                if self.pause_after_current {
+                    if self.running_tasks().is_empty() {
+                        self.pause_after_current = false;
+                    }
+                    return Command::none();
+                }
+                // Paused: free workers wait for Play instead of taking new tasks
+                if self.is_suspended() {
+                    return Command::none();
+                }
+                let command = self.fill_workers();
+                self.notify_if_finished();
+                command
+            }
             Message::StopPressed => {
-                self.tasklist.current_task.is_some().then(|| {
-                    let id = self.tasklist.current_task.unwrap();
-                    // send async command to Julia to run the task
-                    self.tasklist.tasks[id]
-                        .state(TaskState::Failed(String::from("Interrupted by user.")));
+                // Stopping throws away the scans in progress, so it is confirmed
+                if self.queue_busy() {
+                    self.open_dialog(
+                        Dialog::new(
+                            "Stop the run?",
+                            "Scans in progress are interrupted and their tasks marked as failed.",
+                        )
+                        .button("Stop", Message::ConfirmStop)
+                        .close_button("Keep scanning"),
+                    );
+                    return Command::none();
+                }
+                self.update(Message::ConfirmStop)
+            }
+            Message::ConfirmStop => {
+                // Results still on their way belong to the old generation
+                // and are ignored
+                self.run_generation += 1;
+                self.is_dispatching = false;
+                if let Some(jlcontext) = &mut self.jlcontext {
+                    jlcontext.cancel();
+                    jlcontext.resume();
+                }
+                if let Some(mut task) = self.quick_scan.take() {
+                    task.state(TaskState::Failed(String::from("Interrupted by user.")));
+                    record_state(&self.audit_log, &mut self.exporter, &self.output_dir, &task);
+                }
+                // The current task is failed last, together with moving past it
+                for idx in self.paused_tasks() {
+                    if Some(idx) != self.tasklist.current_task {
+                        self.set_task_state(idx, TaskState::Failed(String::from("Interrupted by user.")));
+                    }
+                }
+                for idx in self.running_tasks() {
+                    if Some(idx) != self.tasklist.current_task {
+                        self.set_task_state(idx, TaskState::Failed(String::from("Interrupted by user.")));
+                    }
+                }
+                if let Some(id) = self.tasklist.current_task {
+                    self.set_task_state(id, TaskState::Failed(String::from("Interrupted by user.")));
                     self.tasklist.current_task = Some(min(id + 1, self.tasklist.tasks.len() - 1));
-                });
+                }
+                Command::none()
+            }
+            Message::RelaunchApp => {
+                self.run_generation += 1;
+                self.is_dispatching = false;
+                for idx in self.running_tasks().into_iter().chain(self.paused_tasks()) {
+                    self.set_task_state(idx, TaskState::Failed(String::from("Julia stopped.")));
+                }
+
+                let path = std::env::temp_dir().join(RECOVERED_QUEUE);
+                let relaunched = self
+                    .tasklist
+                    .save(&path)
+                    .and_then(|()| std::env::current_exe())
+                    .and_then(|exe| {
+                        std::process::Command::new(exe)
+                            .arg("--open")
+                            .arg(&path)
+                            .spawn()
+                    });
+                match relaunched {
+                    Ok(_) => {
+                        self.save_settings();
+                        std::process::exit(0);
+                    }
+                    Err(e) => self.open_dialog(
+                        Dialog::new(
+                            "Restart failed",
+                            format!("Could not restart the app: {}", e),
+                        )
+                        .close_button("OK"),
+                    ),
+                }
                 Command::none()
             }
             Message::LinesChanged(lines) => {
                 self.lines = Some(lines);
-                self.time_to_finish = calculate_time_remaining(
-                    self.lines.unwrap_or(0) as f64,
-                    self.line_time.to_f64(),
-                    self.total_images as f64,
-                );
+                self.refresh_time_to_finish();
                 Command::none()
             }
             Message::SizeChanged(size) => {
@@ -210,13 +1502,13 @@ impl Application for R9Control {
                 self.y_offset = y_offset;
                 Command::none()
             }
+            Message::RotationChanged(rotation) => {
+                self.rotation = rotation;
+                Command::none()
+            }
             Message::LineTimeChanged(line_time) => {
                 self.line_time = line_time;
-                self.time_to_finish = calculate_time_remaining(
-                    self.lines.unwrap_or(0) as f64,
-                    self.line_time.to_f64(),
-                    self.total_images as f64,
-                );
+                self.refresh_time_to_finish();
                 Command::none()
             }
             // Message::ScanSpeedChanged(scan_speed) => {
@@ -225,74 +1517,343 @@ impl Application for R9Control {
             // }
             Message::StartVoltageChanged(start_voltage) => {
                 self.start_voltage = start_voltage;
-                self.total_images = calculate_total_images(
-                    self.start_voltage.to_f64(),
-                    self.stop_voltage.to_f64(),
-                    self.step_voltage.to_f64(),
-                );
-                self.time_to_finish = calculate_time_remaining(
-                    self.lines.unwrap_or(0) as f64,
-                    self.line_time.to_f64(),
-                    self.total_images as f64,
-                );
-                Command::none()
+                self.schedule_recompute()
             }
             Message::StopVoltageChanged(stop_voltage) => {
                 self.stop_voltage = stop_voltage;
-                self.total_images = calculate_total_images(
-                    self.start_voltage.to_f64(),
-                    self.stop_voltage.to_f64(),
-                    self.step_voltage.to_f64(),
-                );
-                self.time_to_finish = calculate_time_remaining(
-                    self.lines.unwrap_or(0) as f64,
-                    self.line_time.to_f64(),
-                    self.total_images as f64,
-                );
-                Command::none()
+                self.schedule_recompute()
             }
             Message::StepVoltageChanged(step_voltage) => {
                 self.step_voltage = step_voltage;
-                self.total_images = calculate_total_images(
-                    self.start_voltage.to_f64(),
-                    self.stop_voltage.to_f64(),
-                    self.step_voltage.to_f64(),
-                );
-                self.time_to_finish = calculate_time_remaining(
-                    self.lines.unwrap_or(0) as f64,
-                    self.line_time.to_f64(),
-                    self.total_images as f64,
-                );
+                self.schedule_recompute()
+            }
+            Message::RecomputeEstimates(generation) => {
+                if generation == self.estimates_generation {
+                    self.recompute_estimates();
+                }
+                Command::none()
+            }
+            Message::Sweep2dToggled(sweep_2d) => {
+                self.sweep_2d = sweep_2d;
+                self.recompute_estimates();
+                Command::none()
+            }
+            Message::SetPointChanged(set_point) => {
+                self.set_point = set_point;
+                Command::none()
+            }
+            Message::StartSetPointChanged(set_point) => {
+                self.start_set_point = set_point;
+                self.schedule_recompute()
+            }
+            Message::StopSetPointChanged(set_point) => {
+                self.stop_set_point = set_point;
+                self.schedule_recompute()
+            }
+            Message::StepSetPointChanged(set_point) => {
+                self.step_set_point = set_point;
+                self.schedule_recompute()
+            }
+            Message::SweepModeToggled(explicit) => {
+                self.sweep_mode = if explicit {
+                    SweepMode::List
+                } else {
+                    SweepMode::Range
+                };
+                self.recompute_estimates();
+                Command::none()
+            }
+            Message::LogSpacingToggled(log_spacing) => {
+                self.log_spacing = log_spacing;
+                self.recompute_estimates();
+                Command::none()
+            }
+            Message::LogPointsChanged(points) => {
+                self.log_points = points;
+                self.recompute_estimates();
+                Command::none()
+            }
+            Message::SweepDirectionSelected(direction) => {
+                self.sweep_direction = direction;
+                Command::none()
+            }
+            Message::BiasListChanged(list) => {
+                self.bias_list = list;
+                self.bias_list_rejects = parse_bias_list(&self.bias_list).1.join("\n");
+                self.recompute_estimates();
+                Command::none()
+            }
+            Message::BiasListPathChanged(path) => {
+                self.bias_list_path = path;
                 Command::none()
             }
+            Message::LoadBiasList => match sweep::load_bias_list(Path::new(&self.bias_list_path)) {
+                Ok(list) => self.update(Message::BiasListChanged(list)),
+                Err(e) => {
+                    self.open_dialog(
+                        Dialog::new("Loading bias list failed", e.to_string()).close_button("OK"),
+                    );
+                    Command::none()
+                }
+            },
             Message::NameChanged(value) => {
                 self.name = value;
                 Command::none()
             }
-            Message::FocusNext => iced::widget::focus_next(),
-            Message::FocusPrevious => iced::widget::focus_previous(),
+            Message::TaskMessage(idx, TaskMessage::Select) => {
+                self.tasklist.selected = Some(idx);
+                self.tasklist_focused = true;
+                self.plot_focused = false;
+                Command::none()
+            }
+            Message::TaskMessage(idx, TaskMessage::Retry) => {
+                if self.tasklist.tasks.get(idx).map_or(false, Task::is_failed) {
+                    self.set_task_state(idx, TaskState::Idle);
+                    self.tasklist.current_task = Some(idx);
+                }
+                Command::none()
+            }
+            Message::TaskMessage(idx, TaskMessage::Delete) => {
+                if idx < self.tasklist.tasks.len() && !self.queue_busy() {
+                    let task = self.tasklist.remove(idx);
+                    self.history.record(QueueEdit::Delete { idx, task });
+                }
+                Command::none()
+            }
+            Message::Undo => {
+                if !self.queue_busy() {
+                    self.history.undo(&mut self.tasklist);
+                }
+                Command::none()
+            }
+            Message::Redo => {
+                if !self.queue_busy() {
+                    self.history.redo(&mut self.tasklist);
+                }
+                Command::none()
+            }
+            Message::TaskMessage(idx, TaskMessage::Duplicate) => {
+                if idx < self.tasklist.tasks.len() && !self.queue_busy() {
+                    let copy = self.tasklist.duplicate(idx);
+                    self.record_add(copy);
+                }
+                Command::none()
+            }
+            Message::TaskMessage(idx, TaskMessage::Save) => {
+                let Some(task) = self.tasklist.tasks.get(idx) else {
+                    return Command::none();
+                };
+                let dir = self.output_dir.as_path();
+                let saved = export_task(&mut self.exporter, task, dir).and_then(|mut paths| {
+                    for (i, image) in task.content().iter().enumerate() {
+                        let stem = format!("{}_{}", task.description(), i);
+                        paths.extend(image.export_spectroscopy_csv(dir, &stem)?);
+                    }
+                    Ok(paths)
+                });
+                let dialog = match saved {
+                    Ok(paths) => Dialog::new(
+                        "Saved",
+                        format!("Wrote {} files to {}", paths.len(), dir.display()),
+                    ),
+                    Err(e) => Dialog::new(
+                        "Save failed",
+                        format!("Could not save {:?}: {}", task.description(), e),
+                    ),
+                };
+                self.open_dialog(dialog.close_button("OK"));
+                Command::none()
+            }
+            Message::TaskMessage(idx, TaskMessage::ImageNotesChanged(i, notes)) => {
+                if let Some(image) = self
+                    .tasklist
+                    .tasks
+                    .get_mut(idx)
+                    .and_then(|task| task.content_mut().get_mut(i))
+                {
+                    image.set_notes(notes);
+                }
+                Command::none()
+            }
+            Message::TaskMessage(idx, msg) => {
+                if let Some(task) = self.tasklist.tasks.get_mut(idx) {
+                    task.update(msg);
+                }
+                Command::none()
+            }
+            Message::FocusNext => {
+                self.tasklist_focused = false;
+                self.plot_focused = false;
+                focus::focus_next()
+            }
+            Message::FocusPrevious => {
+                self.tasklist_focused = false;
+                self.plot_focused = false;
+                focus::focus_previous()
+            }
+            Message::SelectNextTask => {
+                if self.tasklist_focused {
+                    self.tasklist.select_next();
+                }
+                Command::none()
+            }
+            Message::SelectPreviousTask => {
+                if self.tasklist_focused {
+                    self.tasklist.select_previous();
+                }
+                Command::none()
+            }
+            Message::ClickedOutside => {
+                self.tasklist_focused = false;
+                self.plot_focused = false;
+                Command::none()
+            }
+            Message::PlotFocused(focused) => {
+                self.plot_focused = focused;
+                if focused {
+                    self.tasklist_focused = false;
+                }
+                Command::none()
+            }
+            Message::ScanAreaChanged(center) => {
+                let (x, y) = match self.grid() {
+                    Some(grid) => {
+                        let geometry =
+                            ScanGeometry::new(self.size.to_f64(), center.x(), center.y(), 0.0)
+                                .snapped(grid);
+                        (geometry.x_offset, geometry.y_offset)
+                    }
+                    None => (center.x(), center.y()),
+                };
+                self.update(Message::OffsetChanged(x, y))
+            }
+            Message::OffsetChanged(x, y) => {
+                self.x_offset = ExponentialNumber::new(x * 1e9, -9);
+                self.y_offset = ExponentialNumber::new(y * 1e9, -9);
+
+                // The plot already snapped the offset; bring the size along
+                if let Some(grid) = self.grid() {
+                    let size = ScanGeometry::new(self.size.to_f64(), x, y, 0.0)
+                        .snapped(grid)
+                        .size;
+                    self.size = ExponentialNumber::new(size * 1e9, -9);
+                }
+                Command::none()
+            }
+            Message::SnapToGridToggled(snap) => {
+                self.snap_to_grid = snap;
+                Command::none()
+            }
+            Message::ClearCompletedPressed => {
+                if !self.queue_busy() {
+                    self.tasklist.clear_completed();
+                }
+                Command::none()
+            }
+            Message::PlacingStsToggled(placing) => {
+                self.placing_sts = placing;
+                Command::none()
+            }
+            Message::StsPlaced(sts) => {
+                self.spectroscopy.push(sts);
+                Command::none()
+            }
+            Message::StsGridSizeSelected(size) => {
+                self.sts_grid_size = size;
+                Command::none()
+            }
+            Message::AddStsGridPressed => {
+                self.spectroscopy.push(STSType::Grid {
+                    rows: self.sts_grid_size,
+                    cols: self.sts_grid_size,
+                });
+                Command::none()
+            }
+            Message::ClearStsPressed => {
+                self.spectroscopy.clear();
+                Command::none()
+            }
+            Message::GridSpacingChanged(spacing) => {
+                self.grid_spacing = spacing;
+                Command::none()
+            }
             _ => Command::none(),
         }
     }
+}
+
+impl Application for R9Control {
+    type Executor = executor::Default;
+    type Flags = Option<PathBuf>;
+    type Message = Message;
+    type Theme = Theme;
+
+    fn new(queue: Option<PathBuf>) -> (Self, Command<Self::Message>) {
+        let mut app = R9Control::default();
+        if let Some(path) = queue {
+            match TaskList::load(&path) {
+                Ok(tasklist) => app.tasklist = tasklist,
+                Err(e) => app.open_dialog(
+                    Dialog::new(
+                        "Queue not restored",
+                        format!("Could not read {:?}: {}", path, e),
+                    )
+                    .close_button("OK"),
+                ),
+            }
+        }
+        app.refresh_form_state();
+        (app, Command::none())
+    }
+
+    fn title(&self) -> String {
+        String::from("STM External Controller")
+    }
+
+    fn update(&mut self, msg: Message) -> Command<Self::Message> {
+        let command = self.handle(msg);
+        self.refresh_form_state();
+        command
+    }
+
+    fn theme(&self) -> Theme {
+        match self.theme {
+            ThemeChoice::Light => Theme::Light,
+            ThemeChoice::Dark => Theme::Dark,
+        }
+    }
 
     fn subscription(&self) -> Subscription<Message> {
-        subscription::events_with(|event, _status| match event {
-            Event::Keyboard(keyboard_event) => match keyboard_event {
-                keyboard::Event::KeyPressed {
-                    key_code: keyboard::KeyCode::Tab,
-                    modifiers,
-                } => Some(if modifiers.shift() {
-                    Message::FocusPrevious
-                } else {
-                    Message::FocusNext
-                }),
-                _ => None,
-            },
-            _ => None,
-        })
+        // Scans report their progress on a channel, polled while any runs
+        let progress = if self.queue_busy() || self.quick_scan.is_some() {
+            iced::time::every(PROGRESS_POLL).map(|_| Message::PollProgress)
+        } else {
+            Subscription::none()
+        };
+
+        // Shortcuts stay off while a dialog has focus
+        if !self.dialogs.is_empty() {
+            return progress;
+        }
+
+        let shortcuts = subscription::events_with(shortcut);
+
+        Subscription::batch([progress, shortcuts])
     }
 
     fn view(&self) -> Element<Message> {
+        let overall_progress = self.tasklist.overall_progress();
+
+        let mut scan_now_button = button(if self.quick_scan.is_some() {
+            "Scanning..."
+        } else {
+            "Scan now"
+        })
+        .style(theme::Button::Custom(Box::from(ToolBarTheme)));
+        if self.quick_scan.is_none() && self.form_error.is_none() {
+            scan_now_button = scan_now_button.on_press(Message::ScanNow);
+        }
+
         let toolbar = container(
             row![
                 horizontal_space(2),
@@ -305,6 +1866,34 @@ impl Application for R9Control {
                 button(graph_icon())
                     .on_press(Message::GraphButtonPressed)
                     .style(theme::Button::Custom(Box::from(ToolBarTheme))),
+                pick_list(
+                    &TestPattern::ALL[..],
+                    None,
+                    Message::TestPatternSelected
+                )
+                .placeholder("Test pattern"),
+                pick_list(
+                    &Interpolation::ALL[..],
+                    Some(self.interpolation),
+                    Message::InterpolationSelected
+                ),
+                pick_list(
+                    &Colormap::ALL[..],
+                    Some(self.colormap),
+                    Message::ColormapSelected
+                ),
+                checkbox("Place STS", self.placing_sts, Message::PlacingStsToggled),
+                pick_list(
+                    &STS_GRID_SIZES[..],
+                    Some(self.sts_grid_size),
+                    Message::StsGridSizeSelected
+                ),
+                button("Add STS grid")
+                    .on_press(Message::AddStsGridPressed)
+                    .style(theme::Button::Custom(Box::from(ToolBarTheme))),
+                button(text(format!("Clear STS ({})", self.spectroscopy.len())))
+                    .on_press(Message::ClearStsPressed)
+                    .style(theme::Button::Custom(Box::from(ToolBarTheme))),
                 horizontal_space(Length::Fill),
                 row![
                     button(play_icon())
@@ -316,9 +1905,33 @@ impl Application for R9Control {
                     button(stop_icon())
                         .on_press(Message::StopPressed)
                         .style(theme::Button::Custom(Box::from(ToolBarTheme))),
-                ],
+                    scan_now_button,
+                    column![
+                        text(percentage(overall_progress, &(0.0..=100.0))).size(14),
+                        progress_bar(0.0..=100.0, overall_progress)
+                            .width(80)
+                            .height(4),
+                    ]
+                    .align_items(Alignment::Center),
+                    checkbox(
+                        "Pause after current",
+                        self.pause_after_current,
+                        Message::PauseAfterCurrentToggled
+                    ),
+                    checkbox(
+                        format!("Run {} tasks in parallel", WORKERS),
+                        self.parallel_tasks,
+                        Message::ParallelTasksToggled
+                    ),
+                ]
+                .align_items(Alignment::Center),
                 horizontal_space(Length::Fill),
                 horizontal_space(92.0),
+                checkbox(
+                    "Reduce motion",
+                    self.reduce_motion,
+                    Message::ReduceMotionToggled
+                ),
                 button(gear_icon())
                     .on_press(Message::SettingsButtonPressed)
                     .style(theme::Button::Custom(Box::from(ToolBarTheme))),
@@ -330,9 +1943,53 @@ impl Application for R9Control {
         .padding(8)
         .style(theme::Container::Custom(Box::from(ToolBarTheme)));
 
-        let scan_area = Canvas::new(Plot::<Message>::new())
+        let settings = container(
+            row![
+                text("Julia procedure"),
+                text_input("Path to a .jl file", &self.procedure_path)
+                    .on_input(Message::ProcedurePathChanged)
+                    .padding(5),
+                text("Operator"),
+                text_input("Name", &self.operator)
+                    .on_input(Message::OperatorChanged)
+                    .padding(5),
+                checkbox(
+                    "Notify when the queue finishes",
+                    self.notify_on_finish,
+                    Message::NotifyOnFinishToggled
+                ),
+                text("Theme"),
+                pick_list(
+                    &ThemeChoice::ALL[..],
+                    Some(self.theme),
+                    Message::ThemeSelected
+                ),
+            ]
+            .spacing(10)
+            .align_items(Alignment::Center),
+        )
+        .padding(8);
+
+        let scan_area = Canvas::new(Plot::<Message>::new().geometry(ScanGeometry::new(
+            self.size.to_f64(),
+            self.x_offset.to_f64(),
+            self.y_offset.to_f64(),
+            self.rotation.to_f64(),
+        ))
+        .data(self.plot_data.as_deref(), self.plot_lines)
+        .interpolation(self.interpolation)
+        .colormap(self.colormap)
+        .grid(self.grid())
+        .focused(self.plot_focused)
+        .on_focus(Message::PlotFocused)
+        .on_offset(Message::OffsetChanged)
+        .on_click(Message::ScanAreaChanged)
+        .placing_sts(self.placing_sts)
+        .spectroscopy(&self.spectroscopy)
+        .on_sts(Message::StsPlaced))
             .width(Length::Fill)
             .height(Length::Fill);
+        let scan_area = Busy::new(scan_area).active(self.is_dispatching);
 
         let lines_list: PickList<u32, Message, Renderer> =
             pick_list(&LinesOptions::ALL[..], self.lines, Message::LinesChanged)
@@ -346,7 +2003,8 @@ impl Application for R9Control {
             ),
             "m",
             Message::SizeChanged,
-        );
+        )
+        .default_value(ExponentialNumber::new(50.0, -9));
 
         let x_offset_input = ScientificSpinBox::new(
             self.x_offset,
@@ -356,7 +2014,8 @@ impl Application for R9Control {
             ),
             "m",
             Message::XOffsetChanged,
-        );
+        )
+        .default_value(ExponentialNumber::new(0.0, -9));
 
         let y_offset_input = ScientificSpinBox::new(
             self.y_offset,
@@ -366,7 +2025,19 @@ impl Application for R9Control {
             ),
             "m",
             Message::YOffsetChanged,
-        );
+        )
+        .default_value(ExponentialNumber::new(0.0, -9));
+
+        let rotation_input = ScientificSpinBox::new(
+            self.rotation,
+            Bounds::new(
+                ExponentialNumber::new(0.0, 0),
+                ExponentialNumber::new(360.0, 0),
+            ),
+            "°",
+            Message::RotationChanged,
+        )
+        .default_value(ExponentialNumber::new(0.0, 0));
 
         let line_time_input = ScientificSpinBox::new(
             self.line_time,
@@ -376,7 +2047,8 @@ impl Application for R9Control {
             ),
             "s",
             Message::LineTimeChanged,
-        );
+        )
+        .default_value(ExponentialNumber::new(102.4, -3));
 
         // let scan_speed_input = ScientificSpinBox::new(
         //     self.scan_speed,
@@ -393,6 +2065,16 @@ impl Application for R9Control {
         let time_to_finish_display: Text<'static, Renderer> = text(&self.time_to_finish);
 
         let spacing = 5;
+        let grid_spacing_input = ScientificSpinBox::new(
+            self.grid_spacing,
+            Bounds::new(
+                ExponentialNumber::new(1.0, -9),
+                ExponentialNumber::new(100.0, -9),
+            ),
+            "m",
+            Message::GridSpacingChanged,
+        );
+
         let scan_area_params = column![
             row![
                 "Lines per frame:",
@@ -406,6 +2088,14 @@ impl Application for R9Control {
                 .align_items(Alignment::Center),
             row!["Y offset:", horizontal_space(Length::Fill), y_offset_input]
                 .align_items(Alignment::Center),
+            row!["Rotation:", horizontal_space(Length::Fill), rotation_input]
+                .align_items(Alignment::Center),
+            row![
+                checkbox("Snap to grid", self.snap_to_grid, Message::SnapToGridToggled),
+                horizontal_space(Length::Fill),
+                grid_spacing_input
+            ]
+            .align_items(Alignment::Center),
             // row![
             //     "Scan speed:",
             //     horizontal_space(Length::Fill),
@@ -451,36 +2141,198 @@ impl Application for R9Control {
             Message::StepVoltageChanged,
         );
 
+        let set_point_bounds = Bounds::new(
+            ExponentialNumber::new(1.0, -12),
+            ExponentialNumber::new(10.0, -9),
+        );
+
+        let set_point_input = ScientificSpinBox::new(
+            self.set_point,
+            set_point_bounds,
+            "A",
+            Message::SetPointChanged,
+        )
+        .default_value(ExponentialNumber::new(100.0, -12));
+
+        let start_set_point_input = ScientificSpinBox::new(
+            self.start_set_point,
+            set_point_bounds,
+            "A",
+            Message::StartSetPointChanged,
+        );
+
+        let stop_set_point_input = ScientificSpinBox::new(
+            self.stop_set_point,
+            set_point_bounds,
+            "A",
+            Message::StopSetPointChanged,
+        );
+
+        let step_set_point_input = ScientificSpinBox::new(
+            self.step_set_point,
+            set_point_bounds,
+            "A",
+            Message::StepSetPointChanged,
+        );
+
         let name: TextInput<'static, Message, Renderer> =
             text_input("Choose an alias for the image set...", &self.name)
                 .on_input(Message::NameChanged)
                 .size(20)
                 .width(Length::Fill);
 
-        let add_to_queue_button: Button<'static, Message, Renderer> = button("Add to queue")
-            .width(Length::Fill)
+        let form_status = row![
+            checkbox(
+                "Lock while running",
+                self.lock_form_while_running,
+                Message::LockFormToggled
+            ),
+            horizontal_space(Length::Fill),
+            text(if self.form_locked() {
+                String::from("Locked while running")
+            } else if let Some(error) = &self.form_error {
+                error.clone()
+            } else if self.form_dirty {
+                String::from("Edited since queued")
+            } else {
+                String::new()
+            }),
+        ]
+        .align_items(Alignment::Center);
+
+        let mut add_to_queue_button: Button<'static, Message, Renderer> =
+            button("Add to queue").width(Length::Fill).padding(10);
+
+        let mut add_and_keep_button: Button<'static, Message, Renderer> =
+            button("Add & keep").padding(10);
+
+        // Disabled, with the reason in the form status, while invalid
+        if self.form_error.is_none() {
+            add_to_queue_button = add_to_queue_button.on_press(Message::AddToQueue);
+            add_and_keep_button = add_and_keep_button.on_press(Message::AddToQueueAndKeep);
+        }
+
+        let batch_path: TextInput<'static, Message, Renderer> =
+            text_input("Batch file (.csv or .json)...", &self.batch_path)
+                .on_input(Message::BatchPathChanged)
+                .on_submit(Message::ImportBatch)
+                .padding(10)
+                .width(Length::Fill);
+
+        let import_batch_button: Button<'static, Message, Renderer> = button("Import")
             .padding(10)
-            .on_press(Message::AddToQueue);
+            .on_press(Message::ImportBatch);
 
-        let voltage_params = column![
-            row![
-                "Start voltage:",
-                horizontal_space(Length::Fill),
-                start_voltage_input
+        let bias_inputs: Element<Message> = match self.sweep_mode {
+            SweepMode::Range => column![
+                row![
+                    "Start voltage:",
+                    horizontal_space(Length::Fill),
+                    start_voltage_input
+                ]
+                .align_items(Alignment::Center),
+                row![
+                    "Stop voltage:",
+                    horizontal_space(Length::Fill),
+                    stop_voltage_input
+                ]
+                .align_items(Alignment::Center),
+                if self.log_spacing {
+                    row![
+                        "Points:",
+                        horizontal_space(Length::Fill),
+                        text_input("5", &self.log_points)
+                            .on_input(Message::LogPointsChanged)
+                            .padding(5)
+                            .width(Length::Fixed(80.0))
+                    ]
+                } else {
+                    row![
+                        "Step voltage:",
+                        horizontal_space(Length::Fill),
+                        step_voltage_input
+                    ]
+                }
+                .align_items(Alignment::Center),
+                checkbox(
+                    "Logarithmic spacing",
+                    self.log_spacing,
+                    Message::LogSpacingToggled
+                ),
             ]
-            .align_items(Alignment::Center),
-            row![
-                "Stop voltage:",
-                horizontal_space(Length::Fill),
-                stop_voltage_input
+            .spacing(spacing)
+            .into(),
+            SweepMode::List => column![
+                text_input("Bias values in V, e.g. -1, -0.5, 0, 0.5, 1", &self.bias_list)
+                    .on_input(Message::BiasListChanged)
+                    .padding(5)
+                    .width(Length::Fill),
+                row![
+                    text_input("Bias list file...", &self.bias_list_path)
+                        .on_input(Message::BiasListPathChanged)
+                        .on_submit(Message::LoadBiasList)
+                        .padding(5)
+                        .width(Length::Fill),
+                    button("Load").padding(5).on_press(Message::LoadBiasList),
+                ]
+                .spacing(spacing),
+                text(&self.bias_list_rejects),
             ]
-            .align_items(Alignment::Center),
+            .spacing(spacing)
+            .into(),
+        };
+
+        let set_point_inputs: Element<Message> = if self.sweep_2d {
+            column![
+                row![
+                    "Start set point:",
+                    horizontal_space(Length::Fill),
+                    start_set_point_input
+                ]
+                .align_items(Alignment::Center),
+                row![
+                    "Stop set point:",
+                    horizontal_space(Length::Fill),
+                    stop_set_point_input
+                ]
+                .align_items(Alignment::Center),
+                row![
+                    "Step set point:",
+                    horizontal_space(Length::Fill),
+                    step_set_point_input
+                ]
+                .align_items(Alignment::Center),
+            ]
+            .spacing(spacing)
+            .into()
+        } else {
+            row!["Set point:", horizontal_space(Length::Fill), set_point_input]
+                .align_items(Alignment::Center)
+                .into()
+        };
+
+        let voltage_params = column![
             row![
-                "Step voltage:",
+                checkbox(
+                    "Explicit bias list",
+                    self.sweep_mode == SweepMode::List,
+                    Message::SweepModeToggled
+                ),
                 horizontal_space(Length::Fill),
-                step_voltage_input
+                pick_list(
+                    &SweepDirection::ALL[..],
+                    Some(self.sweep_direction),
+                    Message::SweepDirectionSelected
+                ),
             ]
             .align_items(Alignment::Center),
+            bias_inputs,
+            checkbox(
+                "2D sweep (bias × set point)",
+                self.sweep_2d,
+                Message::Sweep2dToggled
+            ),
+            set_point_inputs,
             vertical_space(5),
             row![
                 "Total images:",
@@ -492,7 +2344,16 @@ impl Application for R9Control {
             row![
                 "Time to finish:",
                 horizontal_space(Length::Fill),
-                time_to_finish_display
+                time_to_finish_display,
+                horizontal_space(8),
+                text(&self.finish_clock)
+            ]
+            .align_items(Alignment::Center),
+            vertical_space(4),
+            row![
+                "Running task ETA:",
+                horizontal_space(Length::Fill),
+                text(&self.running_eta)
             ]
             .align_items(Alignment::Center),
         ]
@@ -503,15 +2364,31 @@ impl Application for R9Control {
                 .tasks
                 .iter()
                 .enumerate()
-                .map(|(_, task)| {
-                    task.view()
-                        .map(move |message| Message::TaskMessage(message))
+                .map(|(i, task)| {
+                    task.view(
+                        self.tasklist_focused && self.tasklist.selected == Some(i),
+                        STMImage::label,
+                        STMImage::notes,
+                    )
+                    .map(move |message| Message::TaskMessage(i, message))
                 })
                 .collect(),
         )
         .spacing(10)
         .into();
 
+        let mut clear_completed_button = button("Clear completed").padding(5);
+        if !self.queue_busy() {
+            clear_completed_button =
+                clear_completed_button.on_press(Message::ClearCompletedPressed);
+        }
+
+        let toolbar: Element<_> = if self.show_settings {
+            column![toolbar, settings].into()
+        } else {
+            toolbar.into()
+        };
+
         let content = column![
             toolbar,
             row![
@@ -524,56 +2401,223 @@ impl Application for R9Control {
                             voltage_params
                         ]),
                         vertical_space(Length::Fill),
+                        form_status,
                         name,
                         vertical_space(10),
-                        add_to_queue_button,
+                        row![add_to_queue_button, add_and_keep_button].spacing(10),
+                        vertical_space(5),
+                        row![
+                            pick_list(
+                                &InsertPosition::ALL[..],
+                                Some(self.insert_position),
+                                Message::InsertPositionSelected
+                            )
+                            .width(Length::Fill),
+                            pick_list(
+                                &OutputFormat::ALL[..],
+                                Some(self.default_output_format),
+                                Message::OutputFormatSelected
+                            ),
+                        ]
+                        .spacing(10),
+                        vertical_space(10),
+                        row![batch_path, import_batch_button].spacing(10),
+                        text(&self.batch_report),
                     ]
                     .align_items(Alignment::Center)
                 )
                 .max_width(400),
                 vertical_rule(20),
-                scrollable(container(tasks).padding(10)),
+                column![
+                    text(self.disk_warning.as_deref().unwrap_or("")),
+                    clear_completed_button,
+                    scrollable(container(tasks).padding(10)),
+                ],
             ]
             .spacing(20)
         ]
         .align_items(Alignment::Start)
         .spacing(20);
 
-        container(content).padding(20).into()
+        let status = status_line(
+            self.jlcontext.as_ref().map(JuliaContext::is_alive),
+            self.running_task().map(|task| (task.index(), task.description())),
+            self.tasklist.tasks.len(),
+        );
+        let status_bar = container(text(status).size(14))
+            .padding([4, 20])
+            .width(Length::Fill);
+
+        let content = column![
+            container(content).padding(20).height(Length::Fill),
+            horizontal_rule(1),
+            status_bar,
+        ];
+
+        match self.dialogs.last() {
+            Some(dialog) => Modal::new(content, dialog_view(dialog))
+                .on_dismiss(Message::DialogDismissed)
+                .into(),
+            None => content.into(),
+        }
     }
 }
 
+/// Text of the status bar: whether Julia is running (`None` when it never
+/// started) and which task of the queue runs, e.g.
+/// `"Julia connected · Running task 3 of 10: overnight"`.
+fn status_line(julia_alive: Option<bool>, running: Option<(usize, &str)>, queued: usize) -> String {
+    let julia = match julia_alive {
+        Some(true) => "Julia connected",
+        Some(false) => "Julia stopped",
+        None => "Julia unavailable",
+    };
+    let queue = match running {
+        Some((idx, description)) => {
+            format!("Running task {} of {}: {}", idx + 1, queued, description)
+        }
+        None if queued == 0 => String::from("Queue empty"),
+        None => format!(
+            "{} task{} queued",
+            queued,
+            if queued == 1 { "" } else { "s" }
+        ),
+    };
+
+    format!("{julia} · {queue}")
+}
+
+fn dialog_view(dialog: &Dialog<Message>) -> Element<Message> {
+    let buttons = row(dialog
+        .buttons
+        .iter()
+        .map(|(label, msg)| {
+            let msg = match msg {
+                Some(msg) => Message::DialogResponse(Box::new(msg.clone())),
+                None => Message::DialogDismissed,
+            };
+            button(text(label)).on_press(msg).into()
+        })
+        .collect())
+    .spacing(10);
+
+    container(
+        column![text(&dialog.title).size(24), text(&dialog.body), buttons]
+            .spacing(20)
+            .align_items(Alignment::End),
+    )
+    .width(400)
+    .padding(20)
+    .style(theme::Container::Box)
+    .into()
+}
+
 impl Drop for R9Control {
     fn drop(&mut self) {
-        std::mem::drop(&self.jlcontext.julia);
-        std::mem::drop(&self.jlcontext.handle);
+        self.save_settings();
+
+        if let Some(jlcontext) = self.jlcontext.take() {
+            jlcontext.shutdown();
+        }
     }
 }
 
-fn calculate_total_images(start: f64, stop: f64, step: f64) -> u16 {
-    if step != 0.0 {
-        ((start - stop) / step).abs() as u16
-    } else {
-        0 as u16
+/// Message bound to a keyboard or mouse event. Only focus and queueing keys
+/// work while an input has captured the key; the rest are left to the input.
+fn shortcut(event: Event, status: event::Status) -> Option<Message> {
+    match event {
+        Event::Keyboard(keyboard_event) => match keyboard_event {
+            keyboard::Event::KeyPressed {
+                key_code: keyboard::KeyCode::Enter,
+                modifiers,
+            } if modifiers.command() => Some(Message::AddToQueueAndKeep),
+            keyboard::Event::KeyPressed {
+                key_code: keyboard::KeyCode::Tab,
+                modifiers,
+            } => Some(if modifiers.shift() {
+                Message::FocusPrevious
+            } else {
+                Message::FocusNext
+            }),
+            keyboard::Event::KeyPressed {
+                key_code: keyboard::KeyCode::F6,
+                ..
+            } => Some(Message::PlotFocused(true)),
+            // Arrow keys only reach the task list when no input consumed them
+            keyboard::Event::KeyPressed {
+                key_code: keyboard::KeyCode::Up,
+                ..
+            } if status == event::Status::Ignored => Some(Message::SelectPreviousTask),
+            keyboard::Event::KeyPressed {
+                key_code: keyboard::KeyCode::Down,
+                ..
+            } if status == event::Status::Ignored => Some(Message::SelectNextTask),
+            // Queue undo, unless a focused input took the key
+            keyboard::Event::KeyPressed {
+                key_code: keyboard::KeyCode::Z,
+                modifiers,
+            } if status == event::Status::Ignored && modifiers.command() => {
+                Some(if modifiers.shift() {
+                    Message::Redo
+                } else {
+                    Message::Undo
+                })
+            }
+            keyboard::Event::KeyPressed {
+                key_code: keyboard::KeyCode::Y,
+                modifiers,
+            } if status == event::Status::Ignored && modifiers.command() => Some(Message::Redo),
+            // Run controls, unless a focused input took the key as text
+            keyboard::Event::KeyPressed {
+                key_code,
+                modifiers,
+            } if status == event::Status::Ignored => transport_shortcut(key_code, modifiers),
+            _ => None,
+        },
+        Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left))
+            if status == event::Status::Ignored =>
+        {
+            Some(Message::ClickedOutside)
+        }
+        _ => None,
     }
 }
 
-fn calculate_time_remaining(lines_per_frame: f64, line_time: f64, num_images: f64) -> String {
-    let mut secs = lines_per_frame * line_time * num_images;
+/// Play, pause or stop message bound to a key: Space plays, P pauses, and
+/// Escape or Ctrl+. stops.
+fn transport_shortcut(
+    key_code: keyboard::KeyCode,
+    modifiers: keyboard::Modifiers,
+) -> Option<Message> {
+    match key_code {
+        keyboard::KeyCode::Space => Some(Message::PlayPressed),
+        keyboard::KeyCode::P if modifiers.is_empty() => Some(Message::PausePressed),
+        keyboard::KeyCode::Escape => Some(Message::StopPressed),
+        keyboard::KeyCode::Period if modifiers.command() => Some(Message::StopPressed),
+        _ => None,
+    }
+}
 
-    let days = (secs / (60. * 60. * 24.)).floor();
-    secs = secs - days * (60. * 60. * 24.);
+/// Seconds to scan `num_images` images of `lines_per_frame` lines each, or
+/// `None` while any of the inputs is missing.
+fn seconds_remaining(lines_per_frame: f64, line_time: f64, num_images: f64) -> Option<f64> {
+    if lines_per_frame <= 0.0 || line_time <= 0.0 || num_images <= 0.0 {
+        return None;
+    }
 
-    let hrs = (secs / (60. * 60.)).floor();
-    secs = secs - hrs * (60. * 60.);
+    Some(lines_per_frame * line_time * num_images)
+}
 
-    let mins = (secs / 60.0).floor();
-    secs = (secs - mins * 60.0).floor();
+/// Produces the next name in a series so repeated "Add & keep" presses queue
+/// distinct tasks. A trailing number is incremented, keeping any zero padding
+/// ("scan_09" -> "scan_10"); otherwise " 2" is appended ("scan" -> "scan 2").
+fn increment_name(name: &str) -> String {
+    let stem = name.trim_end_matches(|c: char| c.is_ascii_digit());
+    let digits = &name[stem.len()..];
 
-    if days > 0.0 {
-        format!("{:02}:{:02}:{:02}:{:02}", days, hrs, mins, secs)
-    } else {
-        format!("{:02}:{:02}:{:02}", hrs, mins, secs)
+    match digits.parse::<u64>() {
+        Ok(n) => format!("{stem}{:0width$}", n + 1, width = digits.len()),
+        Err(_) => format!("{name} 2"),
     }
 }
 
@@ -584,3 +2628,554 @@ enum LinesOptions {}
 impl LinesOptions {
     const ALL: [u32; 10] = [8, 16, 32, 64, 128, 256, 512, 1024, 2048, 4096];
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use keyboard::KeyCode;
+
+    /// The app with default settings and no Julia, logging and exporting to
+    /// scratch files, without desktop notifications and leaving the settings
+    /// file alone.
+    fn app() -> R9Control {
+        let mut app = R9Control::with_settings(Settings::default(), None);
+        app.audit_log = AuditLog::new(std::env::temp_dir().join("stm_controller_app_audit.jsonl"));
+        app.output_dir = std::env::temp_dir().join("stm_controller_app_exports");
+        std::fs::create_dir_all(&app.output_dir).unwrap();
+        app.notify_on_finish = false;
+        app.settings_path = None;
+        app
+    }
+
+    fn volts(value: f64) -> ExponentialNumber {
+        ExponentialNumber::new(value, 0)
+    }
+
+    /// The app with a linear bias sweep typed into the form.
+    fn sweep(start: f64, stop: f64, step: f64) -> R9Control {
+        let mut app = app();
+        app.start_voltage = volts(start);
+        app.stop_voltage = volts(stop);
+        app.step_voltage = volts(step);
+        app
+    }
+
+    fn queued_biases(app: &R9Control) -> Vec<Vec<f64>> {
+        app.tasklist
+            .tasks
+            .iter()
+            .map(|task| task.content().iter().map(STMImage::bias).collect())
+            .collect()
+    }
+
+    #[test]
+    fn zero_step_is_not_queued() {
+        let mut app = sweep(-1.0, 1.0, 0.0);
+
+        app.update(Message::AddToQueue);
+
+        assert_eq!(
+            app.form_error.as_deref(),
+            Some("Step voltage must be positive")
+        );
+        assert!(app.tasklist.tasks.is_empty());
+    }
+
+    #[test]
+    fn equal_start_and_stop_queue_a_single_image() {
+        let mut app = sweep(0.5, 0.5, 0.1);
+
+        app.update(Message::AddToQueue);
+
+        assert_eq!(app.form_error, None);
+        assert_eq!(queued_biases(&app), [[0.5]]);
+    }
+
+    #[test]
+    fn queued_images_carry_the_set_point() {
+        let mut app = sweep(0.0, 1.0, 0.5);
+        let set_point = ExponentialNumber::new(250.0, -12);
+        app.update(Message::SetPointChanged(set_point));
+
+        app.update(Message::AddToQueue);
+
+        let set_points: Vec<f64> = app.tasklist.tasks[0]
+            .content()
+            .iter()
+            .map(STMImage::set_point)
+            .collect();
+        assert_eq!(set_points, [set_point.to_f64(); 3]);
+    }
+
+    #[test]
+    fn voltage_outside_the_hardware_range_is_not_queued() {
+        let mut app = sweep(-1.0, 7.0, 1.0);
+
+        app.update(Message::AddToQueue);
+
+        assert_eq!(
+            app.form_error.as_deref(),
+            Some("Stop voltage 7 outside [-5, 5]")
+        );
+        assert!(app.tasklist.tasks.is_empty());
+    }
+
+    #[test]
+    fn valid_sweep_queues_one_image_per_step() {
+        let mut app = sweep(-1.0, 1.0, 0.5);
+
+        app.update(Message::AddToQueue);
+
+        assert_eq!(app.form_error, None);
+        assert_eq!(queued_biases(&app), [[-1.0, -0.5, 0.0, 0.5, 1.0]]);
+    }
+
+    #[test]
+    fn explicit_bias_list_queues_one_image_per_value() {
+        let mut app = app();
+        app.sweep_mode = SweepMode::List;
+        app.bias_list = String::from("-1.0, -0.1, 0.1, 1.0");
+
+        app.update(Message::AddToQueue);
+
+        assert_eq!(app.form_error, None);
+        assert_eq!(queued_biases(&app), [[-1.0, -0.1, 0.1, 1.0]]);
+    }
+
+    /// The app with one task of three images queued.
+    fn queued() -> R9Control {
+        let mut app = sweep(0.0, 1.0, 0.5);
+        app.update(Message::AddToQueue);
+        app
+    }
+
+    #[test]
+    fn stopping_a_running_task_is_confirmed_first() {
+        let mut app = queued();
+        app.set_task_state(0, TaskState::Running);
+
+        app.update(Message::StopPressed);
+        assert_eq!(app.dialogs.len(), 1);
+        assert_eq!(app.dialogs[0].title, "Stop the run?");
+        assert!(app.tasklist.tasks[0].is_running());
+
+        // Keep scanning
+        app.update(Message::DialogDismissed);
+        assert!(app.dialogs.is_empty());
+        assert!(app.tasklist.tasks[0].is_running());
+
+        app.update(Message::StopPressed);
+        app.update(Message::DialogResponse(Box::new(Message::ConfirmStop)));
+        assert!(app.dialogs.is_empty());
+        assert!(matches!(
+            app.tasklist.tasks[0].status(),
+            TaskState::Failed(e) if e == "Interrupted by user."
+        ));
+    }
+
+    #[test]
+    fn dialogs_stack_and_close_from_the_top() {
+        let mut app = app();
+        app.open_dialog(Dialog::new("First", "").close_button("OK"));
+        app.open_dialog(Dialog::new("Second", "").close_button("OK"));
+        assert_eq!(app.dialogs.len(), 2);
+        assert_eq!(app.dialogs[1].title, "Second");
+
+        app.update(Message::DialogDismissed);
+        assert_eq!(app.dialogs.len(), 1);
+        assert_eq!(app.dialogs[0].title, "First");
+
+        app.update(Message::DialogDismissed);
+        assert!(app.dialogs.is_empty());
+    }
+
+    #[test]
+    fn adding_to_the_queue_can_be_undone_and_redone() {
+        let mut app = queue_of(1);
+
+        app.update(Message::Undo);
+        assert!(app.tasklist.tasks.is_empty());
+
+        app.update(Message::Redo);
+        assert_eq!(queued_biases(&app), [[0.0, 0.5, 1.0]]);
+    }
+
+    #[test]
+    fn retried_tasks_are_idle_and_run_next() {
+        let mut app = queue_of(2);
+        app.set_task_state(1, TaskState::Failed(String::from("no procedure")));
+
+        // Only failed tasks can be retried
+        app.update(Message::TaskMessage(0, TaskMessage::Retry));
+        assert_eq!(app.tasklist.current_task, Some(0));
+
+        app.update(Message::TaskMessage(1, TaskMessage::Retry));
+        assert!(matches!(app.tasklist.tasks[1].status(), TaskState::Idle));
+        assert_eq!(app.tasklist.current_task, Some(1));
+    }
+
+    #[test]
+    fn arrow_keys_move_the_selection_only_while_the_list_has_focus() {
+        let mut app = queue_of(3);
+        app.update(Message::SelectNextTask);
+        assert_eq!(app.tasklist.selected, None);
+
+        app.update(Message::TaskMessage(1, TaskMessage::Select));
+        app.update(Message::SelectNextTask);
+        app.update(Message::SelectNextTask);
+        assert_eq!(app.tasklist.selected, Some(2));
+
+        app.update(Message::ClickedOutside);
+        app.update(Message::SelectPreviousTask);
+        assert_eq!(app.tasklist.selected, Some(2));
+    }
+
+    /// Writes `contents` to a scratch batch file named `name` and imports it.
+    fn import_batch(app: &mut R9Control, name: &str, contents: &str) {
+        let path = std::env::temp_dir().join(name);
+        std::fs::write(&path, contents).unwrap();
+        app.batch_path = path.to_string_lossy().into_owned();
+
+        app.update(Message::ImportBatch);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    fn task_names(app: &R9Control) -> Vec<&str> {
+        let tasks = app.tasklist.tasks.iter();
+        tasks.map(|task| task.description()).collect()
+    }
+
+    #[test]
+    fn batch_rows_are_queued_after_the_current_task_in_file_order() {
+        let mut app = queued();
+        app.update(Message::AddToQueue);
+        app.insert_position = InsertPosition::AfterCurrent;
+
+        import_batch(
+            &mut app,
+            "stm_controller_app_batch.csv",
+            "name,lines,size,line_time,bias\n\
+             first,64,1e-7,0.2,0.5\n\
+             second,128,2e-7,0.2,-0.5\n",
+        );
+
+        assert_eq!(task_names(&app), ["", "first", "second", ""]);
+        assert_eq!(app.tasklist.current_task, Some(0));
+        assert_eq!(app.batch_report, "Queued 2 rows");
+    }
+
+    #[test]
+    fn invalid_batch_rows_are_skipped_and_reported() {
+        let mut app = app();
+
+        import_batch(
+            &mut app,
+            "stm_controller_app_batch.json",
+            r#"[
+                {"name": "ok", "lines": 64, "size": 1e-7, "line_time": 0.2, "bias": 0.5},
+                {"name": "too fast", "lines": 64, "size": 1e-7, "line_time": 0.01, "bias": 0.5},
+                {"name": "no bias", "lines": 64, "size": 1e-7, "line_time": 0.2}
+            ]"#,
+        );
+
+        assert_eq!(task_names(&app), ["ok"]);
+        assert_eq!(app.tasklist.current_task, Some(0));
+        assert_eq!(
+            app.batch_report.lines().collect::<Vec<_>>(),
+            [
+                "Queued 1 rows",
+                "Skipped row 2: line_time 0.01 outside [0.1024, 100]",
+                "Skipped row 3: missing field `bias`",
+            ]
+        );
+    }
+
+    /// The app with `count` tasks queued, the first of them current.
+    fn queue_of(count: usize) -> R9Control {
+        let mut app = sweep(0.0, 1.0, 0.5);
+        for _ in 0..count {
+            app.update(Message::AddToQueue);
+        }
+        app
+    }
+
+    #[test]
+    fn play_dispatches_the_idle_current_task_one_at_a_time() {
+        let mut app = queue_of(3);
+        assert_eq!(app.tasks_to_dispatch(), [0]);
+
+        app.tasklist.current_task = Some(1);
+        assert_eq!(app.tasks_to_dispatch(), [1]);
+
+        app.set_task_state(1, TaskState::Running);
+        assert!(app.tasks_to_dispatch().is_empty());
+    }
+
+    #[test]
+    fn parallel_play_fills_the_free_workers() {
+        let mut app = queue_of(4);
+        app.parallel_tasks = true;
+        assert_eq!(app.tasks_to_dispatch(), [0, 1]);
+
+        // A paused task keeps its worker
+        app.set_task_state(0, TaskState::Paused);
+        assert_eq!(app.tasks_to_dispatch(), [1]);
+
+        app.set_task_state(1, TaskState::Failed(String::from("Interrupted by user.")));
+        app.set_task_state(2, TaskState::Running);
+        assert!(app.tasks_to_dispatch().is_empty());
+    }
+
+    #[test]
+    fn only_the_latest_voltage_edit_recomputes_the_estimates() {
+        let mut app = sweep(0.0, 1.0, 0.5);
+        app.update(Message::StopVoltageChanged(volts(2.0)));
+        let stale = app.estimates_generation;
+        app.update(Message::StopVoltageChanged(volts(3.0)));
+        let before = app.total_images;
+
+        app.update(Message::RecomputeEstimates(stale));
+        assert_eq!(app.total_images, before);
+
+        app.update(Message::RecomputeEstimates(app.estimates_generation));
+        assert_eq!(app.total_images, 7);
+    }
+
+    #[test]
+    fn form_is_dirty_once_it_differs_from_the_last_queued_or_running_task() {
+        let mut app = sweep(0.0, 1.0, 0.5);
+        assert!(!app.form_is_dirty());
+
+        app.update(Message::AddToQueue);
+        assert!(!app.form_is_dirty());
+
+        app.stop_voltage = volts(2.0);
+        assert!(app.form_is_dirty());
+
+        app.update(Message::AddToQueue);
+        assert!(!app.form_is_dirty());
+
+        // The running task takes precedence over the last one queued
+        app.set_task_state(0, TaskState::Running);
+        assert!(app.form_is_dirty());
+    }
+
+    #[test]
+    fn increment_name_bumps_a_trailing_number() {
+        assert_eq!(increment_name("scan"), "scan 2");
+        assert_eq!(increment_name("scan 2"), "scan 3");
+        assert_eq!(increment_name("scan 9"), "scan 10");
+        assert_eq!(increment_name("scan_09"), "scan_10");
+    }
+
+    #[test]
+    fn add_and_keep_queues_distinct_names_with_the_same_parameters() {
+        let mut app = sweep(0.0, 1.0, 0.5);
+        app.name = String::from("scan");
+        app.update(Message::AddToQueueAndKeep);
+        app.update(Message::AddToQueueAndKeep);
+
+        assert_eq!(task_names(&app), ["scan", "scan 2"]);
+        let tasks = &app.tasklist.tasks;
+        assert_eq!(tasks[0].content(), tasks[1].content());
+        assert_eq!(app.name, "scan 3");
+    }
+
+    fn pressed(key_code: KeyCode, modifiers: keyboard::Modifiers) -> Event {
+        Event::Keyboard(keyboard::Event::KeyPressed {
+            key_code,
+            modifiers,
+        })
+    }
+
+    #[test]
+    fn transport_keys_map_to_play_pause_and_stop() {
+        let none = keyboard::Modifiers::empty();
+        let command = keyboard::Modifiers::COMMAND;
+        let ignored = event::Status::Ignored;
+        let key = |key_code, modifiers| shortcut(pressed(key_code, modifiers), ignored);
+
+        let play = key(KeyCode::Space, none);
+        assert!(matches!(play, Some(Message::PlayPressed)));
+        let pause = key(KeyCode::P, none);
+        assert!(matches!(pause, Some(Message::PausePressed)));
+        assert!(key(KeyCode::P, command).is_none());
+
+        for (key_code, modifiers) in [(KeyCode::Escape, none), (KeyCode::Period, command)] {
+            let stop = key(key_code, modifiers);
+            assert!(matches!(stop, Some(Message::StopPressed)));
+        }
+        assert!(key(KeyCode::Period, none).is_none());
+    }
+
+    #[test]
+    fn transport_keys_are_left_to_a_focused_input() {
+        let none = keyboard::Modifiers::empty();
+        let captured = event::Status::Captured;
+
+        for key_code in [KeyCode::Space, KeyCode::P, KeyCode::Escape] {
+            assert!(shortcut(pressed(key_code, none), captured).is_none());
+        }
+        // Moving focus still works from inside an input
+        let tab = shortcut(pressed(KeyCode::Tab, none), captured);
+        assert!(matches!(tab, Some(Message::FocusNext)));
+    }
+
+    #[test]
+    fn status_line_names_julia_state_and_queue_position() {
+        assert_eq!(
+            status_line(Some(true), Some((2, "overnight")), 10),
+            "Julia connected · Running task 3 of 10: overnight"
+        );
+        assert_eq!(
+            status_line(Some(false), None, 0),
+            "Julia stopped · Queue empty"
+        );
+        assert_eq!(
+            status_line(None, None, 1),
+            "Julia unavailable · 1 task queued"
+        );
+        assert_eq!(
+            status_line(Some(true), None, 4),
+            "Julia connected · 4 tasks queued"
+        );
+    }
+
+    /// Reports the task at `idx` as done scanning, without error.
+    fn finish(app: &mut R9Control, idx: usize) -> Command<Message> {
+        let task = app.tasklist.tasks[idx].id();
+        let dispatched = Dispatched {
+            data: vec![],
+            error: None,
+        };
+        app.update(Message::TaskFinished(task, app.run_generation, dispatched))
+    }
+
+    #[test]
+    fn busy_overlay_clears_once_julia_reports_back() {
+        // As left by pressing play until the task starts
+        let mut app = queue_of(2);
+        app.set_task_state(0, TaskState::Running);
+        app.is_dispatching = true;
+
+        app.update(Message::TaskProgress(0, 10.0));
+        assert!(!app.is_dispatching);
+
+        app.is_dispatching = true;
+        finish(&mut app, 0);
+        assert!(!app.is_dispatching);
+
+        app.is_dispatching = true;
+        app.update(Message::ConfirmStop);
+        assert!(!app.is_dispatching);
+    }
+
+    #[test]
+    fn pause_after_current_holds_the_next_task() {
+        let mut app = queue_of(3);
+        app.set_task_state(0, TaskState::Running);
+        assert!(!finish(&mut app, 0).actions().is_empty());
+
+        app.update(Message::PauseAfterCurrentToggled(true));
+        app.set_task_state(1, TaskState::Running);
+        assert!(finish(&mut app, 1).actions().is_empty());
+        assert!(app.tasklist.tasks[2].is_idle());
+        assert_eq!(app.tasklist.current_task, Some(2));
+        // Play picks up from the held task and runs on
+        assert!(!app.pause_after_current);
+    }
+
+    #[test]
+    fn paused_tasks_resume_where_they_stopped() {
+        let mut app = queue_of(2);
+        assert!(app.tasklist.tasks[0].is_idle());
+
+        app.set_task_state(0, TaskState::Running);
+        app.update(Message::PausePressed);
+        assert!(app.tasklist.tasks[0].is_paused());
+        assert!(app.tasklist.tasks[1].is_idle());
+
+        assert!(app.resume_paused());
+        assert!(app.tasklist.tasks[0].is_running());
+        assert!(!app.resume_paused());
+    }
+
+    #[test]
+    fn scan_now_scans_the_form_outside_the_queue() {
+        let mut app = sweep(0.5, 1.0, 0.5);
+        app.size = ExponentialNumber::new(20.0, -9);
+        app.line_time = ExponentialNumber::new(0.2, 0);
+
+        let task = app.quick_scan_task().unwrap();
+        let [image] = task.content().as_slice() else {
+            panic!("expected one image, got {}", task.content().len());
+        };
+        assert_eq!(image.size(), app.size.to_f64());
+        assert_eq!(image.bias(), 0.5);
+        assert_eq!(image.line_time(), app.line_time.to_f64());
+
+        // Without Julia nothing is dispatched, and the queue is never touched
+        app.update(Message::ScanNow);
+        assert!(app.quick_scan.is_none());
+        assert!(app.tasklist.tasks.is_empty());
+    }
+
+    #[test]
+    fn completion_hook_fires_once_when_the_last_task_completes() {
+        let summaries = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        let mut app = queue_of(2);
+        app.notify_on_finish = true;
+        let log = summaries.clone();
+        app.on_queue_finished = Box::new(move |summary| log.borrow_mut().push(summary.to_owned()));
+
+        app.set_task_state(0, TaskState::Running);
+        finish(&mut app, 0);
+        assert!(summaries.borrow().is_empty());
+
+        app.set_task_state(1, TaskState::Running);
+        finish(&mut app, 1);
+        assert_eq!(*summaries.borrow(), ["2 tasks done, 0 failed"]);
+    }
+
+    #[test]
+    fn picking_a_theme_switches_and_saves_it() {
+        let mut app = app();
+        assert_eq!(app.theme(), Theme::Light);
+
+        app.update(Message::ThemeSelected(ThemeChoice::Dark));
+        assert_eq!(app.theme(), Theme::Dark);
+        assert_eq!(app.settings().theme, ThemeChoice::Dark);
+
+        // Toolbar and task styles read from the palette
+        let (light, dark) = (Theme::Light.palette(), app.theme().palette());
+        assert_ne!(light.background, dark.background);
+    }
+
+    #[test]
+    fn reduced_motion_is_saved_with_the_settings() {
+        let mut app = app();
+        app.update(Message::ReduceMotionToggled(true));
+        assert!(app.settings().reduce_motion);
+
+        let mut reopened = R9Control::with_settings(app.settings(), None);
+        reopened.settings_path = None;
+        assert!(reopened.reduce_motion);
+    }
+
+    #[test]
+    fn julia_errors_fail_the_task_with_their_message() {
+        let mut app = queue_of(1);
+        app.set_task_state(0, TaskState::Running);
+        let dispatched = Dispatched {
+            data: vec![],
+            error: Some(String::from("UndefVarError: read_lockin not defined")),
+        };
+        let task = app.tasklist.tasks[0].id();
+        app.update(Message::TaskFinished(task, app.run_generation, dispatched));
+
+        assert!(matches!(
+            app.tasklist.tasks[0].status(),
+            TaskState::Failed(e) if e == "UndefVarError: read_lockin not defined"
+        ));
+    }
+}
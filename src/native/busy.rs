@@ -0,0 +1,257 @@
+//! Dim content and spin an indicator over it while the app waits on work
+//! it started.
+use iced_native::event::{self, Event};
+use iced_native::layout::{self, Layout};
+use iced_native::mouse;
+use iced_native::renderer;
+use iced_native::time::{Duration, Instant};
+use iced_native::widget::{tree, tree::Tree, Operation, Widget};
+use iced_native::{window, Clipboard, Color, Element, Length, Point, Rectangle, Shell, Size};
+
+use crate::native::motion;
+
+/// Number of dots around the indicator.
+const DOTS: usize = 8;
+/// Radius, in pixels, of the circle the dots sit on.
+const RADIUS: f32 = 14.0;
+/// Radius, in pixels, of each dot.
+const DOT_RADIUS: f32 = 3.0;
+/// Time the lit dot takes to move on to the next one.
+const STEP: Duration = Duration::from_millis(100);
+/// Opacity of the veil laid over the content.
+const VEIL_ALPHA: f32 = 0.08;
+
+pub struct Busy<'a, Message, Renderer> {
+    content: Element<'a, Message, Renderer>,
+    is_active: bool,
+}
+
+impl<'a, Message, Renderer> Busy<'a, Message, Renderer> {
+    pub fn new(content: impl Into<Element<'a, Message, Renderer>>) -> Self {
+        Self {
+            content: content.into(),
+            is_active: false,
+        }
+    }
+
+    /// Shows the indicator and blocks mouse input to the content while
+    /// `is_active`.
+    pub fn active(mut self, is_active: bool) -> Self {
+        self.is_active = is_active;
+        self
+    }
+}
+
+/// When the indicator started spinning and the last frame it was drawn for.
+#[derive(Debug, Clone, Copy, Default)]
+struct State {
+    started: Option<Instant>,
+    now: Option<Instant>,
+}
+
+impl State {
+    /// Index of the brightest dot.
+    fn lit_dot(&self) -> usize {
+        match (self.started, self.now) {
+            (Some(started), Some(now)) if !motion::is_reduced() => {
+                (now.duration_since(started).as_millis() / STEP.as_millis()) as usize % DOTS
+            }
+            _ => 0,
+        }
+    }
+}
+
+impl<'a, Message, Renderer> Widget<Message, Renderer> for Busy<'a, Message, Renderer>
+where
+    Renderer: iced_native::Renderer,
+{
+    fn tag(&self) -> tree::Tag {
+        tree::Tag::of::<State>()
+    }
+
+    fn state(&self) -> tree::State {
+        tree::State::new(State::default())
+    }
+
+    fn children(&self) -> Vec<Tree> {
+        vec![Tree::new(&self.content)]
+    }
+
+    fn diff(&self, tree: &mut Tree) {
+        tree.diff_children(std::slice::from_ref(&self.content))
+    }
+
+    fn width(&self) -> Length {
+        self.content.as_widget().width()
+    }
+
+    fn height(&self) -> Length {
+        self.content.as_widget().height()
+    }
+
+    fn layout(&self, renderer: &Renderer, limits: &layout::Limits) -> layout::Node {
+        let content = self.content.as_widget().layout(renderer, limits);
+
+        layout::Node::with_children(content.size(), vec![content])
+    }
+
+    fn operate(
+        &self,
+        tree: &mut Tree,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+        operation: &mut dyn Operation<Message>,
+    ) {
+        self.content.as_widget().operate(
+            &mut tree.children[0],
+            layout.children().next().unwrap(),
+            renderer,
+            operation,
+        );
+    }
+
+    fn on_event(
+        &mut self,
+        tree: &mut Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        renderer: &Renderer,
+        clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+    ) -> event::Status {
+        let state = tree.state.downcast_mut::<State>();
+
+        if let Event::Window(window::Event::RedrawRequested(now)) = event {
+            if self.is_active {
+                state.started.get_or_insert(now);
+                state.now = Some(now);
+
+                if !motion::is_reduced() {
+                    shell.request_redraw(window::RedrawRequest::At(now + STEP));
+                }
+            } else {
+                *state = State::default();
+            }
+        }
+
+        // The content is not to be touched until the work is under way
+        if self.is_active
+            && matches!(event, Event::Mouse(_) | Event::Touch(_))
+            && layout.bounds().contains(cursor_position)
+        {
+            return event::Status::Captured;
+        }
+
+        self.content.as_widget_mut().on_event(
+            &mut tree.children[0],
+            event,
+            layout.children().next().unwrap(),
+            cursor_position,
+            renderer,
+            clipboard,
+            shell,
+        )
+    }
+
+    fn mouse_interaction(
+        &self,
+        tree: &Tree,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        viewport: &Rectangle,
+        renderer: &Renderer,
+    ) -> mouse::Interaction {
+        if self.is_active && layout.bounds().contains(cursor_position) {
+            return mouse::Interaction::Idle;
+        }
+
+        self.content.as_widget().mouse_interaction(
+            &tree.children[0],
+            layout.children().next().unwrap(),
+            cursor_position,
+            viewport,
+            renderer,
+        )
+    }
+
+    fn draw(
+        &self,
+        tree: &Tree,
+        renderer: &mut Renderer,
+        theme: &Renderer::Theme,
+        style: &renderer::Style,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        viewport: &Rectangle,
+    ) {
+        self.content.as_widget().draw(
+            &tree.children[0],
+            renderer,
+            theme,
+            style,
+            layout.children().next().unwrap(),
+            cursor_position,
+            viewport,
+        );
+
+        if !self.is_active {
+            return;
+        }
+
+        let bounds = layout.bounds();
+        let color = style.text_color;
+
+        renderer.with_layer(bounds, |renderer| {
+            renderer.fill_quad(
+                renderer::Quad {
+                    bounds,
+                    border_radius: 0.0.into(),
+                    border_width: 0.0,
+                    border_color: Color::TRANSPARENT,
+                },
+                Color {
+                    a: VEIL_ALPHA,
+                    ..color
+                },
+            );
+
+            // Dots fade out behind the lit one, which goes round clockwise
+            let lit = tree.state.downcast_ref::<State>().lit_dot();
+            let center = bounds.center();
+            for dot in 0..DOTS {
+                let angle = dot as f32 / DOTS as f32 * std::f32::consts::TAU;
+                let behind = (lit + DOTS - dot) % DOTS;
+
+                renderer.fill_quad(
+                    renderer::Quad {
+                        bounds: Rectangle::new(
+                            Point::new(
+                                center.x + RADIUS * angle.sin() - DOT_RADIUS,
+                                center.y - RADIUS * angle.cos() - DOT_RADIUS,
+                            ),
+                            Size::new(2.0 * DOT_RADIUS, 2.0 * DOT_RADIUS),
+                        ),
+                        border_radius: DOT_RADIUS.into(),
+                        border_width: 0.0,
+                        border_color: Color::TRANSPARENT,
+                    },
+                    Color {
+                        a: 1.0 - behind as f32 / DOTS as f32,
+                        ..color
+                    },
+                );
+            }
+        });
+    }
+}
+
+impl<'a, Message, Renderer> From<Busy<'a, Message, Renderer>> for Element<'a, Message, Renderer>
+where
+    Message: 'a,
+    Renderer: 'a + iced_native::Renderer,
+{
+    fn from(busy: Busy<'a, Message, Renderer>) -> Element<'a, Message, Renderer> {
+        Element::new(busy)
+    }
+}
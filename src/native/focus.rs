@@ -0,0 +1,127 @@
+//! Keyboard focus traversal that wraps around at either end.
+//!
+//! Iced's own `focus_next` and `focus_previous` leave nothing focused after
+//! the last or before the first input. These go round to the other end
+//! instead. Only widgets that report themselves focusable take part, so
+//! inputs that cannot be edited are skipped.
+use iced::Command;
+use iced_native::widget::operation::focusable::{count, Count, Focusable};
+use iced_native::widget::operation::Operation;
+use iced_native::widget::Id;
+
+/// Index of the input that gains focus when moving on from `focused`, out of
+/// `total` focusable inputs, `None` when there are none. Moving forward from
+/// nothing focused lands on the first input, and backward on the last.
+pub fn wrapped_target(focused: Option<usize>, total: usize, forward: bool) -> Option<usize> {
+    if total == 0 {
+        return None;
+    }
+
+    Some(match (focused, forward) {
+        (Some(i), true) => (i + 1) % total,
+        (Some(i), false) => (i + total - 1) % total,
+        (None, true) => 0,
+        (None, false) => total - 1,
+    })
+}
+
+/// Focuses the next focusable widget, wrapping from the last to the first.
+pub fn focus_next<Message: 'static>() -> Command<Message> {
+    Command::widget(count(|count: Count| {
+        FocusIndex::new(wrapped_target(count.focused, count.total, true))
+    }))
+}
+
+/// Focuses the previous focusable widget, wrapping from the first to the last.
+pub fn focus_previous<Message: 'static>() -> Command<Message> {
+    Command::widget(count(|count: Count| {
+        FocusIndex::new(wrapped_target(count.focused, count.total, false))
+    }))
+}
+
+/// Focuses the focusable widget at `target`, in tree order, and unfocuses
+/// every other.
+struct FocusIndex {
+    target: Option<usize>,
+    current: usize,
+}
+
+impl FocusIndex {
+    fn new(target: Option<usize>) -> Self {
+        Self { target, current: 0 }
+    }
+}
+
+impl<T> Operation<T> for FocusIndex {
+    fn focusable(&mut self, state: &mut dyn Focusable, _id: Option<&Id>) {
+        if self.target == Some(self.current) {
+            state.focus();
+        } else {
+            state.unfocus();
+        }
+        self.current += 1;
+    }
+
+    fn container(
+        &mut self,
+        _id: Option<&Id>,
+        operate_on_children: &mut dyn FnMut(&mut dyn Operation<T>),
+    ) {
+        operate_on_children(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An input that only records whether it has focus.
+    #[derive(Default)]
+    struct Input(bool);
+
+    impl Focusable for Input {
+        fn is_focused(&self) -> bool {
+            self.0
+        }
+
+        fn focus(&mut self) {
+            self.0 = true;
+        }
+
+        fn unfocus(&mut self) {
+            self.0 = false;
+        }
+    }
+
+    #[test]
+    fn tabbing_wraps_at_either_end() {
+        assert_eq!(wrapped_target(Some(1), 3, true), Some(2));
+        assert_eq!(wrapped_target(Some(2), 3, true), Some(0));
+        assert_eq!(wrapped_target(Some(0), 3, false), Some(2));
+        assert_eq!(wrapped_target(None, 3, true), Some(0));
+        assert_eq!(wrapped_target(None, 3, false), Some(2));
+        assert_eq!(wrapped_target(None, 0, true), None);
+    }
+
+    #[test]
+    fn only_inputs_that_take_focus_are_counted() {
+        // Five inputs of which the second and fourth cannot be edited, and
+        // so never report themselves focusable
+        let editable = [true, false, true, false, true];
+        let mut inputs: Vec<Input> = editable.iter().map(|_| Input::default()).collect();
+        let mut order = vec![];
+
+        let mut focused = None;
+        for _ in 0..4 {
+            let mut operation = FocusIndex::new(wrapped_target(focused, 3, true));
+            for (input, _) in inputs.iter_mut().zip(editable).filter(|&(_, e)| e) {
+                Operation::<()>::focusable(&mut operation, input, None);
+            }
+            let position = inputs.iter().position(|input| input.0).unwrap();
+            order.push(position);
+            focused = Some(editable[..position].iter().filter(|&&e| e).count());
+        }
+
+        assert_eq!(order, [0, 2, 4, 0]);
+    }
+}
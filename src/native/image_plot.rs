@@ -1,23 +1,660 @@
-use iced::Color;
-use iced_graphics::widget::canvas::{Cache, Cursor, Frame, Geometry, Path, Program};
+use iced::{alignment, keyboard, mouse, Color, Point, Size, Vector};
+use iced_graphics::widget::canvas::{
+    event, Cache, Cursor, Event, Frame, Geometry, Path, Program, Stroke, Text,
+};
+use std::cell::Cell;
+
+use crate::core::hardware;
+use crate::core::scangeometry::ScanGeometry;
+use crate::core::stmimage::STSType;
+use crate::core::vector2::Vector2;
+use crate::native::scientificspinbox::ExponentialNumber;
+
+/// Width of the full scannable field, in meters, shown across the canvas.
+const MAX_FIELD: f64 = 2.1e-6;
+/// Half the length of each arm of the crosshair, in pixels.
+const CROSSHAIR: f32 = 6.0;
+/// Distance, in pixels, the cursor may move between press and release for
+/// the two to count as a click.
+const CLICK_TOLERANCE: f32 = 3.0;
+/// Radius of spectroscopy markers, in pixels.
+const MARKER: f32 = 4.0;
+/// Gap, in pixels, between the cursor readout and the canvas edges.
+const READOUT_MARGIN: f32 = 4.0;
+/// Length, in pixels, of the axis ticks along the canvas edges.
+const TICK: f32 = 5.0;
+/// Zoom factor of one scroll wheel step.
+const ZOOM_STEP: f32 = 1.2;
+/// Pixels of smooth scrolling that make up one wheel step.
+const PIXELS_PER_ZOOM_STEP: f32 = 50.0;
+/// Upper limit on the samples drawn per data pixel when interpolating.
+const MAX_SUBDIVISIONS: usize = 8;
+
+/// How data pixels are filled in when the image is drawn larger than its
+/// resolution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Interpolation {
+    /// Every data pixel is a flat square, faithful to the acquired values.
+    #[default]
+    Nearest,
+    /// Values are blended linearly between neighbouring pixel centres.
+    Bilinear,
+}
+
+impl Interpolation {
+    pub const ALL: [Interpolation; 2] = [Interpolation::Nearest, Interpolation::Bilinear];
+
+    /// Samples a `lines` × `lines` row-major image at continuous pixel
+    /// coordinates, where `(0.0, 0.0)` is the centre of the first pixel.
+    /// Coordinates outside the image are clamped to its edge.
+    pub fn sample(&self, data: &[f32], lines: usize, x: f32, y: f32) -> f32 {
+        let last = (lines - 1) as f32;
+        let (x, y) = (x.clamp(0.0, last), y.clamp(0.0, last));
+        let at = |col: usize, row: usize| data[row * lines + col];
+
+        match self {
+            Interpolation::Nearest => at(x.round() as usize, y.round() as usize),
+            Interpolation::Bilinear => {
+                let (x0, y0) = (x.floor() as usize, y.floor() as usize);
+                let (x1, y1) = ((x0 + 1).min(lines - 1), (y0 + 1).min(lines - 1));
+                let (tx, ty) = (x - x0 as f32, y - y0 as f32);
+
+                let top = at(x0, y0) * (1.0 - tx) + at(x1, y0) * tx;
+                let bottom = at(x0, y1) * (1.0 - tx) + at(x1, y1) * tx;
+                top * (1.0 - ty) + bottom * ty
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for Interpolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Interpolation::Nearest => "Nearest",
+            Interpolation::Bilinear => "Bilinear",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Maps sample coordinates, in meters, onto canvas pixels and back.
+///
+/// The centre of the field sits at the centre of the canvas, [`MAX_FIELD`]
+/// spans its shorter side and Y points up.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CanvasMapping {
+    center: Point,
+    /// Pixels per meter.
+    scale: f64,
+}
+
+impl CanvasMapping {
+    pub fn new(size: Size) -> Self {
+        Self {
+            center: Point::new(size.width / 2.0, size.height / 2.0),
+            scale: size.width.min(size.height) as f64 / MAX_FIELD,
+        }
+    }
+
+    /// Pixels per meter.
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+
+    pub fn to_canvas(&self, (x, y): (f64, f64)) -> Point {
+        Point::new(
+            self.center.x + (x * self.scale) as f32,
+            self.center.y - (y * self.scale) as f32,
+        )
+    }
+
+    pub fn to_sample(&self, point: Point) -> (f64, f64) {
+        (
+            (point.x - self.center.x) as f64 / self.scale,
+            (self.center.y - point.y) as f64 / self.scale,
+        )
+    }
+}
+
+/// The sample position under `point`, kept within the hardware offset range.
+pub fn sample_position(mapping: &CanvasMapping, point: Point) -> Vector2<f64> {
+    let (x, y) = mapping.to_sample(point);
+    let (lower, upper) = hardware::OFFSET;
+
+    Vector2::new(x.clamp(lower, upper), y.clamp(lower, upper))
+}
+
+/// Readout of the sample position under `point`, e.g. `"x: 12.300 nm, y: -4.500 nm"`.
+pub fn position_label(mapping: &CanvasMapping, point: Point) -> String {
+    let (x, y) = mapping.to_sample(point);
+
+    format!(
+        "x: {}, y: {}",
+        ExponentialNumber::from_scientific(x).with_unit("m"),
+        ExponentialNumber::from_scientific(y).with_unit("m")
+    )
+}
+
+/// The largest length of 1, 2 or 5 × 10ⁿ meters that is at most `max`.
+pub fn nice_length(max: f64) -> f64 {
+    let magnitude = 10_f64.powf(max.log10().floor());
+
+    [5.0, 2.0, 1.0]
+        .into_iter()
+        .map(|step| step * magnitude)
+        .find(|length| *length <= max)
+        .unwrap_or(magnitude)
+}
+
+/// Draws a scale bar in the bottom right corner of `frame`, about a quarter
+/// of its width long, and ticks along the top and left edges at multiples of
+/// the bar length.
+fn draw_scale(frame: &mut Frame, mapping: CanvasMapping, color: Color) {
+    let (width, height) = (frame.width(), frame.height());
+    let length = nice_length(f64::from(width / 4.0) / mapping.scale());
+    // Nothing sensible to show on a canvas without area
+    if !length.is_normal() {
+        return;
+    }
+    let pixels = (length * mapping.scale()) as f32;
+    let stroke = Stroke::default().with_color(color).with_width(1.0);
+
+    let end = Point::new(width - READOUT_MARGIN, height - READOUT_MARGIN);
+    let start = Point::new(end.x - pixels, end.y);
+    frame.stroke(&Path::line(start, end), stroke.clone().with_width(2.0));
+    frame.fill_text(Text {
+        content: format!("{:.0}m", ExponentialNumber::from_scientific(length)),
+        position: Point::new(end.x - pixels / 2.0, end.y - READOUT_MARGIN),
+        color,
+        horizontal_alignment: alignment::Horizontal::Center,
+        vertical_alignment: alignment::Vertical::Bottom,
+        ..Text::default()
+    });
+
+    let (left, top) = mapping.to_sample(Point::ORIGIN);
+    let (right, bottom) = mapping.to_sample(Point::new(width, height));
+    let multiples = |from: f64, to: f64| {
+        ((from / length).ceil() as i64..=(to / length).floor() as i64).map(|i| i as f64 * length)
+    };
+
+    let ticks = Path::new(|builder| {
+        for x in multiples(left, right) {
+            let x = mapping.to_canvas((x, 0.0)).x;
+            builder.move_to(Point::new(x, 0.0));
+            builder.line_to(Point::new(x, TICK));
+        }
+        for y in multiples(bottom, top) {
+            let y = mapping.to_canvas((0.0, y)).y;
+            builder.move_to(Point::new(0.0, y));
+            builder.line_to(Point::new(TICK, y));
+        }
+    });
+    frame.stroke(&ticks, stroke);
+}
+
+/// The spectroscopy placed by pressing at `press` and releasing at `release`:
+/// a point where the two are within [`CLICK_TOLERANCE`] of each other, a line
+/// from one to the other otherwise.
+pub fn sts_between(mapping: &CanvasMapping, press: Point, release: Point) -> STSType {
+    if press.distance(release) <= CLICK_TOLERANCE {
+        STSType::Point(sample_position(mapping, press))
+    } else {
+        STSType::Line(vec![
+            sample_position(mapping, press),
+            sample_position(mapping, release),
+        ])
+    }
+}
+
+/// Color scale heights are drawn with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Colormap {
+    #[default]
+    Grayscale,
+    /// Perceptually uniform, dark purple through green to yellow.
+    Viridis,
+    /// Black through red and orange to white, the usual STM look.
+    Afmhot,
+    /// Diverging, blue below the middle of the range and red above it.
+    BlueWhiteRed,
+}
+
+impl Colormap {
+    pub const ALL: [Colormap; 4] = [
+        Colormap::Grayscale,
+        Colormap::Viridis,
+        Colormap::Afmhot,
+        Colormap::BlueWhiteRed,
+    ];
+
+    /// The color at `t`, from 0 at the bottom of the scale to 1 at the top.
+    /// `t` is clamped to that range.
+    pub fn sample(&self, t: f32) -> Color {
+        let t = t.clamp(0.0, 1.0);
+
+        match self {
+            Colormap::Grayscale => Color::from_rgb(t, t, t),
+            Colormap::Viridis => interpolate(
+                &[
+                    [0.267, 0.005, 0.329],
+                    [0.229, 0.322, 0.546],
+                    [0.128, 0.567, 0.551],
+                    [0.369, 0.789, 0.383],
+                    [0.993, 0.906, 0.144],
+                ],
+                t,
+            ),
+            Colormap::Afmhot => Color::from_rgb(
+                (2.0 * t).min(1.0),
+                (2.0 * t - 0.5).clamp(0.0, 1.0),
+                (2.0 * t - 1.0).max(0.0),
+            ),
+            Colormap::BlueWhiteRed => {
+                interpolate(&[[0.0, 0.0, 1.0], [1.0, 1.0, 1.0], [1.0, 0.0, 0.0]], t)
+            }
+        }
+    }
+
+    /// The color of a height between `min` and `max`. Missing values (NaN)
+    /// are transparent, and a flat image takes the bottom of the scale.
+    pub fn height_color(&self, value: f32, min: f32, max: f32) -> Color {
+        if value.is_nan() {
+            return Color::TRANSPARENT;
+        }
+        let t = if max > min {
+            (value - min) / (max - min)
+        } else {
+            0.0
+        };
+
+        self.sample(t)
+    }
+}
+
+impl std::fmt::Display for Colormap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Colormap::Grayscale => "Grayscale",
+            Colormap::Viridis => "Viridis",
+            Colormap::Afmhot => "AFM hot",
+            Colormap::BlueWhiteRed => "Blue-white-red",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Lowest and highest height in `data`, ignoring missing values (NaN).
+pub fn height_range(data: &[f32]) -> (f32, f32) {
+    data.iter()
+        .filter(|v| !v.is_nan())
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), &v| {
+            (min.min(v), max.max(v))
+        })
+}
+
+/// Blends linearly between evenly spaced RGB `stops` at `t` in [0, 1].
+fn interpolate(stops: &[[f32; 3]], t: f32) -> Color {
+    let position = t * (stops.len() - 1) as f32;
+    let i = (position.floor() as usize).min(stops.len() - 2);
+    let local = position - i as f32;
+    let [r, g, b] = [0, 1, 2].map(|c| stops[i][c] * (1.0 - local) + stops[i + 1][c] * local);
+
+    Color::from_rgb(r, g, b)
+}
+
+/// Zoom and pan of the [`Plot`] canvas, applied on top of its
+/// [`CanvasMapping`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    pub zoom: f32,
+    /// Shift of the field centre from the canvas centre, in pixels.
+    pub pan: Vector,
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Self {
+            zoom: 1.0,
+            pan: Vector::new(0.0, 0.0),
+        }
+    }
+}
+
+impl Viewport {
+    pub const MIN_ZOOM: f32 = 0.25;
+    pub const MAX_ZOOM: f32 = 16.0;
+
+    /// The mapping of a canvas of `size` seen through this viewport.
+    pub fn mapping(&self, size: Size) -> CanvasMapping {
+        let base = CanvasMapping::new(size);
+
+        CanvasMapping {
+            center: base.center + self.pan,
+            scale: base.scale * self.zoom as f64,
+        }
+    }
+
+    /// Zooms by `factor`, within [`Viewport::MIN_ZOOM`] and
+    /// [`Viewport::MAX_ZOOM`], keeping the sample point under `point` in
+    /// place.
+    pub fn zoomed_at(&self, size: Size, point: Point, factor: f32) -> Self {
+        let zoom = (self.zoom * factor).clamp(Self::MIN_ZOOM, Self::MAX_ZOOM);
+        let (x, y) = self.mapping(size).to_sample(point);
+        let base = CanvasMapping::new(size);
+        let scale = base.scale * zoom as f64;
+
+        let center = Point::new(point.x - (x * scale) as f32, point.y + (y * scale) as f32);
+
+        Self {
+            zoom,
+            pan: center - base.center,
+        }
+    }
+
+    pub fn panned(&self, delta: Vector) -> Self {
+        Self {
+            pan: self.pan + delta,
+            ..*self
+        }
+    }
+}
+
+/// Local state of a [`Plot`]: the rasterized height data, kept until the
+/// data or the way it is drawn changes.
+#[derive(Default)]
+pub struct State {
+    heatmap: Cache,
+    heatmap_key: Cell<Option<HeatmapKey>>,
+    viewport: Viewport,
+    /// Last cursor position while dragging to pan.
+    drag: Option<Point>,
+    /// Where the left button went down, until it is released.
+    press: Option<Point>,
+    last_click: Option<mouse::Click>,
+}
+
+/// Everything the cached heatmap depends on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct HeatmapKey {
+    checksum: u64,
+    lines: u32,
+    geometry: ScanGeometry,
+    interpolation: Interpolation,
+    colormap: Colormap,
+    viewport: Viewport,
+}
 
 pub struct Plot<'a, Message> {
-    cache: Option<Cache>,
     // TODO: make use of Message?
     on_change: Option<Box<dyn Fn(String) -> Message + 'a>>,
+    geometry: ScanGeometry,
+    data: Option<&'a [f32]>,
+    lines: u32,
+    interpolation: Interpolation,
+    colormap: Colormap,
+    grid: Option<f64>,
+    is_focused: bool,
+    on_focus: Option<Box<dyn Fn(bool) -> Message + 'a>>,
+    on_offset: Option<Box<dyn Fn(f64, f64) -> Message + 'a>>,
+    on_click: Option<Box<dyn Fn(Vector2<f64>) -> Message + 'a>>,
+    placing_sts: bool,
+    spectroscopy: &'a [STSType],
+    on_sts: Option<Box<dyn Fn(STSType) -> Message + 'a>>,
 }
 
 impl<'a, Message> Plot<'a, Message> {
     pub fn new() -> Self {
         Self {
-            cache: None,
             on_change: None,
+            geometry: ScanGeometry::default(),
+            data: None,
+            lines: 0,
+            interpolation: Interpolation::default(),
+            colormap: Colormap::default(),
+            grid: None,
+            is_focused: false,
+            on_focus: None,
+            on_offset: None,
+            on_click: None,
+            placing_sts: false,
+            spectroscopy: &[],
+            on_sts: None,
         }
     }
+
+    /// Sets the [`ScanGeometry`] drawn as the scan-area overlay.
+    pub fn geometry(mut self, geometry: ScanGeometry) -> Self {
+        self.geometry = geometry;
+        self
+    }
+
+    /// Sets the height data drawn inside the scan area, a `lines` × `lines`
+    /// image in row-major order.
+    pub fn data(mut self, data: Option<&'a [f32]>, lines: u32) -> Self {
+        self.data = data;
+        self.lines = lines;
+        self
+    }
+
+    /// Sets how the data is upscaled to the canvas.
+    pub fn interpolation(mut self, interpolation: Interpolation) -> Self {
+        self.interpolation = interpolation;
+        self
+    }
+
+    /// Sets the color scale the heights are drawn with.
+    pub fn colormap(mut self, colormap: Colormap) -> Self {
+        self.colormap = colormap;
+        self
+    }
+
+    /// Sets the grid spacing, in meters, that moves of the scan area snap to.
+    pub fn grid(mut self, grid: Option<f64>) -> Self {
+        self.grid = grid;
+        self
+    }
+
+    /// Sets whether the [`Plot`] has keyboard focus. A focused plot is
+    /// outlined and nudges the scan offset with the arrow keys.
+    pub fn focused(mut self, is_focused: bool) -> Self {
+        self.is_focused = is_focused;
+        self
+    }
+
+    /// Sets the message produced when the [`Plot`] is clicked to take focus.
+    pub fn on_focus(mut self, on_focus: impl Fn(bool) -> Message + 'a) -> Self {
+        self.on_focus = Some(Box::new(on_focus));
+        self
+    }
+
+    /// Sets the message produced with the new X/Y offset, in meters, when the
+    /// scan area is moved from the [`Plot`].
+    pub fn on_offset(mut self, on_offset: impl Fn(f64, f64) -> Message + 'a) -> Self {
+        self.on_offset = Some(Box::new(on_offset));
+        self
+    }
+
+    /// Sets the message produced with the sample position, in meters, that
+    /// was clicked without dragging, e.g. to centre the scan there. The
+    /// position is kept within the hardware offset range.
+    pub fn on_click(mut self, on_click: impl Fn(Vector2<f64>) -> Message + 'a) -> Self {
+        self.on_click = Some(Box::new(on_click));
+        self
+    }
+
+    /// Sets whether clicks place spectroscopy instead of moving the view: a
+    /// click drops a point, a drag draws a line.
+    pub fn placing_sts(mut self, placing_sts: bool) -> Self {
+        self.placing_sts = placing_sts;
+        self
+    }
+
+    /// Sets the spectroscopy positions drawn as markers over the image.
+    pub fn spectroscopy(mut self, spectroscopy: &'a [STSType]) -> Self {
+        self.spectroscopy = spectroscopy;
+        self
+    }
+
+    /// Sets the message produced with the spectroscopy placed while
+    /// [`Plot::placing_sts`] is on.
+    pub fn on_sts(mut self, on_sts: impl Fn(STSType) -> Message + 'a) -> Self {
+        self.on_sts = Some(Box::new(on_sts));
+        self
+    }
+}
+
+impl<'a, Message> Plot<'a, Message> {
+    /// Draws the height data in the scan frame, so the image follows offset
+    /// and rotation.
+    fn draw_heatmap(&self, frame: &mut Frame, mapping: CanvasMapping, data: &[f32]) {
+        let lines = self.lines as usize;
+
+        let (min, max) = height_range(data);
+        let side = (self.geometry.size * mapping.scale()) as f32;
+        let cell = side / lines as f32;
+        let origin = mapping.to_canvas((self.geometry.x_offset, self.geometry.y_offset));
+
+        frame.with_save(|frame| {
+            frame.translate(Vector::new(origin.x, origin.y));
+            frame.rotate(-self.geometry.rotation.to_radians() as f32);
+
+            // Bilinear output is sampled on a finer grid, up to one
+            // sample per canvas pixel
+            let subdivisions = match self.interpolation {
+                Interpolation::Nearest => 1,
+                Interpolation::Bilinear => (cell.ceil() as usize).clamp(1, MAX_SUBDIVISIONS),
+            };
+            let samples = lines * subdivisions;
+            let sample_size = side / samples as f32;
+
+            for i in 0..samples * samples {
+                let (row, col) = ((i / samples) as f32, (i % samples) as f32);
+                let value = self.interpolation.sample(
+                    data,
+                    lines,
+                    (col + 0.5) / subdivisions as f32 - 0.5,
+                    (row + 0.5) / subdivisions as f32 - 0.5,
+                );
+
+                frame.fill_rectangle(
+                    Point::new(
+                        -side / 2.0 + col * sample_size,
+                        -side / 2.0 + row * sample_size,
+                    ),
+                    Size::new(sample_size, sample_size),
+                    self.colormap.height_color(value, min, max),
+                );
+            }
+        });
+    }
 }
 
 impl<'a, Message> Program<Message> for Plot<'a, Message> {
-    type State = ();
+    type State = State;
+
+    fn update(
+        &self,
+        state: &mut Self::State,
+        event: Event,
+        bounds: iced::Rectangle,
+        cursor: Cursor,
+    ) -> (event::Status, Option<Message>) {
+        match event {
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                let Some(position) = cursor.position_in(&bounds) else {
+                    return (event::Status::Ignored, None);
+                };
+
+                let click = mouse::Click::new(position, state.last_click);
+                state.last_click = Some(click);
+
+                // Placing spectroscopy never pans, and a double click
+                // otherwise returns to the whole field
+                if self.placing_sts {
+                    state.drag = None;
+                } else if let mouse::click::Kind::Double = click.kind() {
+                    state.viewport = Viewport::default();
+                    state.drag = None;
+                } else {
+                    state.drag = Some(position);
+                }
+                state.press = Some(position);
+
+                let message = self.on_focus.as_ref().map(|on_focus| on_focus(true));
+                return (event::Status::Captured, message);
+            }
+            Event::Mouse(mouse::Event::CursorMoved { .. }) => {
+                if let (Some(last), Some(position)) = (state.drag, cursor.position_in(&bounds)) {
+                    state.viewport = state.viewport.panned(position - last);
+                    state.drag = Some(position);
+                    return (event::Status::Captured, None);
+                }
+            }
+            Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
+                let press = state.press.take();
+                state.drag = None;
+                let mapping = state.viewport.mapping(bounds.size());
+
+                if self.placing_sts {
+                    let sts = press
+                        .zip(cursor.position_in(&bounds))
+                        .map(|(press, release)| sts_between(&mapping, press, release));
+
+                    return match (sts, &self.on_sts) {
+                        (Some(sts), Some(on_sts)) => (event::Status::Captured, Some(on_sts(sts))),
+                        _ => (event::Status::Captured, None),
+                    };
+                }
+
+                // A press released where it started is a click, not a pan
+                let click = press
+                    .zip(cursor.position_in(&bounds))
+                    .filter(|(press, release)| press.distance(*release) <= CLICK_TOLERANCE);
+
+                if let (Some((_, position)), Some(on_click)) = (click, &self.on_click) {
+                    let position = sample_position(&mapping, position);
+                    return (event::Status::Captured, Some(on_click(position)));
+                }
+                if press.is_some() {
+                    return (event::Status::Captured, None);
+                }
+            }
+            Event::Mouse(mouse::Event::WheelScrolled { delta }) => {
+                let Some(position) = cursor.position_in(&bounds) else {
+                    return (event::Status::Ignored, None);
+                };
+                let steps = match delta {
+                    mouse::ScrollDelta::Lines { y, .. } => y,
+                    mouse::ScrollDelta::Pixels { y, .. } => y / PIXELS_PER_ZOOM_STEP,
+                };
+
+                let factor = ZOOM_STEP.powf(steps);
+
+                state.viewport = state.viewport.zoomed_at(bounds.size(), position, factor);
+                return (event::Status::Captured, None);
+            }
+            Event::Keyboard(keyboard::Event::KeyPressed {
+                key_code,
+                modifiers,
+            }) if self.is_focused => {
+                let direction = match key_code {
+                    keyboard::KeyCode::Left => (-1.0, 0.0),
+                    keyboard::KeyCode::Right => (1.0, 0.0),
+                    keyboard::KeyCode::Up => (0.0, 1.0),
+                    keyboard::KeyCode::Down => (0.0, -1.0),
+                    _ => return (event::Status::Ignored, None),
+                };
+
+                if let Some(on_offset) = &self.on_offset {
+                    let (x, y) = self.geometry.nudged(direction, modifiers.shift(), self.grid);
+                    return (event::Status::Captured, Some(on_offset(x, y)));
+                }
+            }
+            _ => {}
+        }
+
+        (event::Status::Ignored, None)
+    }
 
     fn draw(
         &self,
@@ -28,10 +665,314 @@ impl<'a, Message> Program<Message> for Plot<'a, Message> {
     ) -> Vec<Geometry> {
         let mut frame = Frame::new(bounds.size());
 
-        let circle = Path::circle(frame.center(), 10.0);
+        let mapping = state.viewport.mapping(frame.size());
+        let to_canvas = |point| mapping.to_canvas(point);
+
+        let lines = self.lines as usize;
+        let data = self
+            .data
+            .filter(|data| lines > 0 && data.len() == lines * lines);
+
+        // Rasterize again only when what the heatmap shows has changed
+        let key = data.map(|data| HeatmapKey {
+            checksum: data
+                .iter()
+                .fold(0u64, |hash, v| hash.rotate_left(5) ^ u64::from(v.to_bits())),
+            lines: self.lines,
+            geometry: self.geometry,
+            interpolation: self.interpolation,
+            colormap: self.colormap,
+            viewport: state.viewport,
+        });
+        if state.heatmap_key.replace(key) != key {
+            state.heatmap.clear();
+        }
+
+        let heatmap = state.heatmap.draw(bounds.size(), |frame| {
+            if let Some(data) = data {
+                self.draw_heatmap(frame, mapping, data);
+            }
+        });
+
+        let corners = self.geometry.corners();
+        let scan_area = Path::new(|builder| {
+            builder.move_to(to_canvas(corners[0]));
+            for corner in &corners[1..] {
+                builder.line_to(to_canvas(*corner));
+            }
+            builder.close();
+        });
+
+        frame.stroke(
+            &scan_area,
+            Stroke::default()
+                .with_color(theme.palette().primary)
+                .with_width(2.0),
+        );
+
+        if self.is_focused {
+            frame.stroke(
+                &Path::rectangle(Point::ORIGIN, frame.size()),
+                Stroke::default()
+                    .with_color(theme.palette().primary)
+                    .with_width(4.0),
+            );
+        }
+
+        // Crosshair on the centre of the scan area
+        let origin = to_canvas((self.geometry.x_offset, self.geometry.y_offset));
+        let crosshair = Path::new(|builder| {
+            builder.move_to(Point::new(origin.x - CROSSHAIR, origin.y));
+            builder.line_to(Point::new(origin.x + CROSSHAIR, origin.y));
+            builder.move_to(Point::new(origin.x, origin.y - CROSSHAIR));
+            builder.line_to(Point::new(origin.x, origin.y + CROSSHAIR));
+        });
+
+        frame.stroke(
+            &crosshair,
+            Stroke::default()
+                .with_color(theme.palette().primary)
+                .with_width(1.0),
+        );
 
-        frame.fill(&circle, Color::BLACK);
+        let marker_stroke = Stroke::default()
+            .with_color(theme.palette().danger)
+            .with_width(2.0);
+        let marker_at = |position: &Vector2<f64>| mapping.to_canvas((position.x(), position.y()));
 
-        vec![frame.into_geometry()]
+        for sts in self.spectroscopy {
+            let positions = sts.positions(&self.geometry);
+
+            let marker = Path::new(|builder| {
+                if let (STSType::Line(_), Some((first, rest))) = (sts, positions.split_first()) {
+                    builder.move_to(marker_at(first));
+                    for position in rest {
+                        builder.line_to(marker_at(position));
+                    }
+                }
+                for position in &positions {
+                    builder.circle(marker_at(position), MARKER);
+                }
+            });
+            frame.stroke(&marker, marker_stroke.clone());
+        }
+
+        // Line being dragged out, not placed yet
+        if let (true, Some(press), Some(position)) =
+            (self.placing_sts, state.press, cursor.position_in(&bounds))
+        {
+            if press.distance(position) > CLICK_TOLERANCE {
+                frame.stroke(&Path::line(press, position), marker_stroke);
+            }
+        }
+
+        draw_scale(&mut frame, mapping, theme.palette().text);
+
+        // Where the tip would be, in the bottom left corner
+        if let Some(position) = cursor.position_in(&bounds) {
+            frame.fill_text(Text {
+                content: position_label(&mapping, position),
+                position: Point::new(READOUT_MARGIN, frame.height() - READOUT_MARGIN),
+                color: theme.palette().text,
+                vertical_alignment: alignment::Vertical::Bottom,
+                ..Text::default()
+            });
+        }
+
+        vec![heatmap, frame.into_geometry()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A square canvas on which the 2.1 µm field spans 420 pixels, 200 pixels
+    /// per micrometre.
+    fn canvas() -> Size {
+        Size::new(420.0, 420.0)
+    }
+
+    fn assert_near(actual: Point, expected: Point) {
+        assert!(
+            actual.distance(expected) < 1e-3,
+            "{:?} is not {:?}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn scan_area_maps_onto_canvas_pixels_and_back() {
+        let mapping = CanvasMapping::new(canvas());
+        assert_eq!(mapping.to_canvas((0.0, 0.0)), Point::new(210.0, 210.0));
+        // Y points up on the sample and down on the canvas
+        assert_near(mapping.to_canvas((1e-6, 0.5e-6)), Point::new(410.0, 110.0));
+
+        let (x, y) = mapping.to_sample(Point::new(410.0, 110.0));
+        assert!((x - 1e-6).abs() < 1e-12 && (y - 0.5e-6).abs() < 1e-12);
+
+        // A 1 µm frame centred at the origin spans half the canvas
+        let corners = ScanGeometry::new(1e-6, 0.0, 0.0, 0.0).corners();
+        assert_near(mapping.to_canvas(corners[0]), Point::new(110.0, 310.0));
+        assert_near(mapping.to_canvas(corners[2]), Point::new(310.0, 110.0));
+    }
+
+    #[test]
+    fn full_field_fits_the_shorter_side() {
+        let wide = CanvasMapping::new(Size::new(600.0, 420.0));
+        assert_eq!(wide.scale(), CanvasMapping::new(canvas()).scale());
+        assert_near(wide.to_canvas((-1.05e-6, 1.05e-6)), Point::new(90.0, 0.0));
+    }
+
+    #[test]
+    fn heights_span_black_to_white() {
+        let gray = Colormap::Grayscale;
+        assert_eq!(gray.height_color(-2.0, -2.0, 6.0), Color::BLACK);
+        assert_eq!(gray.height_color(6.0, -2.0, 6.0), Color::WHITE);
+        let middle = Color::from_rgb(0.5, 0.5, 0.5);
+        assert_eq!(gray.height_color(2.0, -2.0, 6.0), middle);
+        assert_eq!(gray.height_color(f32::NAN, -2.0, 6.0), Color::TRANSPARENT);
+        // A flat image has nothing to spread over the scale
+        assert_eq!(gray.height_color(3.0, 3.0, 3.0), Color::BLACK);
+    }
+
+    #[test]
+    fn height_range_skips_missing_values() {
+        assert_eq!(height_range(&[1.0, f32::NAN, -4.0, 2.5]), (-4.0, 2.5));
+    }
+
+    #[test]
+    fn colormaps_run_from_their_first_to_last_color() {
+        let rgb = |colormap: Colormap, t| {
+            let color = colormap.sample(t);
+            [color.r, color.g, color.b]
+        };
+        let ends = |colormap| [0.0, 0.5, 1.0].map(|t| rgb(colormap, t));
+
+        assert_eq!(ends(Colormap::Grayscale), [[0.0; 3], [0.5; 3], [1.0; 3]]);
+        assert_eq!(
+            ends(Colormap::Viridis),
+            [
+                [0.267, 0.005, 0.329],
+                [0.128, 0.567, 0.551],
+                [0.993, 0.906, 0.144]
+            ]
+        );
+        assert_eq!(
+            ends(Colormap::Afmhot),
+            [[0.0; 3], [1.0, 0.5, 0.0], [1.0; 3]]
+        );
+        assert_eq!(
+            ends(Colormap::BlueWhiteRed),
+            [[0.0, 0.0, 1.0], [1.0; 3], [1.0, 0.0, 0.0]]
+        );
+        // Outside the scale the ends are held
+        assert_eq!(rgb(Colormap::Viridis, 1.5), rgb(Colormap::Viridis, 1.0));
+        assert_eq!(rgb(Colormap::Afmhot, -0.5), [0.0; 3]);
+    }
+
+    #[test]
+    fn zoom_keeps_the_point_under_the_cursor() {
+        let cursor = Point::new(300.0, 100.0);
+        let before = Viewport::default().mapping(canvas()).to_sample(cursor);
+
+        let zoomed = Viewport::default().zoomed_at(canvas(), cursor, 2.0);
+        assert_eq!(zoomed.zoom, 2.0);
+        let mapping = zoomed.mapping(canvas());
+        assert_eq!(mapping.scale(), 2.0 * CanvasMapping::new(canvas()).scale());
+        assert_near(mapping.to_canvas(before), cursor);
+    }
+
+    #[test]
+    fn zoom_is_clamped_and_pan_shifts_the_field() {
+        let cursor = Point::new(210.0, 210.0);
+        let viewport = Viewport::default();
+        let zoom = |factor| viewport.zoomed_at(canvas(), cursor, factor).zoom;
+        assert_eq!(zoom(100.0), Viewport::MAX_ZOOM);
+        assert_eq!(zoom(0.01), Viewport::MIN_ZOOM);
+
+        let panned = viewport.panned(Vector::new(30.0, -20.0)).mapping(canvas());
+        assert_eq!(panned.to_canvas((0.0, 0.0)), Point::new(240.0, 190.0));
+        assert_eq!(viewport.mapping(canvas()), CanvasMapping::new(canvas()));
+    }
+
+    #[test]
+    fn clicks_pick_the_sample_position_under_them() {
+        let mapping = CanvasMapping::new(canvas());
+        let at = |x, y| {
+            let position = sample_position(&mapping, Point::new(x, y));
+            (position.x(), position.y())
+        };
+
+        assert_eq!(at(210.0, 210.0), (0.0, 0.0));
+        let (x, y) = at(310.0, 250.0);
+        assert!((x - 0.5e-6).abs() < 1e-12 && (y + 0.2e-6).abs() < 1e-12);
+        // Past the edge of the field on a zoomed-out view
+        let far = Viewport::default().zoomed_at(canvas(), Point::new(210.0, 210.0), 0.5);
+        let position = sample_position(&far.mapping(canvas()), Point::ORIGIN);
+        assert_eq!((position.x(), position.y()), (hardware::OFFSET.0, hardware::OFFSET.1));
+    }
+
+    #[test]
+    fn click_places_a_point_and_drag_a_line() {
+        let mapping = CanvasMapping::new(canvas());
+        let (press, release) = (Point::new(210.0, 210.0), Point::new(212.0, 208.0));
+        let origin = Vector2::new(0.0, 0.0);
+
+        // Within the click tolerance the press position counts
+        assert_eq!(sts_between(&mapping, press, release), STSType::Point(origin.clone()));
+
+        let line = sts_between(&mapping, press, Point::new(410.0, 210.0));
+        let STSType::Line(positions) = line else {
+            panic!("expected a line, got {:?}", line);
+        };
+        assert_eq!(positions.len(), 2);
+        assert_eq!(positions[0], origin);
+        assert!((positions[1].x() - 1e-6).abs() < 1e-12 && positions[1].y() == 0.0);
+    }
+
+    #[test]
+    fn readout_names_the_position_at_the_corners_and_centre() {
+        let mapping = CanvasMapping::new(canvas());
+        let label = |x, y| position_label(&mapping, Point::new(x, y));
+
+        assert_eq!(label(210.0, 210.0), "x: 0.000 m, y: 0.000 m");
+        assert_eq!(label(0.0, 0.0), "x: -1.050 µm, y: 1.050 µm");
+        assert_eq!(label(420.0, 420.0), "x: 1.050 µm, y: -1.050 µm");
+        assert_eq!(label(420.0, 0.0), "x: 1.050 µm, y: 1.050 µm");
+    }
+
+    #[test]
+    fn scale_bar_rounds_down_to_one_two_or_five() {
+        let cases = [(3.3e-7, 2e-7), (1.9e-8, 1e-8), (9.99, 5.0), (1.5e-9, 1e-9)];
+
+        for (max, expected) in cases {
+            let length = nice_length(max);
+            assert!((length / expected - 1.0).abs() < 1e-9, "{} for {}", length, max);
+        }
+        // A quarter of the full field on a 420 pixel canvas
+        let mapping = CanvasMapping::new(canvas());
+        let quarter = nice_length(105.0 / mapping.scale());
+        assert!((quarter / 5e-7 - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn nearest_keeps_a_checkerboard_sharp_and_bilinear_blends_it() {
+        let board = [0.0, 1.0, 1.0, 0.0];
+        let nearest = |x, y| Interpolation::Nearest.sample(&board, 2, x, y);
+        let bilinear = |x, y| Interpolation::Bilinear.sample(&board, 2, x, y);
+
+        // Both agree on pixel centres
+        for (x, y) in [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0)] {
+            assert_eq!(nearest(x, y), bilinear(x, y));
+        }
+        assert_eq!(nearest(0.25, 0.0), 0.0);
+        assert_eq!(bilinear(0.25, 0.0), 0.25);
+        assert_eq!(nearest(0.75, 0.0), 1.0);
+        assert_eq!(bilinear(0.5, 0.5), 0.5);
+        // Past the edge the edge pixel is repeated
+        assert_eq!(bilinear(-3.0, 0.0), 0.0);
+        assert_eq!(nearest(1.0, 5.0), 0.0);
     }
 }
@@ -1,4 +1,8 @@
+pub mod busy;
+pub mod focus;
 pub mod image_plot;
+pub mod modal;
+pub mod motion;
 pub mod scientific_text_input;
 pub mod scientificspinbox;
 pub mod taskdisplay;
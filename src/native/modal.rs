@@ -0,0 +1,332 @@
+//! Show a dialog on top of the rest of the application.
+use iced_native::alignment::Alignment;
+use iced_native::event::{self, Event};
+use iced_native::layout::{self, Layout};
+use iced_native::mouse;
+use iced_native::overlay;
+use iced_native::renderer;
+use iced_native::widget::{tree::Tree, Operation, Widget};
+use iced_native::{keyboard, Clipboard, Color, Element, Length, Point, Rectangle, Shell, Size};
+
+/// The contents of a dialog: a title, a body and the buttons that close it.
+///
+/// Every button closes the dialog, and may carry a message handled after it
+/// closed. Dialogs are kept on a stack by the application and only the top one
+/// is shown.
+#[derive(Debug, Clone)]
+pub struct Dialog<Message> {
+    pub title: String,
+    pub body: String,
+    pub buttons: Vec<(String, Option<Message>)>,
+}
+
+impl<Message> Dialog<Message> {
+    pub fn new(title: impl Into<String>, body: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            body: body.into(),
+            buttons: Vec::new(),
+        }
+    }
+
+    /// Adds a button, left to right, sending `message` when pressed.
+    pub fn button(mut self, label: impl Into<String>, message: Message) -> Self {
+        self.buttons.push((label.into(), Some(message)));
+        self
+    }
+
+    /// Adds a button that only closes the dialog.
+    pub fn close_button(mut self, label: impl Into<String>) -> Self {
+        self.buttons.push((label.into(), None));
+        self
+    }
+}
+
+/// Draws `content` centred over `base`, dimming it.
+///
+/// While a [`Modal`] is shown the base receives no events, so focus stays in
+/// the dialog. Escape and clicks outside the content send the `on_dismiss`
+/// message.
+pub struct Modal<'a, Message, Renderer> {
+    base: Element<'a, Message, Renderer>,
+    content: Element<'a, Message, Renderer>,
+    on_dismiss: Option<Message>,
+}
+
+impl<'a, Message, Renderer> Modal<'a, Message, Renderer> {
+    pub fn new(
+        base: impl Into<Element<'a, Message, Renderer>>,
+        content: impl Into<Element<'a, Message, Renderer>>,
+    ) -> Self {
+        Self {
+            base: base.into(),
+            content: content.into(),
+            on_dismiss: None,
+        }
+    }
+
+    /// Sets the message produced on Escape or a click outside the dialog.
+    pub fn on_dismiss(mut self, message: Message) -> Self {
+        self.on_dismiss = Some(message);
+        self
+    }
+}
+
+impl<'a, Message, Renderer> Widget<Message, Renderer> for Modal<'a, Message, Renderer>
+where
+    Message: Clone,
+    Renderer: renderer::Renderer,
+{
+    fn children(&self) -> Vec<Tree> {
+        vec![Tree::new(&self.base), Tree::new(&self.content)]
+    }
+
+    fn diff(&self, tree: &mut Tree) {
+        tree.diff_children(&[&self.base, &self.content]);
+    }
+
+    fn width(&self) -> Length {
+        self.base.as_widget().width()
+    }
+
+    fn height(&self) -> Length {
+        self.base.as_widget().height()
+    }
+
+    fn layout(&self, renderer: &Renderer, limits: &layout::Limits) -> layout::Node {
+        self.base.as_widget().layout(renderer, limits)
+    }
+
+    fn on_event(
+        &mut self,
+        _tree: &mut Tree,
+        _event: Event,
+        _layout: Layout<'_>,
+        _cursor_position: Point,
+        _renderer: &Renderer,
+        _clipboard: &mut dyn Clipboard,
+        _shell: &mut Shell<'_, Message>,
+    ) -> event::Status {
+        event::Status::Ignored
+    }
+
+    fn draw(
+        &self,
+        tree: &Tree,
+        renderer: &mut Renderer,
+        theme: &Renderer::Theme,
+        style: &renderer::Style,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        viewport: &Rectangle,
+    ) {
+        self.base.as_widget().draw(
+            &tree.children[0],
+            renderer,
+            theme,
+            style,
+            layout,
+            cursor_position,
+            viewport,
+        );
+    }
+
+    fn overlay<'b>(
+        &'b mut self,
+        tree: &'b mut Tree,
+        layout: Layout<'_>,
+        _renderer: &Renderer,
+    ) -> Option<overlay::Element<'b, Message, Renderer>> {
+        Some(overlay::Element::new(
+            layout.position(),
+            Box::new(Overlay {
+                content: &mut self.content,
+                tree: &mut tree.children[1],
+                size: layout.bounds().size(),
+                on_dismiss: self.on_dismiss.clone(),
+            }),
+        ))
+    }
+}
+
+/// Whether `event` closes the dialog: Escape, or a left click that is not
+/// `on_content`.
+fn dismisses(event: &Event, on_content: bool) -> bool {
+    match event {
+        Event::Keyboard(keyboard::Event::KeyPressed {
+            key_code: keyboard::KeyCode::Escape,
+            ..
+        }) => true,
+        Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => !on_content,
+        _ => false,
+    }
+}
+
+struct Overlay<'a, 'b, Message, Renderer> {
+    content: &'b mut Element<'a, Message, Renderer>,
+    tree: &'b mut Tree,
+    size: Size,
+    on_dismiss: Option<Message>,
+}
+
+impl<'a, 'b, Message, Renderer> overlay::Overlay<Message, Renderer>
+    for Overlay<'a, 'b, Message, Renderer>
+where
+    Message: Clone,
+    Renderer: renderer::Renderer,
+{
+    fn layout(&self, renderer: &Renderer, _bounds: Size, position: Point) -> layout::Node {
+        let limits = layout::Limits::new(Size::ZERO, self.size)
+            .width(Length::Fill)
+            .height(Length::Fill);
+
+        let mut child = self.content.as_widget().layout(renderer, &limits);
+        child.align(Alignment::Center, Alignment::Center, limits.max());
+
+        let mut node = layout::Node::with_children(self.size, vec![child]);
+        node.move_to(position);
+        node
+    }
+
+    fn on_event(
+        &mut self,
+        event: Event,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        renderer: &Renderer,
+        clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+    ) -> event::Status {
+        let content_layout = layout.children().next().unwrap();
+
+        if let Some(message) = self.on_dismiss.as_ref() {
+            let on_content = content_layout.bounds().contains(cursor_position);
+
+            if dismisses(&event, on_content) {
+                shell.publish(message.clone());
+                return event::Status::Captured;
+            }
+        }
+
+        self.content.as_widget_mut().on_event(
+            self.tree,
+            event,
+            content_layout,
+            cursor_position,
+            renderer,
+            clipboard,
+            shell,
+        );
+
+        // Nothing behind the dialog may react to the event
+        event::Status::Captured
+    }
+
+    fn draw(
+        &self,
+        renderer: &mut Renderer,
+        theme: &Renderer::Theme,
+        style: &renderer::Style,
+        layout: Layout<'_>,
+        cursor_position: Point,
+    ) {
+        renderer.fill_quad(
+            renderer::Quad {
+                bounds: layout.bounds(),
+                border_radius: 0.0.into(),
+                border_width: 0.0,
+                border_color: Color::TRANSPARENT,
+            },
+            Color {
+                a: 0.8,
+                ..Color::BLACK
+            },
+        );
+
+        self.content.as_widget().draw(
+            self.tree,
+            renderer,
+            theme,
+            style,
+            layout.children().next().unwrap(),
+            cursor_position,
+            &layout.bounds(),
+        );
+    }
+
+    fn operate(
+        &mut self,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+        operation: &mut dyn Operation<Message>,
+    ) {
+        self.content.as_widget().operate(
+            self.tree,
+            layout.children().next().unwrap(),
+            renderer,
+            operation,
+        );
+    }
+
+    fn mouse_interaction(
+        &self,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        viewport: &Rectangle,
+        renderer: &Renderer,
+    ) -> mouse::Interaction {
+        self.content.as_widget().mouse_interaction(
+            self.tree,
+            layout.children().next().unwrap(),
+            cursor_position,
+            viewport,
+            renderer,
+        )
+    }
+}
+
+impl<'a, Message, Renderer> From<Modal<'a, Message, Renderer>> for Element<'a, Message, Renderer>
+where
+    Message: 'a + Clone,
+    Renderer: 'a + renderer::Renderer,
+{
+    fn from(modal: Modal<'a, Message, Renderer>) -> Self {
+        Element::new(modal)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pressed(key_code: keyboard::KeyCode) -> Event {
+        Event::Keyboard(keyboard::Event::KeyPressed {
+            key_code,
+            modifiers: keyboard::Modifiers::empty(),
+        })
+    }
+
+    #[test]
+    fn buttons_are_kept_left_to_right() {
+        let dialog = Dialog::new("Stop the run?", "The scan is lost.")
+            .close_button("Cancel")
+            .button("Stop", 1);
+
+        assert_eq!(dialog.title, "Stop the run?");
+        assert_eq!(dialog.body, "The scan is lost.");
+        assert_eq!(
+            dialog.buttons,
+            [("Cancel".to_string(), None), ("Stop".to_string(), Some(1))]
+        );
+    }
+
+    #[test]
+    fn escape_and_clicks_outside_dismiss() {
+        let click = Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left));
+
+        assert!(dismisses(&pressed(keyboard::KeyCode::Escape), true));
+        assert!(dismisses(&click, false));
+        assert!(!dismisses(&click, true));
+        assert!(!dismisses(&pressed(keyboard::KeyCode::Enter), false));
+    }
+}
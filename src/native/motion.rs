@@ -0,0 +1,33 @@
+//! Application-wide switch for time-driven animations.
+//!
+//! Widgets check [`is_reduced`] before animating: with motion reduced they draw
+//! their final state right away and stop asking for timed redraws, so an idle
+//! window does no work.
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static REDUCE_MOTION: AtomicBool = AtomicBool::new(false);
+
+/// Turns animations off (`true`) or back on for every widget.
+pub fn set_reduced(reduced: bool) {
+    REDUCE_MOTION.store(reduced, Ordering::Relaxed);
+}
+
+/// Whether animations are currently turned off.
+pub fn is_reduced() -> bool {
+    REDUCE_MOTION.load(Ordering::Relaxed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_reduced_follows_the_setting() {
+        assert!(!is_reduced());
+
+        set_reduced(true);
+        assert!(is_reduced());
+        set_reduced(false);
+        assert!(!is_reduced());
+    }
+}
@@ -26,7 +26,9 @@ use iced_native::{
     Clipboard, Color, Element, Layout, Length, Padding, Pixels, Point, Rectangle, Shell, Size,
     Vector, Widget,
 };
+use std::str::FromStr;
 
+use crate::native::motion;
 use crate::style::scientific_text_input::StyleSheet;
 
 /// A field that can be filled with text.
@@ -68,6 +70,7 @@ where
     on_submit: Option<Message>,
     icon: Option<Icon<Renderer::Font>>,
     style: <Renderer::Theme as StyleSheet>::Style,
+    error_style: Option<<Renderer::Theme as StyleSheet>::Style>,
 }
 
 impl<'a, Message, Renderer> ScientificTextInput<'a, Message, Renderer>
@@ -96,6 +99,7 @@ where
             on_submit: None,
             icon: None,
             style: Default::default(),
+            error_style: None,
         }
     }
 
@@ -179,6 +183,14 @@ where
         self
     }
 
+    /// Sets the style the [`ScientificTextInput`] is drawn with while a typed
+    /// character was rejected. Without one, the regular style's error
+    /// appearance is used.
+    pub fn error_style(mut self, style: impl Into<<Renderer::Theme as StyleSheet>::Style>) -> Self {
+        self.error_style = Some(style.into());
+        self
+    }
+
     /// Draws the [`ScientificTextInput`] with the given [`Renderer`], overriding its
     /// [`Value`] if provided.
     ///
@@ -205,6 +217,7 @@ where
             self.on_input.is_none(),
             self.is_secure,
             self.icon.as_ref(),
+            self.error_style.as_ref().unwrap_or(&self.style),
             &self.style,
         )
     }
@@ -233,6 +246,7 @@ where
             state.is_focused = None;
             state.is_pasting = None;
             state.is_dragging = false;
+            state.is_invalid = false;
         }
     }
 
@@ -264,7 +278,10 @@ where
     ) {
         let state = tree.state.downcast_mut::<State>();
 
-        operation.focusable(state, self.id.as_ref().map(|id| &id.0));
+        // Without `on_input` the value cannot be edited, so it takes no focus
+        if self.on_input.is_some() {
+            operation.focusable(state, self.id.as_ref().map(|id| &id.0));
+        }
         // operation.text_input(state, self.id.as_ref().map(|id| &id.0));
     }
 
@@ -274,17 +291,22 @@ where
         event: Event,
         layout: Layout<'_>,
         cursor_position: Point,
-        _: &Renderer,
-        _: &mut dyn Clipboard,
+        renderer: &Renderer,
+        clipboard: &mut dyn Clipboard,
         shell: &mut Shell<'_, Message>,
     ) -> event::Status {
         update(
             event,
             layout,
             cursor_position,
+            renderer,
+            clipboard,
             shell,
             &mut self.value,
+            self.size,
+            &self.font,
             self.on_input.as_deref(),
+            self.on_paste.as_deref(),
             &self.on_submit,
             || tree.state.downcast_mut::<State>(),
         )
@@ -313,6 +335,7 @@ where
             self.on_input.is_none(),
             self.is_secure,
             self.icon.as_ref(),
+            self.error_style.as_ref().unwrap_or(&self.style),
             &self.style,
         )
     }
@@ -452,25 +475,25 @@ where
 
 /// Processes an [`Event`] and updates the [`State`] of a [`ScientificTextInput`]
 /// accordingly.
-pub fn update<'a, Message>(
+pub fn update<'a, Message, Renderer>(
     event: Event,
     layout: Layout<'_>,
     cursor_position: Point,
-    // renderer: &Renderer,
-    // clipboard: &mut dyn Clipboard,
+    renderer: &Renderer,
+    clipboard: &mut dyn Clipboard,
     shell: &mut Shell<'_, Message>,
     value: &mut Value,
-    // size: Option<f32>,
-    // font: &Renderer::Font,
+    size: Option<f32>,
+    font: &Renderer::Font,
     // is_secure: bool,
     on_input: Option<&dyn Fn(String) -> Message>,
-    // on_paste: Option<&dyn Fn(String) -> Message>,
+    on_paste: Option<&dyn Fn(String) -> Message>,
     on_submit: &Option<Message>,
     state: impl FnOnce() -> &'a mut State,
 ) -> event::Status
 where
     Message: Clone,
-    // Renderer: text::Renderer,
+    Renderer: text::Renderer,
 {
     match event {
         Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left))
@@ -488,29 +511,153 @@ where
                     })
                 })
             } else {
+                state.is_invalid = false;
                 None
             };
+
+            if is_clicked {
+                let text_bounds = layout.children().next().unwrap().bounds();
+                let position = find_cursor_position(
+                    renderer,
+                    text_bounds,
+                    font.clone(),
+                    size,
+                    value,
+                    state,
+                    cursor_position.x - text_bounds.x,
+                )
+                .unwrap_or(0);
+
+                let click = mouse::Click::new(cursor_position, state.last_click);
+
+                match click.kind() {
+                    mouse::click::Kind::Single => {}
+                    mouse::click::Kind::Double => state.cursor.select_digits(value, position),
+                    mouse::click::Kind::Triple => state.cursor.select_all(value),
+                }
+
+                state.last_click = Some(click);
+            }
         }
-        Event::Keyboard(keyboard::Event::KeyPressed { key_code, .. }) => {
+        Event::Keyboard(keyboard::Event::KeyPressed {
+            key_code,
+            modifiers,
+        }) => {
             let state = state();
 
             if let Some(focus) = &mut state.is_focused {
-                let Some(_) = on_input else { return event::Status::Ignored };
+                let Some(on_input) = on_input else {
+                    return event::Status::Ignored;
+                };
 
                 focus.updated_at = Instant::now();
 
                 match key_code {
+                    keyboard::KeyCode::V if modifiers.command() => {
+                        // Only numbers are pasted, once per key press
+                        if state.is_pasting.is_none() {
+                            let pasted = clipboard
+                                .read()
+                                .map(|content| content.trim().to_string())
+                                .filter(|content| {
+                                    f64::from_str(content).map_or(false, |n| n.is_finite())
+                                });
+
+                            if let Some(pasted) = pasted {
+                                let pasted = Value::new(&pasted);
+                                let position = match state.cursor.state(value) {
+                                    cursor::State::Index(index) => index,
+                                    cursor::State::Selection { start, end } => {
+                                        let (start, end) = (start.min(end), start.max(end));
+                                        value.remove_many(start, end);
+                                        start
+                                    }
+                                };
+                                let end = position + pasted.len();
+
+                                value.insert_many(position, pasted.clone());
+                                state.cursor.select_range(end, end);
+                                state.is_pasting = Some(pasted);
+                                state.is_invalid = false;
+
+                                let message = on_paste.unwrap_or(on_input);
+                                shell.publish(message(value.to_string()));
+                            }
+                        }
+                    }
+                    keyboard::KeyCode::Backspace | keyboard::KeyCode::Delete => {
+                        // A selection is removed whole, otherwise the grapheme
+                        // on the side of the key
+                        let removed = match state.cursor.selection(value) {
+                            Some((start, end)) => {
+                                value.remove_many(start, end);
+                                Some(start)
+                            }
+                            None => {
+                                let index = match state.cursor.state(value) {
+                                    cursor::State::Index(index) => index,
+                                    cursor::State::Selection { start, .. } => start,
+                                };
+
+                                if key_code == keyboard::KeyCode::Backspace && index > 0 {
+                                    value.remove(index - 1);
+                                    Some(index - 1)
+                                } else if key_code == keyboard::KeyCode::Delete
+                                    && index < value.len()
+                                {
+                                    value.remove(index);
+                                    Some(index)
+                                } else {
+                                    None
+                                }
+                            }
+                        };
+
+                        if let Some(index) = removed {
+                            state.cursor.select_range(index, index);
+                            state.is_invalid = false;
+                            shell.publish(on_input(value.to_string()));
+                        }
+                    }
+                    keyboard::KeyCode::A if state.keyboard_modifiers.command() => {
+                        state.cursor.select_all(value);
+                    }
                     keyboard::KeyCode::Enter | keyboard::KeyCode::NumpadEnter => {
                         if let Some(on_submit) = on_submit.clone() {
                             shell.publish(on_submit);
                         }
                     }
+                    keyboard::KeyCode::Left if state.keyboard_modifiers.jump() => {
+                        state.cursor.move_word_left(value)
+                    }
+                    keyboard::KeyCode::Right if state.keyboard_modifiers.jump() => {
+                        state.cursor.move_word_right(value)
+                    }
+                    keyboard::KeyCode::Home | keyboard::KeyCode::End => {
+                        let target = if key_code == keyboard::KeyCode::Home {
+                            0
+                        } else {
+                            value.len()
+                        };
+
+                        // Shift extends the selection from where it started
+                        if state.keyboard_modifiers.shift() {
+                            let anchor = match state.cursor.state(value) {
+                                cursor::State::Index(index) => index,
+                                cursor::State::Selection { start, .. } => start,
+                            };
+                            state.cursor.select_range(anchor, target);
+                        } else {
+                            state.cursor.move_to(target);
+                        }
+                    }
                     keyboard::KeyCode::Left => state.cursor.select_left(value),
                     keyboard::KeyCode::Right => state.cursor.select_right(value),
                     keyboard::KeyCode::Escape => {
                         state.is_focused = None;
                         state.is_dragging = false;
                         state.is_pasting = None;
+                        state.is_invalid = false;
 
                         state.keyboard_modifiers = keyboard::Modifiers::default();
                     }
@@ -524,12 +671,40 @@ where
                 return event::Status::Captured;
             }
         }
+        // The input takes digits only: a decimal point, sign or exponent is
+        // flagged like any other character. Signs are flipped and absolute
+        // values typed by the spin box around it, before reaching the input.
+        Event::Keyboard(keyboard::Event::CharacterReceived(c)) => {
+            let state = state();
+
+            if state.is_focused.is_some() && on_input.is_some() && !c.is_control() {
+                state.is_invalid = !c.is_numeric();
+            }
+        }
+        Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) => {
+            let state = state();
+
+            if state.is_focused.is_some() {
+                state.keyboard_modifiers = modifiers;
+            }
+        }
+        Event::Keyboard(keyboard::Event::KeyReleased {
+            key_code: keyboard::KeyCode::V,
+            ..
+        }) => {
+            state().is_pasting = None;
+        }
         Event::Window(window::Event::RedrawRequested(now)) => {
             let state = state();
 
             if let Some(focus) = &mut state.is_focused {
                 focus.now = now;
 
+                // A solid cursor needs no further redraws
+                if motion::is_reduced() {
+                    return event::Status::Ignored;
+                }
+
                 let millis_until_redraw = CURSOR_BLINK_INTERVAL_MILLIS
                     - (now - focus.updated_at).as_millis() % CURSOR_BLINK_INTERVAL_MILLIS;
 
@@ -561,6 +736,7 @@ pub fn draw<Renderer>(
     is_disabled: bool,
     is_secure: bool,
     icon: Option<&Icon<Renderer::Font>>,
+    error_style: &<Renderer::Theme as StyleSheet>::Style,
     style: &<Renderer::Theme as StyleSheet>::Style,
 ) where
     Renderer: text::Renderer,
@@ -575,16 +751,7 @@ pub fn draw<Renderer>(
     let text_bounds = children_layout.next().unwrap().bounds();
 
     let is_mouse_over = bounds.contains(cursor_position);
-
-    let appearance = if is_disabled {
-        theme.disabled(style)
-    } else if state.is_focused() {
-        theme.focused(style)
-    } else if is_mouse_over {
-        theme.hovered(style)
-    } else {
-        theme.active(style)
-    };
+    let appearance = appearance(theme, state, is_disabled, is_mouse_over, style, error_style);
 
     renderer.fill_quad(
         renderer::Quad {
@@ -625,8 +792,9 @@ pub fn draw<Renderer>(
                     font.clone(),
                 );
 
-                let is_cursor_visible =
-                    ((focus.now - focus.updated_at).as_millis() / CURSOR_BLINK_INTERVAL_MILLIS) % 2
+                let is_cursor_visible = motion::is_reduced()
+                    || ((focus.now - focus.updated_at).as_millis() / CURSOR_BLINK_INTERVAL_MILLIS)
+                        % 2
                         == 0;
 
                 let cursor = if is_cursor_visible {
@@ -742,6 +910,31 @@ pub fn draw<Renderer>(
     }
 }
 
+/// Picks the [`Appearance`] of a [`ScientificTextInput`] in `state`. A rejected
+/// character shows in `error_style` until the next edit, unless disabled.
+///
+/// [`Appearance`]: crate::style::scientific_text_input::Appearance
+fn appearance<Theme: StyleSheet>(
+    theme: &Theme,
+    state: &State,
+    is_disabled: bool,
+    is_mouse_over: bool,
+    style: &Theme::Style,
+    error_style: &Theme::Style,
+) -> crate::style::scientific_text_input::Appearance {
+    if is_disabled {
+        theme.disabled(style)
+    } else if !state.is_valid() {
+        theme.error(error_style)
+    } else if state.is_focused() {
+        theme.focused(style)
+    } else if is_mouse_over {
+        theme.hovered(style)
+    } else {
+        theme.active(style)
+    }
+}
+
 /// Computes the current [`mouse::Interaction`] of the [`ScientificTextInput`].
 pub fn mouse_interaction(
     layout: Layout<'_>,
@@ -768,6 +961,9 @@ pub struct State {
     last_click: Option<mouse::Click>,
     cursor: Cursor,
     keyboard_modifiers: keyboard::Modifiers,
+    /// Set when the last character typed was rejected, until the next edit or
+    /// the input loses focus.
+    is_invalid: bool,
     // TODO: Add stateful horizontal scrolling offset
 }
 
@@ -788,6 +984,16 @@ impl State {
         self.is_focused.is_some()
     }
 
+    /// Returns whether the last character typed was accepted. Only digits are.
+    pub fn is_valid(&self) -> bool {
+        !self.is_invalid
+    }
+
+    /// Marks the last character typed as accepted or rejected.
+    pub fn set_valid(&mut self, is_valid: bool) {
+        self.is_invalid = !is_valid;
+    }
+
     /// Returns the [`Cursor`] of the [`ScientificTextInput`].
     pub fn cursor(&self) -> Cursor {
         self.cursor
@@ -797,6 +1003,11 @@ impl State {
         self.cursor.select_left(value)
     }
 
+    /// Moves the cursor to `index`, dropping any selection.
+    pub fn move_cursor_to(&mut self, index: usize) {
+        self.cursor.move_to(index)
+    }
+
     pub fn select_right(&mut self, value: &Value) {
         self.cursor.select_right(value)
     }
@@ -814,6 +1025,7 @@ impl State {
     /// Unfocuses the [`ScientificTextInput`].
     pub fn unfocus(&mut self) {
         self.is_focused = None;
+        self.is_invalid = false;
     }
 }
 
@@ -831,6 +1043,55 @@ impl operation::Focusable for State {
     }
 }
 
+/// Returns the grapheme index under `x`, measured from the left of the text
+/// bounds, accounting for the scroll offset of a focused input.
+fn find_cursor_position<Renderer>(
+    renderer: &Renderer,
+    text_bounds: Rectangle,
+    font: Renderer::Font,
+    size: Option<f32>,
+    value: &Value,
+    state: &State,
+    x: f32,
+) -> Option<usize>
+where
+    Renderer: text::Renderer,
+{
+    let size = size.unwrap_or_else(|| renderer.default_size());
+
+    let offset = if state.is_focused() {
+        let end = match state.cursor.state(value) {
+            cursor::State::Index(index) => index,
+            cursor::State::Selection { end, .. } => end,
+        };
+
+        measure_cursor_and_scroll_offset(renderer, text_bounds, value, size, end, font.clone()).1
+    } else {
+        0.0
+    };
+
+    let value = value.to_string();
+
+    let char_offset = renderer
+        .hit_test(
+            &value,
+            size,
+            font,
+            Size::INFINITY,
+            Point::new(x + offset, text_bounds.height / 2.0),
+            true,
+        )
+        .map(text::Hit::cursor)?;
+
+    Some(
+        unicode_segmentation::UnicodeSegmentation::graphemes(
+            &value[..char_offset.min(value.len())],
+            true,
+        )
+        .count(),
+    )
+}
+
 fn measure_cursor_and_scroll_offset<Renderer>(
     renderer: &Renderer,
     text_bounds: Rectangle,
@@ -852,3 +1113,258 @@ where
 }
 
 const CURSOR_BLINK_INTERVAL_MILLIS: u128 = 500;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iced::Theme;
+
+    /// Sends `event` to a text input holding `value` in `state`, returning the
+    /// values it published.
+    fn send(state: &mut State, value: &mut Value, event: Event) -> Vec<String> {
+        send_with(state, value, &mut iced_native::clipboard::Null, event)
+    }
+
+    /// Like [`send`], with `clipboard` to paste from.
+    fn send_with(
+        state: &mut State,
+        value: &mut Value,
+        clipboard: &mut dyn Clipboard,
+        event: Event,
+    ) -> Vec<String> {
+        let text = layout::Node::new(Size::new(190.0, 20.0));
+        let node = layout::Node::with_children(Size::new(200.0, 30.0), vec![text]);
+        let on_input = |value: String| value;
+        let mut messages = vec![];
+
+        update(
+            event,
+            Layout::new(&node),
+            Point::ORIGIN,
+            &renderer::Null::new(),
+            clipboard,
+            &mut Shell::new(&mut messages),
+            value,
+            None,
+            &Default::default(),
+            Some(&on_input),
+            None,
+            &None,
+            || state,
+        );
+        messages
+    }
+
+    /// A clipboard holding fixed contents.
+    struct StubClipboard(Option<String>);
+
+    impl Clipboard for StubClipboard {
+        fn read(&self) -> Option<String> {
+            self.0.clone()
+        }
+
+        fn write(&mut self, contents: String) {
+            self.0 = Some(contents);
+        }
+    }
+
+    /// Pastes `contents` into `value` with the cursor state in `state`.
+    fn paste(state: &mut State, value: &mut Value, contents: &str) -> Vec<String> {
+        let event = Event::Keyboard(keyboard::Event::KeyPressed {
+            key_code: keyboard::KeyCode::V,
+            modifiers: keyboard::Modifiers::COMMAND,
+        });
+        let mut clipboard = StubClipboard(Some(String::from(contents)));
+        send_with(state, value, &mut clipboard, event)
+    }
+
+    /// Where a cursor without a selection is.
+    fn cursor_index(state: &State, value: &Value) -> Option<usize> {
+        match state.cursor().state(value) {
+            cursor::State::Index(index) => Some(index),
+            cursor::State::Selection { .. } => None,
+        }
+    }
+
+    fn pressed(key_code: keyboard::KeyCode) -> Event {
+        Event::Keyboard(keyboard::Event::KeyPressed {
+            key_code,
+            modifiers: keyboard::Modifiers::default(),
+        })
+    }
+
+    fn typed(c: char) -> Event {
+        Event::Keyboard(keyboard::Event::CharacterReceived(c))
+    }
+
+    #[test]
+    fn non_digit_is_flagged_until_a_digit_is_typed() {
+        let (mut state, mut value) = (State::new(), Value::new("12"));
+        state.focus();
+
+        send(&mut state, &mut value, typed('x'));
+        assert!(!state.is_valid());
+
+        send(&mut state, &mut value, typed('3'));
+        assert!(state.is_valid());
+
+        for c in ['.', 'e', '-'] {
+            send(&mut state, &mut value, typed(c));
+            assert!(!state.is_valid(), "{c:?} is not a digit");
+        }
+    }
+
+    #[test]
+    fn rejected_character_shows_the_error_appearance() {
+        let theme = Theme::Light;
+        let style = Default::default();
+        let mut state = State::new();
+        state.focus();
+
+        let focused = appearance(&theme, &state, false, false, &style, &style);
+        assert_eq!(focused.border_color, theme.focused(&style).border_color);
+
+        state.set_valid(false);
+        let rejected = appearance(&theme, &state, false, false, &style, &style);
+        assert_eq!(rejected.border_color, theme.error(&style).border_color);
+        assert_ne!(rejected.border_color, focused.border_color);
+
+        let disabled = appearance(&theme, &state, true, false, &style, &style);
+        assert_eq!(disabled.border_color, theme.disabled(&style).border_color);
+    }
+
+    #[test]
+    fn pasted_number_is_inserted_at_the_cursor() {
+        let (mut state, mut value) = (State::new(), Value::new(""));
+        state.focus();
+
+        assert_eq!(paste(&mut state, &mut value, " 2.5\n"), ["2.5"]);
+        assert_eq!(value.to_string(), "2.5");
+        assert!(matches!(
+            state.cursor().state(&value),
+            cursor::State::Index(3)
+        ));
+    }
+
+    #[test]
+    fn pasted_text_that_is_not_a_number_is_dropped() {
+        let (mut state, mut value) = (State::new(), Value::new("12"));
+        state.focus();
+
+        assert!(paste(&mut state, &mut value, "abc").is_empty());
+        assert!(paste(&mut state, &mut value, "inf").is_empty());
+        assert_eq!(value.to_string(), "12");
+    }
+
+    #[test]
+    fn pasted_number_replaces_the_selection() {
+        let (mut state, mut value) = (State::new(), Value::new("12.5"));
+        state.focus();
+        state.cursor.select_range(0, 2);
+
+        assert_eq!(paste(&mut state, &mut value, "7"), ["7.5"]);
+        assert_eq!(value.to_string(), "7.5");
+    }
+
+    #[test]
+    fn backspace_and_delete_remove_the_grapheme_beside_the_cursor() {
+        let (mut state, mut value) = (State::new(), Value::new("12.5"));
+        state.focus();
+        state.move_cursor_to(2);
+
+        let backspace = pressed(keyboard::KeyCode::Backspace);
+        let removed = send(&mut state, &mut value, backspace);
+        assert_eq!(removed, ["1.5"]);
+        assert_eq!(cursor_index(&state, &value), Some(1));
+
+        let delete = pressed(keyboard::KeyCode::Delete);
+        let removed = send(&mut state, &mut value, delete);
+        assert_eq!(removed, ["15"]);
+        assert_eq!(cursor_index(&state, &value), Some(1));
+    }
+
+    #[test]
+    fn backspace_at_the_start_and_delete_at_the_end_do_nothing() {
+        let (mut state, mut value) = (State::new(), Value::new("12.5"));
+        state.focus();
+
+        let backspace = pressed(keyboard::KeyCode::Backspace);
+        state.move_cursor_to(0);
+        assert!(send(&mut state, &mut value, backspace).is_empty());
+
+        let delete = pressed(keyboard::KeyCode::Delete);
+        state.move_cursor_to(4);
+        assert!(send(&mut state, &mut value, delete).is_empty());
+        assert_eq!(value.to_string(), "12.5");
+    }
+
+    #[test]
+    fn backspace_removes_a_whole_selection() {
+        let (mut state, mut value) = (State::new(), Value::new("12.5"));
+        state.focus();
+        state.cursor.select_range(1, 3);
+
+        let backspace = pressed(keyboard::KeyCode::Backspace);
+        let removed = send(&mut state, &mut value, backspace);
+        assert_eq!(removed, ["15"]);
+        assert_eq!(cursor_index(&state, &value), Some(1));
+    }
+
+    #[test]
+    fn command_a_selects_the_whole_value() {
+        let (mut state, mut value) = (State::new(), Value::new("12.5"));
+        state.focus();
+        let command = keyboard::Event::ModifiersChanged(keyboard::Modifiers::COMMAND);
+        send(&mut state, &mut value, Event::Keyboard(command));
+
+        let select_all = Event::Keyboard(keyboard::Event::KeyPressed {
+            key_code: keyboard::KeyCode::A,
+            modifiers: keyboard::Modifiers::COMMAND,
+        });
+
+        assert!(send(&mut state, &mut value, select_all).is_empty());
+        assert_eq!(state.cursor().selection(&value), Some((0, 4)));
+    }
+
+    #[test]
+    fn triple_click_selects_the_whole_value() {
+        let (mut state, mut value) = (State::new(), Value::new("12.5 nm"));
+        let click = || Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left));
+
+        for _ in 0..3 {
+            send(&mut state, &mut value, click());
+        }
+
+        assert!(state.is_focused());
+        assert_eq!(state.cursor().selection(&value), Some((0, 7)));
+    }
+
+    #[test]
+    fn home_and_end_move_to_either_end_of_the_value() {
+        let (mut state, mut value) = (State::new(), Value::new("12.5"));
+        state.focus();
+        state.move_cursor_to(2);
+
+        send(&mut state, &mut value, pressed(keyboard::KeyCode::Home));
+        assert_eq!(cursor_index(&state, &value), Some(0));
+
+        send(&mut state, &mut value, pressed(keyboard::KeyCode::End));
+        assert_eq!(cursor_index(&state, &value), Some(4));
+    }
+
+    #[test]
+    fn shift_home_and_end_select_from_the_cursor() {
+        let (mut state, mut value) = (State::new(), Value::new("12.5"));
+        state.focus();
+        state.move_cursor_to(2);
+        let shift = keyboard::Event::ModifiersChanged(keyboard::Modifiers::SHIFT);
+        send(&mut state, &mut value, Event::Keyboard(shift));
+
+        send(&mut state, &mut value, pressed(keyboard::KeyCode::Home));
+        assert_eq!(state.cursor().selection(&value), Some((0, 2)));
+
+        // The selection keeps its anchor, now extending the other way
+        send(&mut state, &mut value, pressed(keyboard::KeyCode::End));
+        assert_eq!(state.cursor().selection(&value), Some((2, 4)));
+    }
+}
@@ -66,6 +66,82 @@ impl Cursor {
         }
     }
 
+    /// Places the cursor at `index`, dropping any selection.
+    pub(crate) fn move_to(&mut self, index: usize) {
+        self.state = State::Index(index);
+    }
+
+    /// Selects the whole [`Value`].
+    pub(crate) fn select_all(&mut self, value: &Value) {
+        self.select_range(0, value.len());
+    }
+
+    /// Selects the run of digits around the grapheme `index`, stopping at
+    /// anything else such as a decimal point or a unit.
+    pub(crate) fn select_digits(&mut self, value: &Value, index: usize) {
+        let is_digit = |i: usize| {
+            value.graphemes[i]
+                .chars()
+                .next()
+                .map_or(false, char::is_numeric)
+        };
+        let index = index.min(value.len());
+
+        let start = (0..index)
+            .rev()
+            .take_while(|&i| is_digit(i))
+            .last()
+            .unwrap_or(index);
+        let end = (index..value.len())
+            .take_while(|&i| is_digit(i))
+            .last()
+            .map_or(index, |i| i + 1);
+
+        self.select_range(start, end)
+    }
+
+    /// Moves to the start of the word before the cursor. Words are split at
+    /// spaces and decimal points, so in `"123.45 nm"` the cursor stops at 0,
+    /// 4 and 7.
+    pub(crate) fn move_word_left(&mut self, value: &Value) {
+        let index = self.position(value);
+        let word = |i: usize| !is_word_boundary(value, i);
+
+        let end = (0..index).rev().find(|&i| word(i)).map_or(0, |i| i + 1);
+        let start = (0..end)
+            .rev()
+            .take_while(|&i| word(i))
+            .last()
+            .unwrap_or(end);
+
+        self.state = State::Index(start);
+    }
+
+    /// Moves to the end of the word after the cursor. Words are split at
+    /// spaces and decimal points, so in `"123.45 nm"` the cursor stops at 3,
+    /// 6 and 9.
+    pub(crate) fn move_word_right(&mut self, value: &Value) {
+        let index = self.position(value);
+        let word = |i: usize| !is_word_boundary(value, i);
+
+        let start = (index..value.len())
+            .find(|&i| word(i))
+            .unwrap_or(value.len());
+        let end = (start..value.len())
+            .find(|&i| !word(i))
+            .unwrap_or(value.len());
+
+        self.state = State::Index(end);
+    }
+
+    /// Start of the selection, or the index of a plain cursor.
+    fn position(&self, value: &Value) -> usize {
+        match self.state(value) {
+            State::Index(index) => index,
+            State::Selection { start, end } => start.min(end),
+        }
+    }
+
     pub(crate) fn select_left(&mut self, value: &Value) {
         match self.state(value) {
             State::Index(index) if index > 0 => {
@@ -121,3 +197,76 @@ impl Cursor {
         }
     }
 }
+
+/// Whether the grapheme at `index` separates words: a space or a decimal point.
+fn is_word_boundary(value: &Value, index: usize) -> bool {
+    matches!(value.graphemes[index].as_str(), " " | ".")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index(cursor: &Cursor, value: &Value) -> usize {
+        match cursor.state(value) {
+            State::Index(index) => index,
+            State::Selection { .. } => panic!("expected a plain cursor"),
+        }
+    }
+
+    #[test]
+    fn word_left_stops_at_the_start_of_each_word() {
+        let value = Value::new("123.45 nm");
+        let mut cursor = Cursor::default();
+        cursor.move_to(value.len());
+
+        let mut stops = vec![];
+        for _ in 0..4 {
+            cursor.move_word_left(&value);
+            stops.push(index(&cursor, &value));
+        }
+        assert_eq!(stops, [7, 4, 0, 0]);
+    }
+
+    #[test]
+    fn word_right_stops_at_the_end_of_each_word() {
+        let value = Value::new("123.45 nm");
+        let mut cursor = Cursor::default();
+
+        let mut stops = vec![];
+        for _ in 0..4 {
+            cursor.move_word_right(&value);
+            stops.push(index(&cursor, &value));
+        }
+        assert_eq!(stops, [3, 6, 9, 9]);
+    }
+
+    #[test]
+    fn select_all_covers_every_grapheme() {
+        let mut cursor = Cursor::default();
+
+        let empty = Value::new("");
+        cursor.select_all(&empty);
+        assert_eq!(cursor.selection(&empty), None);
+        assert_eq!(index(&cursor, &empty), 0);
+
+        let value = Value::new("5 \u{b5}m");
+        cursor.select_all(&value);
+        assert_eq!(cursor.selection(&value), Some((0, 4)));
+    }
+
+    #[test]
+    fn double_click_selects_the_run_of_digits_clicked() {
+        let value = Value::new("12.5 nm");
+        let mut cursor = Cursor::default();
+
+        cursor.select_digits(&value, 1);
+        assert_eq!(cursor.selection(&value), Some((0, 2)));
+
+        cursor.select_digits(&value, 3);
+        assert_eq!(cursor.selection(&value), Some((3, 4)));
+
+        cursor.select_digits(&value, 6);
+        assert_eq!(cursor.selection(&value), None);
+    }
+}
@@ -4,21 +4,36 @@ use iced_native::{
     event, keyboard,
     layout::{Limits, Node},
     mouse,
+    time::{Duration, Instant},
     widget::{
         container, text,
         tree::{self, Tree},
         Column, Container, Operation, Row, Text,
     },
-    Alignment, Clipboard, Element, Event, Layout, Length, Padding, Point, Rectangle, Shell, Size,
-    Widget,
+    window, Alignment, Clipboard, Color, Element, Event, Layout, Length, Padding, Point, Rectangle,
+    Shell, Size, Widget,
 };
 
 use num_traits::clamp;
+use std::cmp::Ordering;
+use std::ops::{Add, Div, Mul, Sub};
 use std::str::FromStr;
 
 use crate::style::scientificspinbox;
 
 const DEFAULT_PADDING: f32 = 5.0;
+/// Opacity of a modifier button that cannot change the value.
+const DISABLED_ALPHA: f32 = 0.3;
+/// Label of the button that sets a spin box back to its default.
+const RESET_ICON: char = '↺';
+/// How long a modifier button is held before it starts repeating.
+const REPEAT_DELAY: Duration = Duration::from_millis(400);
+/// Time between repeats of a held modifier button.
+const REPEAT_INTERVAL: Duration = Duration::from_millis(50);
+/// Exponent range kept by [`ExponentialNumber`] conversions, on SI prefixes so
+/// that rounding down to one never leaves `i8`.
+const MIN_EXPONENT: i8 = -126;
+const MAX_EXPONENT: i8 = 126;
 
 #[derive(Debug, Clone, Copy)]
 pub struct ExponentialNumber {
@@ -37,6 +52,265 @@ impl ExponentialNumber {
     pub fn to_f64(&self) -> f64 {
         self.significand * 10_f64.powf(self.exponent as f64)
     }
+
+    /// Splits `value` into a significand in `[1, 10)` and a power of ten, as
+    /// `{:e}` formatting would. Zero and non-finite values keep exponent `0`.
+    /// Powers of ten beyond `±126` stay at the limit, with the significand
+    /// outside `[1, 10)` carrying the rest.
+    pub fn from_scientific(value: f64) -> Self {
+        if value == 0.0 || !value.is_finite() {
+            return Self::new(value, 0);
+        }
+
+        let mut exponent = value.abs().log10().floor() as i32;
+        let mut significand = value / 10_f64.powi(exponent);
+
+        // log10 can land one off right at a power of ten
+        if significand.abs() >= 10.0 {
+            significand /= 10.0;
+            exponent += 1;
+        } else if significand.abs() < 1.0 {
+            significand *= 10.0;
+            exponent -= 1;
+        }
+
+        let clamped = exponent.clamp(MIN_EXPONENT as i32, MAX_EXPONENT as i32);
+        if clamped != exponent {
+            significand = value / 10_f64.powi(clamped);
+        }
+        Self::new(significand, clamped as i8)
+    }
+
+    /// The same value with its exponent lowered to a multiple of three, the
+    /// nearest SI prefix, and the significand scaled to match: `4.7e-8` becomes
+    /// `47e-9`.
+    pub fn on_si_prefix(self) -> Self {
+        let shift = self.exponent.rem_euclid(3);
+
+        Self::new(
+            self.significand * 10_f64.powi(shift as i32),
+            self.exponent - shift,
+        )
+    }
+
+    /// The same value with its exponent a multiple of three and, unless it is
+    /// zero or not finite, a significand magnitude in `[1, 1000)`. The exponent
+    /// does not move past `±126`, so magnitudes beyond `1e±126` keep a larger
+    /// or smaller significand instead.
+    pub fn normalized(self) -> Self {
+        let Self {
+            significand: mut sig,
+            exponent: mut exp,
+        } = self.on_si_prefix();
+
+        // Zero has no sign, so -0 does not show up as "-0" while editing
+        if sig == 0.0 {
+            return Self::new(0.0, exp);
+        }
+        if !sig.is_finite() {
+            return Self::new(sig, exp);
+        }
+
+        while sig.abs() >= 1000.0 && exp <= MAX_EXPONENT - 3 {
+            sig /= 1000.0;
+            exp += 3;
+        }
+        while sig.abs() < 1.0 && exp >= MIN_EXPONENT + 3 {
+            sig *= 1000.0;
+            exp -= 3;
+        }
+
+        Self::new(sig, exp)
+    }
+
+    /// Whether the significand is normalized: zero, or a finite magnitude in
+    /// `[1, 1000)`.
+    fn is_normal(&self) -> bool {
+        self.significand == 0.0 || (1.0..1000.0).contains(&self.significand.abs())
+    }
+
+    /// The value with its sign flipped and the exponent kept. Zero stays
+    /// positive.
+    pub fn negated(self) -> Self {
+        if self.significand == 0.0 {
+            return Self::new(0.0, self.exponent);
+        }
+
+        Self::new(-self.significand, self.exponent)
+    }
+
+    /// Formats the value like [`Display`](std::fmt::Display), followed by
+    /// `unit`, e.g. `"50.000 nm"` or `"1.000 V"`.
+    pub fn with_unit(&self, unit: &str) -> String {
+        if get_prefix_from_exponent(self.on_si_prefix().exponent)
+            .trim()
+            .is_empty()
+        {
+            format!("{self} {unit}")
+        } else {
+            format!("{self}{unit}")
+        }
+    }
+
+    /// Significand of `self` rewritten for `exponent`.
+    fn significand_at(&self, exponent: i8) -> f64 {
+        self.significand * 10_f64.powi(self.exponent as i32 - exponent as i32)
+    }
+}
+
+/// Writes the significand and SI prefix, e.g. `"-2.100 µ"`, or no prefix for
+/// an exponent of zero. Other exponents are shown on the nearest SI prefix,
+/// so `4.7e-8` reads `"47.000 n"`. The precision defaults to three decimals
+/// and can be set with the format flag: `format!("{:.1}", n)`.
+impl std::fmt::Display for ExponentialNumber {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = self.on_si_prefix();
+        let precision = f.precision().unwrap_or(3);
+        let sign = if value.significand < 0.0 { "-" } else { "" };
+        write!(f, "{sign}{:.*}", precision, value.significand.abs())?;
+
+        let prefix = get_prefix_from_exponent(value.exponent);
+        if !prefix.trim().is_empty() {
+            write!(f, " {prefix}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Why a string could not be read as an [`ExponentialNumber`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseExponentialError {
+    Empty,
+    InvalidNumber(String),
+    UnknownPrefix(String),
+    /// The magnitude is beyond `1e±126`, or not finite.
+    OutOfRange(String),
+}
+
+impl std::fmt::Display for ParseExponentialError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseExponentialError::Empty => write!(f, "no number given"),
+            ParseExponentialError::InvalidNumber(s) => write!(f, "\"{s}\" is not a number"),
+            ParseExponentialError::UnknownPrefix(s) => write!(f, "unknown SI prefix \"{s}\""),
+            ParseExponentialError::OutOfRange(s) => write!(f, "\"{s}\" is out of range"),
+        }
+    }
+}
+
+impl std::error::Error for ParseExponentialError {}
+
+/// Reads a number with an optional SI prefix and unit, e.g. `"50n"`,
+/// `"2.1 µ"`, `"-3.3m"`, `"50 nm"` or `"1.5e-9"`.
+///
+/// The first letter after the number is always taken as the prefix (`u` is
+/// accepted for micro), and anything after it as the unit, which is ignored.
+/// A unit without a prefix is therefore rejected: `"5 m"` is 5 milli. So are
+/// magnitudes too large or small to normalize, like `"1e300"`.
+impl FromStr for ExponentialNumber {
+    type Err = ParseExponentialError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(ParseExponentialError::Empty);
+        }
+
+        let split = s
+            .find(|c: char| !(c.is_ascii_digit() || "+-.eE".contains(c)))
+            .unwrap_or(s.len());
+        let (number, suffix) = s.split_at(split);
+        let significand = f64::from_str(number)
+            .map_err(|_| ParseExponentialError::InvalidNumber(s.to_string()))?;
+
+        let suffix = suffix.trim_start();
+        let exponent = match suffix.chars().next() {
+            None => 0,
+            Some(prefix) => exponent_from_prefix(prefix)
+                .filter(|_| suffix[prefix.len_utf8()..].chars().all(char::is_alphabetic))
+                .ok_or_else(|| ParseExponentialError::UnknownPrefix(suffix.to_string()))?,
+        };
+
+        Some(Self::new(significand, exponent).normalized())
+            .filter(ExponentialNumber::is_normal)
+            .ok_or_else(|| ParseExponentialError::OutOfRange(s.to_string()))
+    }
+}
+
+// Values compare by magnitude, so `1.0e-6 == 1000.0e-9`. Significands are
+// compared at a common exponent and count as equal within one ULP, absorbing
+// the rounding of that rescale. NaN sorts above every other value.
+
+impl PartialEq for ExponentialNumber {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for ExponentialNumber {}
+
+impl PartialOrd for ExponentialNumber {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ExponentialNumber {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let exp = self.exponent.max(other.exponent);
+        let (a, b) = (self.significand_at(exp), other.significand_at(exp));
+
+        if (a - b).abs() <= f64::EPSILON * a.abs().max(b.abs()) {
+            Ordering::Equal
+        } else {
+            a.total_cmp(&b)
+        }
+    }
+}
+
+// Arithmetic keeps the operands' exponents rather than going through `f64`,
+// then normalizes the result.
+
+impl Add for ExponentialNumber {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        let exp = self.exponent.max(rhs.exponent);
+        Self::new(self.significand_at(exp) + rhs.significand_at(exp), exp).normalized()
+    }
+}
+
+impl Sub for ExponentialNumber {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        let exp = self.exponent.max(rhs.exponent);
+        Self::new(self.significand_at(exp) - rhs.significand_at(exp), exp).normalized()
+    }
+}
+
+impl Mul for ExponentialNumber {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Self::new(
+            self.significand * rhs.significand,
+            self.exponent + rhs.exponent,
+        )
+        .normalized()
+    }
+}
+
+impl Div for ExponentialNumber {
+    type Output = Self;
+
+    fn div(self, rhs: Self) -> Self {
+        Self::new(
+            self.significand / rhs.significand,
+            self.exponent - rhs.exponent,
+        )
+        .normalized()
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -46,30 +320,22 @@ pub struct Bounds {
 }
 
 impl Bounds {
+    /// Creates bounds from `lower` to `upper`, swapping them if given in the
+    /// wrong order.
     pub fn new(lower: ExponentialNumber, upper: ExponentialNumber) -> Self {
-        Self { lower, upper }
+        Self {
+            lower: lower.min(upper),
+            upper: lower.max(upper),
+        }
     }
 
+    /// Creates bounds from plain numbers, each stored in scientific notation
+    /// (`-1e-9` becomes `-1 × 10⁻⁹`, zero becomes `0 × 10⁰`).
     pub fn from_f64(lower: f64, upper: f64) -> Self {
-        let low_str = format!("{:.e}", lower);
-        let low_val = low_str
-            .split("e")
-            .collect::<Vec<&str>>()
-            .into_iter()
-            .map(|s| s.parse::<f64>().unwrap())
-            .collect::<Vec<f64>>();
-        let up_str = format!("{:.e}", upper);
-        let up_val = up_str
-            .split("e")
-            .collect::<Vec<&str>>()
-            .into_iter()
-            .map(|s| s.parse::<f64>().unwrap())
-            .collect::<Vec<f64>>();
-
-        Self {
-            lower: ExponentialNumber::new(low_val[0], low_val[1] as i8),
-            upper: ExponentialNumber::new(up_val[0], up_val[1] as i8),
-        }
+        Self::new(
+            ExponentialNumber::from_scientific(lower),
+            ExponentialNumber::from_scientific(upper),
+        )
     }
 
     pub fn clamp(&self, value: &f64) -> f64 {
@@ -96,6 +362,9 @@ where
     value: ExponentialNumber,
     step: f64,
     bounds: Bounds,
+    unit: String,
+    default: Option<ExponentialNumber>,
+    invert_scroll: bool,
     padding: f32,
     size: Option<f32>,
     content: ScientificTextInput<'a, Message, Renderer>,
@@ -117,6 +386,9 @@ where
     where
         F: 'static + Copy + Fn(ExponentialNumber) -> Message,
     {
+        // Editing and stepping work on the digits shown, so the value is
+        // expressed on the prefix it is displayed with
+        let value = value.on_si_prefix();
         let convert_to_num = move |s: String| {
             on_changed(ExponentialNumber {
                 significand: f64::from_str(&s).unwrap_or(if s.is_empty() {
@@ -128,17 +400,15 @@ where
             })
         };
 
-        let prefix = get_prefix_from_exponent(value.exponent);
-        let mut display = format!("{:.3} {prefix}{unit}", value.significand.abs());
-
-        if value.significand < 0.0 {
-            display = "-".to_owned() + display.as_str();
-        }
+        let display = value.with_unit(unit);
 
         Self {
             value,
             step: 1.0,
             bounds,
+            unit: unit.to_string(),
+            default: None,
+            invert_scroll: false,
             padding: DEFAULT_PADDING,
             size: None,
             content: ScientificTextInput::new("", display.as_str())
@@ -247,6 +517,96 @@ where
         self
     }
 
+    /// Shows a reset button that sets the [`NumberInput`] back to `default`.
+    #[must_use]
+    pub fn default_value(mut self, default: ExponentialNumber) -> Self {
+        self.default = Some(default);
+        self
+    }
+
+    /// Makes scrolling up decrease the value instead of increasing it.
+    #[must_use]
+    pub fn invert_scroll(mut self, invert: bool) -> Self {
+        self.invert_scroll = invert;
+        self
+    }
+
+    /// The value with its sign flipped, `None` if that leaves the bounds.
+    fn sign_toggled(&self) -> Option<ExponentialNumber> {
+        let negated = self.value.negated();
+        self.bounds.in_bounds(&negated.to_f64()).then_some(negated)
+    }
+
+    /// The message publishing the default value when `event` is a click on
+    /// the reset button, `over_reset` telling whether the cursor is on it.
+    fn reset_message(&self, event: &Event, over_reset: bool) -> Option<Message> {
+        match event {
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) if over_reset => {
+                self.default.map(&self.on_change)
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether scrolling the wheel by `delta` increases the value: scrolling
+    /// up does, unless the direction was inverted with [`Self::invert_scroll`].
+    fn scroll_increases(&self, delta: mouse::ScrollDelta) -> bool {
+        let scrolled_down = match delta {
+            mouse::ScrollDelta::Lines { y, .. } | mouse::ScrollDelta::Pixels { y, .. } => {
+                y.is_sign_negative()
+            }
+        };
+        scrolled_down == self.invert_scroll
+    }
+
+    /// Reads `text` as an absolute value, with or without the unit of the
+    /// spin box: `"50 s"` or `"50"` in a box of seconds, `"5 ms"` or `"5m"` for
+    /// milliseconds. `None` if it is not a number within the bounds.
+    fn parse_absolute(&self, text: &str) -> Option<ExponentialNumber> {
+        let text = text.trim();
+        let number = text.strip_suffix(self.unit.as_str()).unwrap_or(text);
+
+        ExponentialNumber::from_str(number)
+            .ok()
+            .filter(|value| value.to_f64().is_finite())
+            .filter(|value| (self.bounds.lower..=self.bounds.upper).contains(value))
+    }
+
+    /// Enters the absolute value typed as `entry`, marking `input` invalid
+    /// when [`parse_absolute`](Self::parse_absolute) rejects it. The value to
+    /// change to, if any.
+    fn enter_absolute(&self, entry: &str, input: &mut State) -> Option<ExponentialNumber> {
+        let value = self.parse_absolute(entry);
+        input.set_valid(value.is_some());
+        value
+    }
+
+    /// Labels of the increase and decrease buttons; compact spin boxes lay
+    /// them out side by side.
+    fn modifier_icons(&self) -> (char, char) {
+        if self.padding < DEFAULT_PADDING {
+            ('+', '-')
+        } else {
+            ('▲', '▼')
+        }
+    }
+
+    /// Whether the bounds leave any value to choose. A spin box without one
+    /// takes no focus.
+    fn is_editable(&self) -> bool {
+        self.bounds.lower.to_f64() != self.bounds.upper.to_f64()
+    }
+
+    /// Whether the value is at its lower bound, or there is no range at all.
+    fn is_decrease_disabled(&self) -> bool {
+        self.value.to_f64() <= self.bounds.lower.to_f64() || !self.is_editable()
+    }
+
+    /// Whether the value is at its upper bound, or there is no range at all.
+    fn is_increase_disabled(&self) -> bool {
+        self.value.to_f64() >= self.bounds.upper.to_f64() || !self.is_editable()
+    }
+
     /// Decrease current significand by step of the [`NumberInput`].
     fn decrease_val(&mut self, shell: &mut Shell<Message>, child: &mut Tree, value: &mut Value) {
         let (start, end) = child
@@ -257,7 +617,6 @@ where
             .unwrap_or_else(|| (0, 1));
         let pos = start.min(end) as i32;
         let sig = self.value.significand;
-        let mut exp = self.value.exponent;
 
         if value.graphemes[pos as usize]
             .chars()
@@ -265,44 +624,52 @@ where
             .unwrap()
             .is_numeric()
         {
-            let mut new_sig = sig - get_step(pos, value);
-            if new_sig <= -1000.0 {
-                new_sig = new_sig / 1000.0;
-                exp = exp + 3;
-            } else if new_sig < 1.0 && new_sig > 0.0 && exp - 3 != -12 {
-                new_sig = new_sig * 1000.0;
-                exp = exp - 3;
+            let (new_val, scaled_down) = decrement(self.value, get_step(pos, value), &self.bounds);
 
+            if scaled_down {
                 // Move cursor for selection continuity
-                let new_value = Value::new(new_sig.to_string().as_str());
+                let new_value = Value::new(new_val.significand.to_string().as_str());
                 child.state.downcast_mut::<State>().select_left(&new_value);
                 child.state.downcast_mut::<State>().select_left(&new_value);
             }
 
-            let new_val = ExponentialNumber::new(new_sig, exp);
-
-            if self.bounds.in_bounds(&new_val.to_f64()) {
-                shell.publish((self.on_change)(new_val));
-            } else {
-                shell.publish((self.on_change)(self.bounds.lower));
-            }
+            shell.publish((self.on_change)(new_val));
 
-            if sig >= 0.0 && new_sig < 0.0 {
-                let new_value = Value::new(new_sig.to_string().as_str());
+            if sig >= 0.0 && new_val.significand < 0.0 {
+                let new_value = Value::new(new_val.significand.to_string().as_str());
                 child.state.downcast_mut::<State>().select_right(&new_value);
             }
         } else {
-            let new_exp = exp - 3;
-            let mut new_val = ExponentialNumber::new(sig, new_exp);
+            shell.publish((self.on_change)(prefix_down(self.value, &self.bounds)));
+        }
+    }
 
-            if !self.bounds.in_bounds(&new_val.to_f64()) {
-                new_val = self.bounds.lower;
-            } else if new_exp < -12 {
-                new_val.exponent = -12;
-            }
+    /// Splices `text` into the significand at the cursor, replacing any
+    /// selection. Returns the renormalized result, or `None` if it is not a
+    /// finite number within bounds.
+    fn spliced(&self, cursor: cursor::Cursor, text: &str) -> Option<ExponentialNumber> {
+        let mut new_val = self.value.significand.to_string();
 
-            shell.publish((self.on_change)(new_val));
+        match cursor.state(&Value::new(&new_val)) {
+            cursor::State::Index(idx) => {
+                if self.value.significand == 0.0 {
+                    new_val = text.to_string();
+                } else {
+                    new_val.insert_str(idx.min(new_val.len()), text);
+                }
+            }
+            cursor::State::Selection { start, end } => {
+                let range = start.min(end)..start.max(end).min(new_val.len());
+                new_val.replace_range(range, text);
+            }
         }
+
+        let significand = f64::from_str(&new_val).ok().filter(|val| val.is_finite())?;
+        let candidate = ExponentialNumber::new(significand, self.value.exponent);
+
+        self.bounds
+            .in_bounds(&candidate.to_f64())
+            .then(|| candidate.normalized())
     }
 
     /// Increase current significand by step of the [`NumberInput`].
@@ -315,7 +682,6 @@ where
             .unwrap_or_else(|| (0, 1));
         let pos = start.min(end) as i32;
         let sig = self.value.significand;
-        let mut exp = self.value.exponent;
 
         if value.graphemes[pos as usize]
             .chars()
@@ -323,47 +689,121 @@ where
             .unwrap()
             .is_numeric()
         {
-            let mut new_sig = sig + get_step(pos, value);
-            if new_sig >= 1000.0 {
-                new_sig = new_sig / 1000.0;
-                exp = exp + 3;
-            } else if (-1.0 < new_sig && new_sig < 0.0) | (0.0 < new_sig && new_sig < 1.0) {
-                new_sig = new_sig * 1000.0;
-                exp = exp - 3;
+            let (new_val, scaled_down) = increment(self.value, get_step(pos, value), &self.bounds);
 
+            if scaled_down {
                 // Move cursor for selection continuity
-                let new_value = Value::new(new_sig.to_string().as_str());
+                let new_value = Value::new(new_val.significand.to_string().as_str());
                 child.state.downcast_mut::<State>().select_left(&new_value);
                 child.state.downcast_mut::<State>().select_left(&new_value);
             }
 
-            let new_val = ExponentialNumber::new(new_sig, exp);
-
-            if self.bounds.in_bounds(&new_val.to_f64()) {
-                shell.publish((self.on_change)(new_val));
-            } else {
-                shell.publish((self.on_change)(self.bounds.upper));
-            }
+            shell.publish((self.on_change)(new_val));
 
-            if sig < 0.0 && new_sig >= 0.0 {
-                let new_value = Value::new(new_sig.to_string().as_str());
+            if sig < 0.0 && new_val.significand >= 0.0 {
+                let new_value = Value::new(new_val.significand.to_string().as_str());
                 child.state.downcast_mut::<State>().select_left(&new_value);
             }
         } else {
-            let new_exp = exp + 3;
-            let mut new_val = ExponentialNumber::new(sig, new_exp);
+            shell.publish((self.on_change)(prefix_up(self.value, &self.bounds)));
+        }
+    }
+}
 
-            if !self.bounds.in_bounds(&new_val.to_f64()) {
-                new_val = self.bounds.upper;
-            } else if new_exp > self.bounds.upper.exponent {
-                new_val.exponent = self.bounds.upper.exponent;
-            }
+/// Adds `step` to the significand of `value` and renormalizes the result with
+/// [`ExponentialNumber::normalized`].
+///
+/// Invariants, shared with [`step_down`]:
+/// - a significand reaching `1000` in magnitude moves to the next prefix
+///   (`/ 1000`, exponent `+ 3`), so the displayed digits never exceed three
+///   before the decimal point;
+/// - a nonzero significand whose magnitude drops below `1` moves to the
+///   previous prefix (`* 1000`, exponent `- 3`);
+/// - a significand reaching zero stays on its prefix, and crossing zero only
+///   flips the sign;
+/// - `to_f64()` always changes by `step * 10^exponent`, up to rounding.
+///
+/// The returned flag is set when the prefix moved down, so callers can shift
+/// the cursor to keep the same digit selected.
+pub(crate) fn step_up(value: ExponentialNumber, step: f64) -> (ExponentialNumber, bool) {
+    stepped(value, value.significand + step)
+}
 
-            shell.publish((self.on_change)(new_val));
-        }
+/// Subtracts `step` from the significand of `value` and renormalizes the
+/// result, with the same invariants as [`step_up`].
+pub(crate) fn step_down(value: ExponentialNumber, step: f64) -> (ExponentialNumber, bool) {
+    stepped(value, value.significand - step)
+}
+
+/// `value` with its significand changed to `significand`, normalized, and
+/// whether that moved it to a lower prefix.
+fn stepped(value: ExponentialNumber, significand: f64) -> (ExponentialNumber, bool) {
+    let new_val = ExponentialNumber::new(significand, value.exponent).normalized();
+    (new_val, new_val.exponent < value.exponent)
+}
+
+/// [`step_up`] within `bounds`: a result above them is the upper bound.
+pub(crate) fn increment(
+    value: ExponentialNumber,
+    step: f64,
+    bounds: &Bounds,
+) -> (ExponentialNumber, bool) {
+    let (new_val, scaled_down) = step_up(value, step);
+
+    if bounds.in_bounds(&new_val.to_f64()) {
+        (new_val, scaled_down)
+    } else {
+        (bounds.upper, scaled_down)
     }
 }
 
+/// [`step_down`] within `bounds`: a result below them is the lower bound.
+pub(crate) fn decrement(
+    value: ExponentialNumber,
+    step: f64,
+    bounds: &Bounds,
+) -> (ExponentialNumber, bool) {
+    let (new_val, scaled_down) = step_down(value, step);
+
+    if bounds.in_bounds(&new_val.to_f64()) {
+        (new_val, scaled_down)
+    } else {
+        (bounds.lower, scaled_down)
+    }
+}
+
+/// Moves `value` to the next SI prefix (exponent `+ 3`), keeping the
+/// significand. Out-of-bounds results snap to the upper bound, and the
+/// exponent never exceeds that of the upper bound.
+pub(crate) fn prefix_up(value: ExponentialNumber, bounds: &Bounds) -> ExponentialNumber {
+    let new_exp = value.exponent + 3;
+    let mut new_val = ExponentialNumber::new(value.significand, new_exp);
+
+    if !bounds.in_bounds(&new_val.to_f64()) {
+        new_val = bounds.upper;
+    } else if new_exp > bounds.upper.exponent {
+        new_val.exponent = bounds.upper.exponent;
+    }
+
+    new_val
+}
+
+/// Moves `value` to the previous SI prefix (exponent `- 3`), keeping the
+/// significand. Out-of-bounds results snap to the lower bound, and the
+/// exponent never goes below pico (`-12`).
+pub(crate) fn prefix_down(value: ExponentialNumber, bounds: &Bounds) -> ExponentialNumber {
+    let new_exp = value.exponent - 3;
+    let mut new_val = ExponentialNumber::new(value.significand, new_exp);
+
+    if !bounds.in_bounds(&new_val.to_f64()) {
+        new_val = bounds.lower;
+    } else if new_exp < -12 {
+        new_val.exponent = -12;
+    }
+
+    new_val
+}
+
 impl<'a, Message, Renderer> Widget<Message, Renderer> for ScientificSpinBox<'a, Message, Renderer>
 where
     Message: Clone,
@@ -422,19 +862,22 @@ where
                 .center_y()
                 .center_x()
         };
-        let mut modifier = if self.padding < DEFAULT_PADDING {
-            Row::<(), Renderer>::new()
+        let compact = self.padding < DEFAULT_PADDING;
+        let (inc, dec) = self.modifier_icons();
+        let mut buttons: Vec<Element<(), Renderer>> =
+            vec![btn_mod(inc).into(), btn_mod(dec).into()];
+        if self.default.is_some() {
+            buttons.push(btn_mod(RESET_ICON).into());
+        }
+        let mut modifier = if compact {
+            Row::with_children(buttons)
                 .spacing(1)
                 .width(Length::Shrink)
-                .push(btn_mod('+'))
-                .push(btn_mod('-'))
                 .layout(renderer, &limits.loose())
         } else {
-            Column::<(), Renderer>::new()
+            Column::with_children(buttons)
                 .spacing(1)
                 .width(Length::Shrink)
-                .push(btn_mod('▲'))
-                .push(btn_mod('▼'))
                 .layout(renderer, &limits.loose())
         };
         let intrinsic = Size::new(
@@ -453,6 +896,11 @@ where
         renderer: &Renderer,
         operation: &mut dyn Operation<Message>,
     ) {
+        // A spin box with nothing to choose from is left out of the tab order
+        if !self.is_editable() {
+            return;
+        }
+
         operation.container(None, &mut |operation| {
             self.content.operate(
                 &mut tree.children[0],
@@ -491,6 +939,7 @@ where
             .next()
             .expect("fail to get decreate mod layout")
             .bounds();
+        let reset_bounds = mod_children.next().map(|reset| reset.bounds());
         let mouse_over_inc = inc_bounds.contains(cursor_position);
         let mouse_over_dec = dec_bounds.contains(cursor_position);
         let modifiers = state.state.downcast_mut::<ModifierState>();
@@ -500,6 +949,97 @@ where
             return event::Status::Ignored;
         }
 
+        // Reset works without focusing the input first
+        let over_reset = reset_bounds.map_or(false, |bounds| bounds.contains(cursor_position));
+        if let Some(message) = self.reset_message(&event, over_reset) {
+            shell.publish(message);
+            return event::Status::Captured;
+        }
+
+        // A held modifier button repeats on timed redraws until released,
+        // wherever the cursor is by then
+        match &event {
+            Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
+                modifiers.increase_pressed = false;
+                modifiers.decrease_pressed = false;
+                modifiers.repeat_at = None;
+            }
+            Event::Window(window::Event::RedrawRequested(now)) => {
+                let at_bound = if modifiers.increase_pressed {
+                    self.is_increase_disabled()
+                } else {
+                    self.is_decrease_disabled()
+                };
+
+                if modifiers.repeat(*now, at_bound) {
+                    if modifiers.increase_pressed {
+                        self.increase_val(shell, &mut child, &mut self.content.get_value());
+                    } else if modifiers.decrease_pressed {
+                        self.decrease_val(shell, &mut child, &mut self.content.get_value());
+                    }
+                }
+                if let Some(next) = modifiers.repeat_at {
+                    shell.request_redraw(window::RedrawRequest::At(next));
+                }
+            }
+            _ => {}
+        }
+
+        // A double click on the value starts typing an absolute value over it
+        if let Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) = event {
+            if content.bounds().contains(cursor_position) && !mouse_over_inc && !mouse_over_dec {
+                let click = mouse::Click::new(cursor_position, modifiers.last_click);
+                if click.kind() == mouse::click::Kind::Double {
+                    modifiers.entry = Some(String::new());
+                }
+                modifiers.last_click = Some(click);
+            } else {
+                modifiers.entry = None;
+            }
+        }
+        if !child.state.downcast_ref::<State>().is_focused() {
+            modifiers.entry = None;
+        }
+
+        if let Some(entry) = &mut modifiers.entry {
+            let input = child.state.downcast_mut::<State>();
+
+            match event {
+                Event::Keyboard(keyboard::Event::CharacterReceived(c)) if !c.is_control() => {
+                    entry.push(c);
+                    input.move_cursor_to(Value::new(entry).len());
+                    return event::Status::Captured;
+                }
+                Event::Keyboard(keyboard::Event::KeyPressed {
+                    key_code: keyboard::KeyCode::Backspace,
+                    ..
+                }) => {
+                    entry.pop();
+                    input.move_cursor_to(Value::new(entry).len());
+                    return event::Status::Captured;
+                }
+                Event::Keyboard(keyboard::Event::KeyPressed {
+                    key_code: keyboard::KeyCode::Enter | keyboard::KeyCode::NumpadEnter,
+                    ..
+                }) => {
+                    if let Some(value) = self.enter_absolute(entry, input) {
+                        shell.publish((self.on_change)(value));
+                        modifiers.entry = None;
+                    }
+                    return event::Status::Captured;
+                }
+                Event::Keyboard(keyboard::Event::KeyPressed {
+                    key_code: keyboard::KeyCode::Escape,
+                    ..
+                }) => {
+                    input.set_valid(true);
+                    modifiers.entry = None;
+                    return event::Status::Captured;
+                }
+                _ => {}
+            }
+        }
+
         if child.state.downcast_mut::<State>().is_focused() {
             if mouse_over_inc || mouse_over_dec {
                 let mut event_status = event::Status::Captured;
@@ -514,21 +1054,36 @@ where
                         } else {
                             event_status = event::Status::Ignored;
                         }
-                    }
-                    Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
-                        if mouse_over_dec {
-                            modifiers.decrease_pressed = false;
-                        } else if mouse_over_inc {
-                            modifiers.increase_pressed = false;
-                        } else {
-                            event_status = event::Status::Ignored;
+
+                        if event_status == event::Status::Captured {
+                            let repeat_at = modifiers.hold(Instant::now());
+                            shell.request_redraw(window::RedrawRequest::At(repeat_at));
                         }
                     }
+                    Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {}
                     _ => event_status = event::Status::Ignored,
                 }
                 event_status
             } else {
                 match event {
+                    // Minus flips the sign, keeping the cursor on the same digit
+                    Event::Keyboard(keyboard::Event::CharacterReceived('-')) => {
+                        let toggled = self.sign_toggled();
+                        let state = child.state.downcast_mut::<State>();
+
+                        state.set_valid(toggled.is_some());
+                        let current = self.value.significand;
+                        if let Some(negated) = toggled.filter(|n| n.significand != current) {
+                            let new_value = Value::new(negated.significand.to_string().as_str());
+                            if negated.significand < 0.0 {
+                                state.select_right(&new_value);
+                            } else {
+                                state.select_left(&new_value);
+                            }
+                            shell.publish((self.on_change)(negated));
+                        }
+                        event::Status::Captured
+                    }
                     Event::Keyboard(keyboard::Event::CharacterReceived(c)) if c.is_numeric() => {
                         let mut new_val = self.value.significand.to_string();
                         match child
@@ -573,12 +1128,36 @@ where
                                         shell,
                                     )
                                 } else {
+                                    child.state.downcast_mut::<State>().set_valid(false);
                                     event::Status::Ignored
                                 }
                             }
-                            Err(_) => event::Status::Ignored,
+                            Err(_) => {
+                                child.state.downcast_mut::<State>().set_valid(false);
+                                event::Status::Ignored
+                            }
                         }
                     }
+                    // Pastes are validated like typed digits instead of reaching
+                    // the text input raw; anything else on the clipboard is dropped
+                    Event::Keyboard(keyboard::Event::KeyPressed {
+                        key_code: keyboard::KeyCode::V,
+                        modifiers,
+                    }) if modifiers.command() => {
+                        if let Some(pasted) = clipboard.read() {
+                            let cursor = child.state.downcast_ref::<State>().cursor();
+                            let new_val = self.spliced(cursor, pasted.trim());
+
+                            child
+                                .state
+                                .downcast_mut::<State>()
+                                .set_valid(new_val.is_some());
+                            if let Some(new_val) = new_val {
+                                shell.publish((self.on_change)(new_val));
+                            }
+                        }
+                        event::Status::Captured
+                    }
                     Event::Keyboard(keyboard::Event::KeyPressed { key_code, .. })
                         if child.state.downcast_mut::<State>().is_focused() =>
                     {
@@ -606,11 +1185,7 @@ where
                     Event::Mouse(mouse::Event::WheelScrolled { delta })
                         if layout.bounds().contains(cursor_position) =>
                     {
-                        let negative = match delta {
-                            mouse::ScrollDelta::Lines { y, .. }
-                            | mouse::ScrollDelta::Pixels { y, .. } => y.is_sign_negative(),
-                        };
-                        if negative {
+                        if self.scroll_increases(delta) {
                             self.increase_val(shell, &mut child, &mut self.content.get_value());
                         } else {
                             self.decrease_val(shell, &mut child, &mut self.content.get_value());
@@ -667,16 +1242,18 @@ where
             .next()
             .expect("fail to get decreate mod layout")
             .bounds();
+        let mouse_over_reset = mod_children
+            .next()
+            .map_or(false, |reset| reset.bounds().contains(cursor_position));
         let is_mouse_over = bounds.contains(cursor_position);
-        let is_decrease_disabled = self.value.to_f64() <= self.bounds.lower.to_f64()
-            || self.bounds.lower.to_f64() == self.bounds.upper.to_f64();
-        let is_increase_disabled = self.value.to_f64() >= self.bounds.upper.to_f64()
-            || self.bounds.lower.to_f64() == self.bounds.upper.to_f64();
+        let is_decrease_disabled = self.is_decrease_disabled();
+        let is_increase_disabled = self.is_increase_disabled();
         let mouse_over_decrease = dec_bounds.contains(cursor_position);
         let mouse_over_increase = inc_bounds.contains(cursor_position);
 
         if (mouse_over_decrease && !is_decrease_disabled)
             || (mouse_over_increase && !is_increase_disabled)
+            || mouse_over_reset
         {
             mouse::Interaction::Pointer
         } else if is_mouse_over {
@@ -691,7 +1268,7 @@ where
         state: &Tree,
         renderer: &mut Renderer,
         theme: &Renderer::Theme,
-        _style: &iced_native::renderer::Style,
+        style: &iced_native::renderer::Style,
         layout: iced_native::Layout<'_>,
         cursor_position: iced_graphics::Point,
         _viewport: &iced_graphics::Rectangle,
@@ -699,14 +1276,65 @@ where
         let mut children = layout.children();
         let content_layout = children.next().expect("fail to get content layout");
 
+        // While an absolute value is typed, it is shown instead of the value
+        let entry = state
+            .state
+            .downcast_ref::<ModifierState>()
+            .entry
+            .as_deref()
+            .map(Value::new);
+
         self.content.draw(
             &state.children[0],
             renderer,
             theme,
             content_layout,
             cursor_position,
-            None,
+            entry.as_ref(),
         );
+
+        // A modifier that cannot change the value is dimmed
+        let dimmed = Color {
+            a: DISABLED_ALPHA,
+            ..style.text_color
+        };
+        let color = |is_disabled| {
+            if is_disabled {
+                dimmed
+            } else {
+                style.text_color
+            }
+        };
+        let (inc, dec) = self.modifier_icons();
+        let mut modifiers = children
+            .next()
+            .expect("fail to get modifiers layout")
+            .children();
+
+        for (icon, color) in [
+            (inc, color(self.is_increase_disabled())),
+            (dec, color(self.is_decrease_disabled())),
+            (RESET_ICON, style.text_color),
+        ] {
+            let Some(modifier) = modifiers.next() else {
+                break;
+            };
+            let bounds = modifier.bounds();
+
+            renderer.fill_text(iced_native::text::Text {
+                content: &icon.to_string(),
+                size: self.size.unwrap_or_else(|| renderer.default_size()) * 3.0 / 4.0,
+                font: self.font,
+                color,
+                bounds: Rectangle {
+                    x: bounds.center_x(),
+                    y: bounds.center_y(),
+                    ..bounds
+                },
+                horizontal_alignment: iced_native::alignment::Horizontal::Center,
+                vertical_alignment: iced_native::alignment::Vertical::Center,
+            });
+        }
     }
 }
 
@@ -717,8 +1345,42 @@ pub struct ModifierState {
     pub decrease_pressed: bool,
     /// The state of increase button on a [`NumberInput`].
     pub increase_pressed: bool,
+    /// When the held button fires next, while one is held.
+    pub repeat_at: Option<Instant>,
+    /// Absolute value being typed after a double click, until entered.
+    pub entry: Option<String>,
+    /// Last click on the value, to tell double clicks.
+    pub last_click: Option<mouse::Click>,
+}
+
+impl ModifierState {
+    /// Starts repeating the button pressed at `now`, first after
+    /// [`REPEAT_DELAY`]. Returns when it fires.
+    fn hold(&mut self, now: Instant) -> Instant {
+        let repeat_at = now + REPEAT_DELAY;
+        self.repeat_at = Some(repeat_at);
+        repeat_at
+    }
+
+    /// Whether the held button fires at `now`, then every [`REPEAT_INTERVAL`].
+    /// The repeat stops once `at_bound`, with nothing left to step to.
+    fn repeat(&mut self, now: Instant, at_bound: bool) -> bool {
+        match self.repeat_at {
+            Some(_) if at_bound => {
+                self.repeat_at = None;
+                false
+            }
+            Some(repeat_at) if now >= repeat_at => {
+                self.repeat_at = Some(now + REPEAT_INTERVAL);
+                true
+            }
+            _ => false,
+        }
+    }
 }
 
+/// SI prefix of `exp`, a multiple of three between pico and tera. Anything
+/// else is written in e-notation, e.g. `"e-8"`.
 fn get_prefix_from_exponent(exp: i8) -> String {
     let mu = "\u{00b5}";
 
@@ -732,7 +1394,22 @@ fn get_prefix_from_exponent(exp: i8) -> String {
         6 => String::from("M"),
         9 => String::from("G"),
         12 => String::from("T"),
-        _ => String::from("woops"),
+        _ => format!("e{exp}"),
+    }
+}
+
+/// Inverse of [`get_prefix_from_exponent`].
+fn exponent_from_prefix(prefix: char) -> Option<i8> {
+    match prefix {
+        'p' => Some(-12),
+        'n' => Some(-9),
+        'u' | '\u{00b5}' | '\u{03bc}' => Some(-6),
+        'm' => Some(-3),
+        'k' => Some(3),
+        'M' => Some(6),
+        'G' => Some(9),
+        'T' => Some(12),
+        _ => None,
     }
 }
 
@@ -764,3 +1441,424 @@ where
         Element::new(num_input)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: ExponentialNumber, significand: f64, exponent: i8) {
+        assert!(
+            (actual.significand - significand).abs() < 1e-9 && actual.exponent == exponent,
+            "{actual:?} is not {significand}e{exponent}"
+        );
+    }
+
+    /// A spin box of `unit` showing `value` within `bounds`, publishing the
+    /// values it changes to.
+    fn spin_box(
+        value: ExponentialNumber,
+        bounds: Bounds,
+        unit: &str,
+    ) -> ScientificSpinBox<'static, ExponentialNumber, iced::Renderer> {
+        ScientificSpinBox::new(value, bounds, unit, |value| value)
+    }
+
+    /// A spin box of a bias `volts` within ±5 V.
+    fn bias_box(volts: f64) -> ScientificSpinBox<'static, ExponentialNumber, iced::Renderer> {
+        spin_box(
+            ExponentialNumber::new(volts, 0),
+            Bounds::from_f64(-5.0, 5.0),
+            "V",
+        )
+    }
+
+    #[test]
+    fn arithmetic_rolls_over_between_prefixes() {
+        let nano = |significand| ExponentialNumber::new(significand, -9);
+
+        assert_close(nano(1.5) * ExponentialNumber::new(2.0, 0), 3.0, -9);
+        assert_close(nano(999.0) + nano(2.0), 1.001, -6);
+        assert_close(ExponentialNumber::new(1.001, -6) - nano(2.0), 999.0, -9);
+        assert_close(nano(3.0) / nano(1.5), 2.0, 0);
+        assert_close(nano(2.0) - nano(2.0), 0.0, -9);
+    }
+
+    #[test]
+    fn compares_by_magnitude_across_exponents() {
+        let micro = ExponentialNumber::new(1.0, -6);
+
+        assert_eq!(micro, ExponentialNumber::new(1000.0, -9));
+        assert!(ExponentialNumber::new(999.0, -9) < micro);
+        assert!(ExponentialNumber::new(-1.0, -3) < ExponentialNumber::new(1.0, -9));
+        assert!(ExponentialNumber::new(2.0, 3) > ExponentialNumber::new(999.0, 0));
+        assert!(ExponentialNumber::new(f64::NAN, 0) > ExponentialNumber::new(1.0, 12));
+        assert_eq!(
+            ExponentialNumber::new(5.0, -6).clamp(ExponentialNumber::new(0.0, 0), micro),
+            micro
+        );
+    }
+
+    #[test]
+    fn displays_the_significand_with_its_prefix() {
+        let micro = ExponentialNumber::new(-2.1, -6);
+        assert_eq!(micro.to_string(), "-2.100 \u{b5}");
+        assert_eq!(ExponentialNumber::new(0.0, 0).to_string(), "0.000");
+        assert_eq!(ExponentialNumber::new(1.0, 0).to_string(), "1.000");
+        assert_eq!(format!("{:.1}", ExponentialNumber::new(4.7, -8)), "47.0 n");
+
+        assert_eq!(ExponentialNumber::new(50.0, -9).with_unit("m"), "50.000 nm");
+        assert_eq!(ExponentialNumber::new(1.0, 0).with_unit("V"), "1.000 V");
+    }
+
+    #[test]
+    fn exponents_between_prefixes_show_on_the_prefix_below() {
+        for (exponent, positive, negative) in [
+            (-8, "47.000 n", "-47.000 n"),
+            (-7, "470.000 n", "-470.000 n"),
+            (-5, "47.000 \u{b5}", "-47.000 \u{b5}"),
+            (-2, "47.000 m", "-47.000 m"),
+        ] {
+            assert_eq!(ExponentialNumber::new(4.7, exponent).to_string(), positive);
+            assert_eq!(ExponentialNumber::new(-4.7, exponent).to_string(), negative);
+        }
+    }
+
+    #[test]
+    fn bounds_from_zero_and_negative_values() {
+        let bounds = Bounds::from_f64(0.0, 5.0);
+        assert_close(bounds.lower, 0.0, 0);
+        assert_close(bounds.upper, 5.0, 0);
+
+        let bounds = Bounds::from_f64(-5.0, 5.0);
+        assert_close(bounds.lower, -5.0, 0);
+        assert!(bounds.in_bounds(&-5.0) && !bounds.in_bounds(&-5.1));
+
+        let bounds = Bounds::from_f64(-1e-9, -1e-12);
+        assert_close(bounds.lower, -1.0, -9);
+        assert_close(bounds.upper, -1.0, -12);
+        assert!(bounds.in_bounds(&-1e-10) && !bounds.in_bounds(&0.0));
+
+        // Given the wrong way round
+        let bounds = Bounds::from_f64(5.0, -5.0);
+        assert_close(bounds.lower, -5.0, 0);
+        assert_close(bounds.upper, 5.0, 0);
+    }
+
+    #[test]
+    fn parses_every_si_prefix() {
+        for (text, exponent) in [
+            ("1p", -12),
+            ("1n", -9),
+            ("1µ", -6),
+            ("1u", -6),
+            ("1m", -3),
+            ("1k", 3),
+            ("1M", 6),
+            ("1G", 9),
+            ("1T", 12),
+        ] {
+            assert_close(text.parse().unwrap(), 1.0, exponent);
+        }
+
+        assert_close("50 nm".parse().unwrap(), 50.0, -9);
+        assert_close("2.1 µ".parse().unwrap(), 2.1, -6);
+        assert_close("-3.3m".parse().unwrap(), -3.3, -3);
+        assert_close("1.5e-9".parse().unwrap(), 1.5, -9);
+    }
+
+    #[test]
+    fn rejects_malformed_and_out_of_range_input() {
+        use ParseExponentialError::*;
+        let parse = |s: &str| s.parse::<ExponentialNumber>().unwrap_err();
+
+        assert_eq!(parse(""), Empty);
+        assert_eq!(parse("abc"), InvalidNumber(String::from("abc")));
+        assert_eq!(parse("5x"), UnknownPrefix(String::from("x")));
+        assert_eq!(parse("1e300"), OutOfRange(String::from("1e300")));
+        assert_eq!(parse("1e-300"), OutOfRange(String::from("1e-300")));
+        assert_eq!(parse("1e400"), OutOfRange(String::from("1e400")));
+    }
+
+    #[test]
+    fn huge_exponents_stay_within_i8() {
+        for value in [1e300, -1e300, 1e-300] {
+            let number = ExponentialNumber::from_scientific(value);
+            assert!((number.to_f64() / value - 1.0).abs() < 1e-9);
+
+            let normalized = number.normalized();
+            assert!((-126..=126).contains(&normalized.exponent));
+            assert!((normalized.to_f64() / value - 1.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn stepping_up_past_999_moves_to_the_next_prefix() {
+        let (value, scaled_down) = step_up(ExponentialNumber::new(999.0, -9), 1.0);
+        assert_close(value, 1.0, -6);
+        assert!(!scaled_down);
+
+        let (value, _) = step_up(ExponentialNumber::new(995.0, -9), 10.0);
+        assert_close(value, 1.005, -6);
+    }
+
+    #[test]
+    fn stepping_down_below_one_moves_to_the_previous_prefix() {
+        let (value, scaled_down) = step_down(ExponentialNumber::new(1.5, -6), 1.0);
+        assert_close(value, 500.0, -9);
+        assert!(scaled_down);
+
+        let (value, scaled_down) = step_down(ExponentialNumber::new(-999.0, -9), 1.0);
+        assert_close(value, -1.0, -6);
+        assert!(!scaled_down);
+    }
+
+    #[test]
+    fn stepping_rescales_below_pico_like_any_prefix() {
+        let (value, scaled_down) = step_down(ExponentialNumber::new(1.5, -9), 1.0);
+        assert_close(value, 500.0, -12);
+        assert!(scaled_down);
+
+        let (value, scaled_down) = step_down(ExponentialNumber::new(1.5, -12), 1.0);
+        assert_close(value, 500.0, -15);
+        assert!(scaled_down);
+    }
+
+    #[test]
+    fn stepping_across_zero_flips_the_sign() {
+        let (value, scaled_down) = step_down(ExponentialNumber::new(2.0, 0), 10.0);
+        assert_close(value, -8.0, 0);
+        assert!(!scaled_down);
+
+        let (value, _) = step_up(ExponentialNumber::new(-5.0, 0), 10.0);
+        assert_close(value, 5.0, 0);
+
+        let (value, scaled_down) = step_up(ExponentialNumber::new(-1.5, 0), 1.0);
+        assert_close(value, -500.0, -3);
+        assert!(scaled_down);
+
+        let (value, scaled_down) = step_down(ExponentialNumber::new(1.0, 0), 1.0);
+        assert_close(value, 0.0, 0);
+        assert!(!scaled_down);
+    }
+
+    #[test]
+    fn steps_follow_the_cursor_and_clamp_at_the_bounds() {
+        let bounds = Bounds::from_f64(-10.0, 10.0);
+
+        // Cursor on the ones, tenths and hundredths of "9.500"
+        let text = Value::new("9.500");
+        let value = ExponentialNumber::new(9.5, 0);
+        let (up, _) = increment(value, get_step(0, &text), &bounds);
+        assert_close(up, 1.0, 1);
+        let (up, _) = increment(value, get_step(2, &text), &bounds);
+        assert_close(up, 9.6, 0);
+        let (up, _) = increment(value, get_step(3, &text), &bounds);
+        assert_close(up, 9.51, 0);
+
+        // The sign takes a place in front of the ones of "-9.500"
+        let text = Value::new("-9.500");
+        let value = ExponentialNumber::new(-9.5, 0);
+        let (down, _) = decrement(value, get_step(1, &text), &bounds);
+        assert_close(down, -1.0, 1);
+        let (down, _) = decrement(value, get_step(3, &text), &bounds);
+        assert_close(down, -9.6, 0);
+    }
+
+    #[test]
+    fn prefix_steps_clamp_at_the_bounds() {
+        let bounds = Bounds::from_f64(0.0, 1e-3);
+
+        let (nano, micro, pico) = (
+            ExponentialNumber::new(5.0, -9),
+            ExponentialNumber::new(5.0, -6),
+            ExponentialNumber::new(5.0, -12),
+        );
+
+        assert_close(prefix_up(nano, &bounds), 5.0, -6);
+        assert_close(prefix_up(micro, &bounds), 1.0, -3);
+        assert_close(prefix_down(nano, &bounds), 5.0, -12);
+        assert_close(prefix_down(pico, &bounds), 5.0, -12);
+    }
+
+    #[test]
+    fn pasted_number_is_spliced_in_at_the_cursor() {
+        let spin_box = bias_box(2.5);
+        let mut cursor = cursor::Cursor::default();
+
+        cursor.move_to(3);
+        assert_close(spin_box.spliced(cursor, "1").unwrap(), 2.51, 0);
+
+        // Replacing the selected "2"
+        cursor.select_range(0, 1);
+        assert_close(spin_box.spliced(cursor, "4").unwrap(), 4.5, 0);
+    }
+
+    #[test]
+    fn pasted_text_that_is_not_a_number_in_bounds_is_rejected() {
+        let spin_box = bias_box(2.5);
+        let mut cursor = cursor::Cursor::default();
+        cursor.move_to(3);
+
+        assert_eq!(spin_box.spliced(cursor, "abc"), None);
+        assert_eq!(spin_box.spliced(cursor, "e999"), None);
+        // 92.5 V
+        cursor.move_to(0);
+        assert_eq!(spin_box.spliced(cursor, "9"), None);
+    }
+
+    #[test]
+    fn scrolling_up_increases_unless_inverted() {
+        let up = mouse::ScrollDelta::Lines { x: 0.0, y: 1.0 };
+        let down = mouse::ScrollDelta::Lines { x: 0.0, y: -1.0 };
+
+        let spin_box = bias_box(2.5);
+        assert!(spin_box.scroll_increases(up));
+        assert!(!spin_box.scroll_increases(down));
+        assert!(spin_box.scroll_increases(mouse::ScrollDelta::Pixels { x: 0.0, y: 12.0 }));
+
+        let inverted = bias_box(2.5).invert_scroll(true);
+        assert!(!inverted.scroll_increases(up));
+        assert!(inverted.scroll_increases(down));
+    }
+
+    #[test]
+    fn held_button_repeats_after_a_delay() {
+        let pressed = Instant::now();
+        let after = |millis| pressed + Duration::from_millis(millis);
+        let mut modifiers = ModifierState::default();
+
+        assert_eq!(modifiers.hold(pressed), after(400));
+        let fired: Vec<_> = [100, 399, 400, 420, 449, 450, 500, 560]
+            .into_iter()
+            .filter(|&millis| modifiers.repeat(after(millis), false))
+            .collect();
+        assert_eq!(fired, [400, 450, 500]);
+        assert_eq!(modifiers.repeat_at, Some(after(550)));
+    }
+
+    #[test]
+    fn held_button_stops_repeating_at_the_bound() {
+        let pressed = Instant::now();
+        let mut modifiers = ModifierState::default();
+        modifiers.hold(pressed);
+
+        assert!(!modifiers.repeat(pressed + REPEAT_DELAY, true));
+        assert_eq!(modifiers.repeat_at, None);
+        assert!(!modifiers.repeat(pressed + REPEAT_DELAY * 2, false));
+    }
+
+    #[test]
+    fn negation_flips_the_sign_and_keeps_the_exponent() {
+        for (significand, exponent) in [(1.5, -9), (-250.0, -3), (4.7, 0), (999.0, 6)] {
+            let negated = ExponentialNumber::new(significand, exponent).negated();
+            assert_eq!(negated.significand, -significand);
+            assert_eq!(negated.exponent, exponent);
+        }
+
+        // Zero never turns into negative zero
+        for zero in [0.0, -0.0] {
+            let negated = ExponentialNumber::new(zero, -9).negated();
+            assert!(negated.significand == 0.0 && negated.significand.is_sign_positive());
+            assert_eq!(negated.exponent, -9);
+        }
+        let negative_zero = ExponentialNumber::new(-0.0, 0);
+        assert_eq!(negative_zero.negated().to_string(), "0.000");
+    }
+
+    #[test]
+    fn sign_toggles_only_within_the_bounds() {
+        assert_close(bias_box(2.5).sign_toggled().unwrap(), -2.5, 0);
+        assert_close(bias_box(-0.5).sign_toggled().unwrap(), 0.5, 0);
+
+        let positive = Bounds::from_f64(0.0, 5.0);
+        let above_zero = spin_box(ExponentialNumber::new(2.5, 0), positive, "V");
+        assert_eq!(above_zero.sign_toggled(), None);
+        let zero = spin_box(ExponentialNumber::new(-0.0, 0), positive, "V");
+        assert_close(zero.sign_toggled().unwrap(), 0.0, 0);
+    }
+
+    #[test]
+    fn pressing_reset_publishes_the_default() {
+        let default = ExponentialNumber::new(1.0, 0);
+        let click = Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left));
+        let release = Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left));
+
+        let spin_box = bias_box(2.5).default_value(default);
+        assert_eq!(spin_box.reset_message(&click, true), Some(default));
+        assert_eq!(spin_box.reset_message(&click, false), None);
+        assert_eq!(spin_box.reset_message(&release, true), None);
+
+        // Without a default there is no reset button to press
+        assert_eq!(bias_box(1.0).reset_message(&click, true), None);
+    }
+
+    #[test]
+    fn spin_box_without_a_range_is_not_editable() {
+        assert!(bias_box(0.5).is_editable());
+
+        let fixed = ExponentialNumber::new(1.0, 0);
+        let pinned = spin_box(fixed, Bounds::from_f64(1.0, 1.0), "V");
+        assert!(!pinned.is_editable());
+        assert!(pinned.is_increase_disabled() && pinned.is_decrease_disabled());
+    }
+
+    #[test]
+    fn modifier_at_a_bound_is_disabled() {
+        let lower = bias_box(-5.0);
+        assert!(lower.is_decrease_disabled());
+        assert!(!lower.is_increase_disabled());
+
+        let upper = bias_box(5.0);
+        assert!(upper.is_increase_disabled());
+        assert!(!upper.is_decrease_disabled());
+
+        let within = bias_box(0.5);
+        assert!(!within.is_increase_disabled() && !within.is_decrease_disabled());
+
+        let fixed = spin_box(
+            ExponentialNumber::new(1.0, 0),
+            Bounds::from_f64(1.0, 1.0),
+            "V",
+        );
+        assert!(fixed.is_increase_disabled() && fixed.is_decrease_disabled());
+    }
+
+    #[test]
+    fn absolute_value_is_read_with_or_without_the_unit() {
+        let line_time = spin_box(
+            ExponentialNumber::new(1.0, 0),
+            Bounds::from_f64(102.4e-3, 100.0),
+            "s",
+        );
+        assert_close(line_time.parse_absolute("100 s").unwrap(), 100.0, 0);
+        assert_close(line_time.parse_absolute(" 50 s ").unwrap(), 50.0, 0);
+        assert_close(line_time.parse_absolute("200 ms").unwrap(), 200.0, -3);
+        assert_close(line_time.parse_absolute("50").unwrap(), 50.0, 0);
+
+        let size = spin_box(
+            ExponentialNumber::new(100.0, -9),
+            Bounds::from_f64(1e-9, 1e-6),
+            "m",
+        );
+        assert_close(size.parse_absolute("5 nm").unwrap(), 5.0, -9);
+    }
+
+    #[test]
+    fn rejected_absolute_value_marks_the_input_invalid() {
+        let line_time = spin_box(
+            ExponentialNumber::new(1.0, 0),
+            Bounds::from_f64(102.4e-3, 100.0),
+            "s",
+        );
+        let mut input = State::new();
+
+        for entry in ["abc", "", "500 s", "1 ms", "-50 s"] {
+            assert_eq!(line_time.enter_absolute(entry, &mut input), None, "{entry}");
+            assert!(!input.is_valid(), "{entry}");
+        }
+
+        let entered = line_time.enter_absolute("50 s", &mut input);
+        assert_close(entered.unwrap(), 50.0, 0);
+        assert!(input.is_valid());
+    }
+}
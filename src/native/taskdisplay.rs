@@ -1,31 +1,56 @@
 //! Provide progress feedback to your users.
+use iced_native::alignment;
+use iced_native::event::{self, Event};
 use iced_native::layout::{self, Layout};
+use iced_native::mouse;
 use iced_native::renderer;
-use iced_native::widget::{tree::Tree, Widget};
-use iced_native::{Color, Element, Length, Padding, Point, Rectangle};
+use iced_native::text::{self, Text};
+use iced_native::time::{Duration, Instant};
+use iced_native::widget::{tree, tree::Tree, Operation, Widget};
+use iced_native::{window, Clipboard, Color, Element, Length, Padding, Point, Rectangle, Shell};
 
+use crate::native::motion;
 use crate::style::taskdisplay::StyleSheet;
 
 use std::ops::RangeInclusive;
 
+/// Time the indeterminate highlight takes to cross the bar once.
+const SWEEP_PERIOD: Duration = Duration::from_millis(1500);
+/// Width of the indeterminate highlight, as a fraction of the bar.
+const SWEEP_WIDTH: f32 = 0.25;
+/// Time between frames of the indeterminate highlight.
+const FRAME: Duration = Duration::from_millis(16);
+
+/// How much of the task is done, if that is known yet.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    /// The bar is filled up to this value of the range.
+    Determinate(f32),
+    /// No progress is known, so a highlight sweeps across the bar.
+    Indeterminate,
+}
+
 pub struct TaskDisplay<'a, Message, Renderer>
 where
-    Renderer: renderer::Renderer,
+    Renderer: text::Renderer,
     Renderer::Theme: StyleSheet,
 {
     content: Element<'a, Message, Renderer>,
     range: RangeInclusive<f32>,
-    value: f32,
+    mode: Mode,
     width: Length,
     height: Option<Length>,
     padding: Padding,
     border_radius: f32,
+    on_press: Option<Message>,
+    is_selected: bool,
+    show_percentage: bool,
     style: <Renderer::Theme as StyleSheet>::Style,
 }
 
 impl<'a, Message, Renderer> TaskDisplay<'a, Message, Renderer>
 where
-    Renderer: renderer::Renderer,
+    Renderer: text::Renderer,
     Renderer::Theme: StyleSheet,
 {
     pub const DEFAULT_HEIGHT: f32 = 40.0;
@@ -34,11 +59,14 @@ where
         TaskDisplay {
             content: content.into(),
             range: 0.0..=100.0,
-            value: 0.0,
+            mode: Mode::Determinate(0.0),
             width: Length::Fill,
             height: Some(Length::Shrink),
             padding: Padding::new(15.0),
             border_radius: 10.0,
+            on_press: None,
+            is_selected: false,
+            show_percentage: false,
             style: Default::default(),
         }
     }
@@ -49,18 +77,62 @@ where
         self
     }
 
+    /// Fills the bar up to `value`, as [`Mode::Determinate`].
     pub fn value(mut self, value: f32) -> Self {
-        self.value = value;
+        self.mode = Mode::Determinate(value);
+        self
+    }
+
+    pub fn mode(mut self, mode: Mode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Sets the message produced when the [`TaskDisplay`] is clicked outside of
+    /// any interactive content.
+    pub fn on_press(mut self, message: Message) -> Self {
+        self.on_press = Some(message);
+        self
+    }
+
+    /// Outlines the [`TaskDisplay`] as the current selection.
+    pub fn selected(mut self, is_selected: bool) -> Self {
+        self.is_selected = is_selected;
+        self
+    }
+
+    /// Writes the progress as a percentage in the middle of the bar.
+    pub fn show_percentage(mut self, show_percentage: bool) -> Self {
+        self.show_percentage = show_percentage;
         self
     }
+
+    /// Sets the height of the [`TaskDisplay`].
+    pub fn height(mut self, height: impl Into<Length>) -> Self {
+        self.height = Some(height.into());
+        self
+    }
+
+    /// The configured height, or [`Self::DEFAULT_HEIGHT`] when unset.
+    fn resolved_height(&self) -> Length {
+        self.height.unwrap_or(Length::Fixed(Self::DEFAULT_HEIGHT))
+    }
 }
 
 impl<'a, Message, Renderer> Widget<Message, Renderer> for TaskDisplay<'a, Message, Renderer>
 where
     Message: 'a + Clone,
-    Renderer: 'a + renderer::Renderer,
+    Renderer: 'a + text::Renderer,
     Renderer::Theme: StyleSheet,
 {
+    fn tag(&self) -> tree::Tag {
+        tree::Tag::of::<State>()
+    }
+
+    fn state(&self) -> tree::State {
+        tree::State::new(State::default())
+    }
+
     fn children(&self) -> Vec<Tree> {
         vec![Tree::new(&self.content)]
     }
@@ -74,7 +146,7 @@ where
     }
 
     fn height(&self) -> Length {
-        self.height.unwrap_or(Length::Fixed(Self::DEFAULT_HEIGHT))
+        self.resolved_height()
     }
 
     fn layout(&self, renderer: &Renderer, limits: &layout::Limits) -> layout::Node {
@@ -82,12 +154,94 @@ where
             renderer,
             limits,
             self.width,
-            self.height,
+            self.resolved_height(),
             self.padding,
             |renderer, limits| self.content.as_widget().layout(renderer, limits),
         )
     }
 
+    fn operate(
+        &self,
+        tree: &mut Tree,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+        operation: &mut dyn Operation<Message>,
+    ) {
+        self.content.as_widget().operate(
+            &mut tree.children[0],
+            layout.children().next().unwrap(),
+            renderer,
+            operation,
+        );
+    }
+
+    fn on_event(
+        &mut self,
+        tree: &mut Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        renderer: &Renderer,
+        clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+    ) -> event::Status {
+        if let Event::Window(window::Event::RedrawRequested(now)) = event {
+            let state = tree.state.downcast_mut::<State>();
+
+            if self.mode == Mode::Indeterminate {
+                state.started.get_or_insert(now);
+                state.now = Some(now);
+
+                if !motion::is_reduced() {
+                    shell.request_redraw(window::RedrawRequest::At(now + FRAME));
+                }
+            } else {
+                *state = State::default();
+            }
+        }
+
+        // Forward events so interactive content (e.g. the task menu button) works
+        let status = self.content.as_widget_mut().on_event(
+            &mut tree.children[0],
+            event.clone(),
+            layout.children().next().unwrap(),
+            cursor_position,
+            renderer,
+            clipboard,
+            shell,
+        );
+
+        if status == event::Status::Ignored {
+            if let (Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)), Some(on_press)) =
+                (event, &self.on_press)
+            {
+                if layout.bounds().contains(cursor_position) {
+                    shell.publish(on_press.clone());
+                    return event::Status::Captured;
+                }
+            }
+        }
+
+        status
+    }
+
+    fn mouse_interaction(
+        &self,
+        tree: &Tree,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        viewport: &Rectangle,
+        renderer: &Renderer,
+    ) -> mouse::Interaction {
+        self.content.as_widget().mouse_interaction(
+            &tree.children[0],
+            layout.children().next().unwrap(),
+            cursor_position,
+            viewport,
+            renderer,
+        )
+    }
+
     fn draw(
         &self,
         _state: &Tree,
@@ -99,14 +253,11 @@ where
         _viewport: &Rectangle,
     ) {
         let bounds = layout.bounds();
-        let (range_start, range_end) = self.range.clone().into_inner();
         let content_layout = layout.children().next().unwrap();
 
-        let active_progress_width = if range_start >= range_end {
-            0.0
-        } else {
-            bounds.width * (self.value - range_start) / (range_end - range_start)
-        };
+        let elapsed = _state.state.downcast_ref::<State>().elapsed();
+        let (fill_start, fill_end) = fill(self.mode, &self.range, elapsed);
+        let active_progress_width = bounds.width * (fill_end - fill_start);
 
         let style = theme.appearance(&self.style);
 
@@ -126,6 +277,7 @@ where
             renderer.fill_quad(
                 renderer::Quad {
                     bounds: Rectangle {
+                        x: bounds.x + bounds.width * fill_start,
                         width: active_progress_width,
                         ..bounds
                     },
@@ -137,6 +289,36 @@ where
             );
         }
 
+        // Percentage over the bar, under the content
+        if let (true, Mode::Determinate(value)) = (self.show_percentage, self.mode) {
+            renderer.fill_text(Text {
+                content: &percentage(value, &self.range),
+                size: renderer.default_size(),
+                font: Default::default(),
+                color: style.text_color,
+                bounds: Rectangle {
+                    x: bounds.center_x(),
+                    y: bounds.center_y(),
+                    ..bounds
+                },
+                horizontal_alignment: alignment::Horizontal::Center,
+                vertical_alignment: alignment::Vertical::Center,
+            });
+        }
+
+        // Outline the selected task over the progress quad
+        if self.is_selected {
+            renderer.fill_quad(
+                renderer::Quad {
+                    bounds,
+                    border_radius: self.border_radius.into(),
+                    border_width: 2.0,
+                    border_color: style.text_color,
+                },
+                Color::TRANSPARENT,
+            );
+        }
+
         // Draw content on top of task bar
         self.content.as_widget().draw(
             &_state.children[0],
@@ -156,7 +338,7 @@ impl<'a, Message, Renderer> From<TaskDisplay<'a, Message, Renderer>>
     for Element<'a, Message, Renderer>
 where
     Message: 'a + Clone,
-    Renderer: 'a + renderer::Renderer,
+    Renderer: 'a + text::Renderer,
     Renderer::Theme: StyleSheet,
 {
     fn from(task: TaskDisplay<'a, Message, Renderer>) -> Element<'a, Message, Renderer> {
@@ -164,16 +346,76 @@ where
     }
 }
 
+/// When the indeterminate highlight started sweeping and the last frame it was
+/// drawn for.
+#[derive(Debug, Clone, Copy, Default)]
+struct State {
+    started: Option<Instant>,
+    now: Option<Instant>,
+}
+
+impl State {
+    /// Time the highlight has been sweeping, or `None` when it stands still.
+    fn elapsed(&self) -> Option<Duration> {
+        match (self.started, self.now) {
+            (Some(started), Some(now)) if !motion::is_reduced() => {
+                Some(now.duration_since(started))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Span of the bar to fill in `mode`, as fractions of its width: up to the
+/// value's place in `range`, or the [`sweep`] after `elapsed`.
+fn fill(mode: Mode, range: &RangeInclusive<f32>, elapsed: Option<Duration>) -> (f32, f32) {
+    let (start, end) = range.clone().into_inner();
+
+    match mode {
+        Mode::Determinate(_) if start >= end => (0.0, 0.0),
+        Mode::Determinate(value) => (0.0, (value - start) / (end - start)),
+        Mode::Indeterminate => sweep(elapsed),
+    }
+}
+
+/// Span of the bar covered by the indeterminate highlight after sweeping for
+/// `elapsed`, as fractions of its width. The highlight enters on the left,
+/// leaves on the right and starts over; without an elapsed time it rests in
+/// the middle.
+pub fn sweep(elapsed: Option<Duration>) -> (f32, f32) {
+    let phase = match elapsed {
+        Some(elapsed) => (elapsed.as_secs_f32() / SWEEP_PERIOD.as_secs_f32()).fract(),
+        None => 0.5,
+    };
+    let start = phase * (1.0 + SWEEP_WIDTH) - SWEEP_WIDTH;
+
+    (start.max(0.0), (start + SWEEP_WIDTH).min(1.0))
+}
+
+/// Formats `value` as a whole percentage of `range`, e.g. `"42%"`. The value
+/// is clamped to the range, and an empty range reads as no progress.
+pub fn percentage(value: f32, range: &RangeInclusive<f32>) -> String {
+    let (start, end) = range.clone().into_inner();
+
+    let fraction = if start >= end {
+        0.0
+    } else {
+        ((value - start) / (end - start)).clamp(0.0, 1.0)
+    };
+
+    format!("{:.0}%", fraction * 100.0)
+}
+
 /// Computes the layout of a [`TaskDisplay`].
 pub fn layout<Renderer>(
     renderer: &Renderer,
     limits: &layout::Limits,
     width: Length,
-    height: Option<Length>,
+    height: Length,
     padding: Padding,
     layout_content: impl FnOnce(&Renderer, &layout::Limits) -> layout::Node,
 ) -> layout::Node {
-    let limits = limits.width(width).height(height.unwrap());
+    let limits = limits.width(width).height(height);
 
     let mut content = layout_content(renderer, &limits.pad(padding));
     let padding = padding.fit(content.size(), limits.max());
@@ -183,3 +425,90 @@ pub fn layout<Renderer>(
 
     layout::Node::with_children(size, vec![content])
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iced_native::Size;
+
+    #[test]
+    fn percentage_is_a_whole_share_of_the_range() {
+        assert_eq!(percentage(42.0, &(0.0..=100.0)), "42%");
+        assert_eq!(percentage(37.5, &(0.0..=100.0)), "38%");
+        assert_eq!(percentage(0.25, &(0.0..=1.0)), "25%");
+        assert_eq!(percentage(15.0, &(10.0..=20.0)), "50%");
+    }
+
+    #[test]
+    fn percentage_clamps_to_the_range() {
+        assert_eq!(percentage(150.0, &(0.0..=100.0)), "100%");
+        assert_eq!(percentage(-5.0, &(0.0..=100.0)), "0%");
+        // An empty range has no progress to show
+        assert_eq!(percentage(5.0, &(5.0..=5.0)), "0%");
+    }
+
+    fn task_display() -> TaskDisplay<'static, (), iced::Renderer> {
+        TaskDisplay::new(iced::widget::text("survey"))
+    }
+
+    #[test]
+    fn height_falls_back_to_the_default() {
+        let mut display = task_display().height(Length::Fixed(60.0));
+        assert_eq!(display.resolved_height(), Length::Fixed(60.0));
+
+        display.height = None;
+        assert_eq!(display.resolved_height(), Length::Fixed(40.0));
+    }
+
+    #[test]
+    fn layout_takes_the_configured_height() {
+        let limits = layout::Limits::new(Size::ZERO, Size::new(300.0, 500.0));
+        let node = layout(
+            &renderer::Null::new(),
+            &limits,
+            Length::Fill,
+            Length::Fixed(60.0),
+            Padding::new(15.0),
+            |_, _| layout::Node::new(Size::new(50.0, 20.0)),
+        );
+
+        assert_eq!(node.size(), Size::new(300.0, 60.0));
+    }
+
+    #[test]
+    fn determinate_bar_fills_up_to_the_value() {
+        let percent = 0.0..=100.0;
+        assert_eq!(fill(Mode::Determinate(25.0), &percent, None), (0.0, 0.25));
+        let empty = 5.0..=5.0;
+        assert_eq!(fill(Mode::Determinate(25.0), &empty, None), (0.0, 0.0));
+        // Elapsed time only moves the indeterminate highlight
+        let elapsed = Some(Duration::from_millis(300));
+        assert_eq!(fill(Mode::Determinate(50.0), &percent, elapsed), (0.0, 0.5));
+    }
+
+    #[test]
+    fn indeterminate_highlight_sweeps_with_time() {
+        let at = |millis| {
+            let elapsed = Duration::from_millis(millis);
+            fill(Mode::Indeterminate, &(0.0..=100.0), Some(elapsed))
+        };
+
+        // Entering on the left, crossing the middle, leaving on the right
+        assert_eq!(at(0), (0.0, 0.0));
+        assert_eq!(at(750), (0.375, 0.625));
+        let (start, end) = at(1200);
+        assert!((start - 0.75).abs() < 1e-6 && end == 1.0);
+        // And over again
+        assert_eq!(at(2250), at(750));
+        // Resting in the middle without a clock
+        assert_eq!(fill(Mode::Indeterminate, &(0.0..=100.0), None), at(750));
+    }
+
+    #[test]
+    fn value_and_mode_pick_the_mode() {
+        assert_eq!(task_display().mode, Mode::Determinate(0.0));
+        assert_eq!(task_display().value(30.0).mode, Mode::Determinate(30.0));
+        let display = task_display().value(30.0).mode(Mode::Indeterminate);
+        assert_eq!(display.mode, Mode::Indeterminate);
+    }
+}
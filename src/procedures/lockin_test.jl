@@ -2,7 +2,15 @@ module Test
 
     using Sockets
 
-    function read_lockin()
+    # Scans one line of an image, called for line = 0, 1, ..., lines - 1 in
+    # turn. Scan parameters arrive in instrument units: line and lines::UInt32,
+    # then size, x_offset, y_offset, rotation (degrees, counterclockwise about
+    # the scan center), line_time, bias and the tunneling current set_point as
+    # Float64. Returns the line's height data as a Vector{Float64} of length
+    # lines.
+    function read_lockin(line, lines, size, x_offset, y_offset, rotation, line_time, bias, set_point)
+        println("Scanning line $(line + 1)/$lines of $size at ($x_offset, $y_offset) rotated $rotation°, $line_time s/line, $bias V, $set_point A")
+
         ip = ip"169.254.11.17"
         port = 50000
 
@@ -10,9 +18,31 @@ module Test
 
         println(socket, "X.")
         x = readavailable(socket)
-        val = Char.(x)
+        val = parse(Float64, strip(String(x)))
+        Sockets.close(socket)
+
+        # A single lock-in reading stands in for every pixel of the test line
+        fill(val, Int(lines))
+    end
+
+    # Measures a spectroscopy curve at (x, y), in meters, after the image has
+    # been scanned. The bias goes through start + i * step for i = 0, 1, ...,
+    # points - 1, with points::UInt64 and the rest Float64, at the tunneling
+    # current set_point. Returns one Float64 per bias as a Vector{Float64}.
+    function read_sts(x, y, start, step, points, set_point)
+        println("Measuring $points biases from $start V by $step V at ($x, $y), $set_point A")
+
+        ip = ip"169.254.11.17"
+        port = 50000
+
+        socket = Sockets.connect(ip, port)
+
+        println(socket, "X.")
+        reply = readavailable(socket)
+        val = parse(Float64, strip(String(reply)))
         Sockets.close(socket)
 
-        val
+        # A single lock-in reading stands in for every bias of the test curve
+        fill(val, Int(points))
     end
 end
\ No newline at end of file
@@ -47,6 +47,11 @@ pub trait StyleSheet {
 
     /// Produces the style of a disabled text input.
     fn disabled(&self, style: &Self::Style) -> Appearance;
+
+    /// Produces the style of a text input that rejected what was typed.
+    fn error(&self, style: &Self::Style) -> Appearance {
+        self.focused(style)
+    }
 }
 
 /// The style of a text input.
@@ -156,6 +161,22 @@ impl StyleSheet for Theme {
         }
     }
 
+    fn error(&self, style: &Self::Style) -> Appearance {
+        if let ScientificTextStyle::Custom(custom) = style {
+            return custom.error(self);
+        }
+
+        let palette = self.extended_palette();
+
+        Appearance {
+            background: palette.background.base.color.into(),
+            border_radius: 2.0,
+            border_width: 1.0,
+            border_color: palette.danger.base.color,
+            icon_color: palette.background.weak.text,
+        }
+    }
+
     fn disabled_color(&self, style: &Self::Style) -> Color {
         if let ScientificTextStyle::Custom(custom) = style {
             return custom.disabled_color(self);
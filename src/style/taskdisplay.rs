@@ -23,9 +23,11 @@ pub trait StyleSheet {
     fn appearance(&self, style: &Self::Style) -> Appearance;
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TaskDisplayStyles {
     Waiting,
     Running,
+    Paused,
     Completed,
     Failed,
 }
@@ -47,26 +49,57 @@ impl StyleSheet for Theme {
                 background: palette.background.weak.color.into(),
                 bar: palette.background.strong.color.into(),
                 border_radius: 0.0,
-                text_color: Color::BLACK,
+                text_color: palette.background.weak.text,
             },
             TaskDisplayStyles::Running => Appearance {
                 background: palette.primary.weak.color.into(),
                 bar: palette.primary.strong.color.into(),
                 border_radius: 0.0,
-                text_color: Color::BLACK,
+                text_color: palette.primary.weak.text,
+            },
+            TaskDisplayStyles::Paused => Appearance {
+                background: palette.secondary.weak.color.into(),
+                bar: palette.secondary.strong.color.into(),
+                border_radius: 0.0,
+                text_color: palette.secondary.weak.text,
             },
             TaskDisplayStyles::Completed => Appearance {
                 background: palette.success.weak.color.into(),
                 bar: palette.success.strong.color.into(),
                 border_radius: 0.0,
-                text_color: Color::BLACK,
+                text_color: palette.success.weak.text,
             },
             TaskDisplayStyles::Failed => Appearance {
                 background: palette.danger.weak.color.into(),
                 bar: palette.danger.strong.color.into(),
                 border_radius: 0.0,
-                text_color: Color::BLACK,
+                text_color: palette.danger.weak.text,
             },
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_state_has_its_own_bar_color() {
+        let styles = [
+            TaskDisplayStyles::Waiting,
+            TaskDisplayStyles::Running,
+            TaskDisplayStyles::Paused,
+            TaskDisplayStyles::Completed,
+            TaskDisplayStyles::Failed,
+        ];
+
+        for theme in [Theme::Light, Theme::Dark] {
+            let bar = |style: &TaskDisplayStyles| theme.appearance(style).bar;
+            for (i, a) in styles.iter().enumerate() {
+                for b in &styles[i + 1..] {
+                    assert_ne!(bar(a), bar(b), "{:?} and {:?} share a color", a, b);
+                }
+            }
+        }
+    }
+}
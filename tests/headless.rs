@@ -0,0 +1,147 @@
+//! Runs queues the way `stm_controller --run` does, on a stub instrument.
+use stm_controller::core::{
+    export::{export_task, FileExporter},
+    headless,
+    runner::TaskRunner,
+    stmimage::{Measurement, STMImage, DEFAULT_SET_POINT},
+    task::{InsertPosition, Task, TaskList, TaskState},
+};
+
+/// Bias above which the stub instrument refuses to scan.
+const MAX_BIAS: f64 = 5.0;
+
+/// Scans every image as a flat surface, without Julia or hardware.
+struct StubRunner {
+    scanned: usize,
+}
+
+impl TaskRunner<STMImage> for StubRunner {
+    type Output = Measurement;
+    type Error = String;
+
+    fn run(&mut self, image: STMImage) -> Result<Measurement, String> {
+        if image.bias() > MAX_BIAS {
+            return Err(format!("Bias {} V out of range", image.bias()));
+        }
+        self.scanned += 1;
+        Ok(Measurement {
+            data: vec![0.0; (image.lines() as usize).pow(2)],
+            spectroscopy: vec![],
+        })
+    }
+}
+
+fn image(bias: f64) -> STMImage {
+    STMImage::new(4, 1e-8, 0.0, 0.0, 0.0, 0.1, bias, DEFAULT_SET_POINT, None)
+}
+
+/// A queue of two tasks, the second scanning at `bias`.
+fn two_task_queue(bias: f64) -> TaskList<STMImage> {
+    let mut tasklist = TaskList::default();
+    tasklist.insert(
+        Task::new(vec![image(0.5), image(1.0)], String::from("survey"), 0),
+        InsertPosition::End,
+    );
+    tasklist.insert(
+        Task::new(vec![image(bias)], String::from("detail"), 0),
+        InsertPosition::End,
+    );
+    tasklist
+}
+
+/// Runs `tasklist` on the stub, returning the exit code, the printed lines and
+/// the number of state changes seen.
+fn run(tasklist: &mut TaskList<STMImage>, runner: &mut StubRunner) -> (i32, Vec<String>, usize) {
+    let mut out = Vec::new();
+    let mut changes = 0;
+    let result = headless::run(tasklist, runner, &mut out, |_| changes += 1);
+
+    let printed = String::from_utf8(out).unwrap();
+    let lines = printed.lines().map(String::from).collect();
+    (headless::exit_code(&result), lines, changes)
+}
+
+#[test]
+fn queue_that_completes_exits_with_zero() {
+    let mut tasklist = two_task_queue(2.0);
+    let mut runner = StubRunner { scanned: 0 };
+
+    let (code, lines, changes) = run(&mut tasklist, &mut runner);
+
+    assert_eq!(code, 0);
+    assert_eq!(runner.scanned, 3);
+    assert_eq!(changes, 4);
+    assert!(lines.contains(&String::from("[0] image 2/2 (1 V, 1e-10 A): 16 points")));
+    assert_eq!(lines.last().unwrap(), "2 tasks: 2 completed, 0 failed");
+    assert!(tasklist.tasks.iter().all(|task| !task.is_idle()));
+}
+
+#[test]
+fn completed_run_keeps_the_data_and_exports_it() {
+    let dir = std::env::temp_dir().join("stm_controller_headless_export");
+    std::fs::create_dir_all(&dir).unwrap();
+    let mut tasklist = two_task_queue(2.0);
+    let mut runner = StubRunner { scanned: 0 };
+    let mut exporter = FileExporter::default();
+    let mut written = vec![];
+
+    let result = headless::run(&mut tasklist, &mut runner, &mut Vec::new(), |task| {
+        if matches!(task.status(), TaskState::Completed) {
+            written.extend(export_task(&mut exporter, task, &dir).unwrap());
+        }
+    });
+
+    assert!(result.is_ok());
+    for image in tasklist.tasks.iter().flat_map(|task| task.content()) {
+        assert_eq!(image.data(), Some(&[0.0; 16][..]));
+        assert!(image.captured_at().is_some());
+    }
+    assert_eq!(
+        written,
+        ["survey_0.png", "survey_1.png", "detail_0.png"].map(|name| dir.join(name))
+    );
+    for path in &written {
+        assert!(std::fs::metadata(path).unwrap().len() > 0);
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn failed_task_is_summarized_and_exits_nonzero() {
+    let mut tasklist = two_task_queue(10.0);
+    let mut runner = StubRunner { scanned: 0 };
+
+    let (code, lines, _) = run(&mut tasklist, &mut runner);
+
+    assert_ne!(code, 0);
+    assert_eq!(runner.scanned, 2);
+    assert!(lines.contains(&String::from(
+        "[1] detail: Failed(\"Bias 10 V out of range\")"
+    )));
+    assert_eq!(lines.last().unwrap(), "2 tasks: 1 completed, 1 failed");
+    assert!(tasklist.tasks[1].is_failed());
+}
+
+#[test]
+fn unwritable_output_fails_the_run() {
+    struct Closed;
+
+    impl std::io::Write for Closed {
+        fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::ErrorKind::BrokenPipe.into())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let mut tasklist = two_task_queue(2.0);
+    let mut runner = StubRunner { scanned: 0 };
+
+    let result = headless::run(&mut tasklist, &mut runner, &mut Closed, |_| {});
+
+    assert!(result.is_err());
+    assert_ne!(headless::exit_code(&result), 0);
+    assert_eq!(runner.scanned, 3);
+}